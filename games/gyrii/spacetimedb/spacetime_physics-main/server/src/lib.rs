@@ -1,4 +1,4 @@
-use log::debug;
+use log::{debug, warn};
 use spacetime_physics::{
     math::{Quat, Vec3},
     physics_raycasts,
@@ -37,7 +37,7 @@ pub fn init(ctx: &ReducerContext) {
         .ticks_per_second(60.0) // The reducer responsible for stepping the physics world will be scheduled at 60Hz, see TickWorld bellow
         .gravity(Vec3::new(0.0, -9.81, 0.0)) // The default gravity is set to Earth's gravity, this
         // is the default value, but you can change it to whatever you want.
-        .sub_step(0)
+        .sub_step(1)
         .debug_time(true)
         .build()
         .insert(ctx);
@@ -48,46 +48,52 @@ pub fn init(ctx: &ReducerContext) {
     let trigger_collider = Collider::cuboid(world.id, Vec3::new(1.0, 1.0, 1.0))
         .insert(ctx)
         .id;
-    for _ in 0..2000 {
-        RigidBody::builder()
-            .position(Vec3::new(
-                ctx.rng().gen_range(range.clone()),
+    RigidBody::insert_many(
+        ctx,
+        (0..2000).map(|_| {
+            RigidBody::builder()
+                .position(Vec3::new(
+                    ctx.rng().gen_range(range.clone()),
+                    100.0,
+                    ctx.rng().gen_range(range.clone()),
+                ))
+                .collider_id(sphere_collider)
+                .properties_id(sphere_properties)
+                .body_type(RigidBodyType::Dynamic)
+                .build()
+        }),
+    );
+
+    Trigger::insert_many(
+        ctx,
+        (0..15000).map(|_| {
+            Trigger::builder()
+                .position(Vec3::new(
+                    ctx.rng().gen_range(range.clone()),
+                    100.0,
+                    ctx.rng().gen_range(range.clone()),
+                ))
+                .collider_id(trigger_collider)
+                .build()
+        }),
+    );
+
+    RayCast::insert_many(
+        ctx,
+        (0..5000).map(|_| {
+            RayCast::new(
+                world.id,
+                Vec3::new(
+                    ctx.rng().gen_range(range.clone()),
+                    100.0,
+                    ctx.rng().gen_range(range.clone()),
+                ),
+                Vec3::Z,
                 100.0,
-                ctx.rng().gen_range(range.clone()),
-            ))
-            .collider_id(sphere_collider)
-            .properties_id(sphere_properties)
-            .body_type(RigidBodyType::Dynamic)
-            .build()
-            .insert(ctx);
-    }
-
-    for _ in 0..15000 {
-        Trigger::builder()
-            .position(Vec3::new(
-                ctx.rng().gen_range(range.clone()),
-                100.0,
-                ctx.rng().gen_range(range.clone()),
-            ))
-            .collider_id(trigger_collider)
-            .build()
-            .insert(ctx);
-    }
-
-    for _ in 0..5000 {
-        RayCast::new(
-            world.id,
-            Vec3::new(
-                ctx.rng().gen_range(range.clone()),
-                100.0,
-                ctx.rng().gen_range(range.clone()),
-            ),
-            Vec3::Z,
-            100.0,
-            false,
-        )
-        .insert(ctx);
-    }
+                false,
+            )
+        }),
+    );
 
     // Create a small sphere that will fal towards the ground
     // RigidBody::builder()
@@ -168,7 +174,9 @@ pub fn physics_tick_world(ctx: &ReducerContext, tick: PhysicsWorldTick) {
         .map(|c| (c.rigid_body_id, (c.position, c.rotation)));
 
     // Update the physics world and synchorinze the kinematic entities positions and rotations
-    step_world(ctx, &world, kinematic_entities);
+    if let Err(err) = step_world(ctx, &world, kinematic_entities) {
+        warn!("physics_tick_world: {}", err);
+    }
 }
 
 #[reducer]
@@ -10,9 +10,12 @@ pub fn test_collision(
     let iso_a = body_a.into();
     let iso_b = body_b.into();
 
+    // Margins widen contact generation and are treated as part of the shapes,
+    // so the reported distance is negative as soon as the margins overlap.
+    let margin = body_a.margin() + body_b.margin();
     let result = body_a
         .shape()
-        .contact(&iso_a, body_b.shape(), &iso_b, prediction);
+        .contact(&iso_a, body_b.shape(), &iso_b, prediction + margin);
 
     result.map(|contact| {
         let world_a = contact.point1.into();
@@ -31,7 +34,34 @@ pub fn test_collision(
             local_a,
             local_b,
             normal: contact.normal1.into(),
-            distance: contact.dist,
+            distance: contact.dist - margin,
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::test_collision;
+    use crate::{
+        engine::test_utils::dynamic_body, math::Vec3, Collider, RigidBodyData,
+        RigidBodyProperties,
+    };
+
+    fn sphere(id: u64, x: f32, margin: f32) -> RigidBodyData {
+        let collider = Collider::sphere(1, 0.5).with_margin(margin);
+        let properties = RigidBodyProperties::builder().build();
+        RigidBodyData::new(dynamic_body(id, Vec3::new(x, 0.0, 0.0)), &properties, &collider)
+    }
+
+    #[test]
+    fn margin_generates_contacts_at_a_greater_separation() {
+        // 0.2 apart, beyond the prediction distance
+        let (a, b) = (sphere(1, 0.0, 0.0), sphere(2, 1.2, 0.0));
+        assert!(test_collision(&a, &b, 0.01).is_none());
+
+        let padded = sphere(2, 1.2, 0.3);
+        let contact = test_collision(&a, &padded, 0.01).expect("margin should generate a contact");
+        // The margin counts as part of the shape
+        assert!((contact.distance + 0.1).abs() < 1e-4, "distance {}", contact.distance);
+    }
+}
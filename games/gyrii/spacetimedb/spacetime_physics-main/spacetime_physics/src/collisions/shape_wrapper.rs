@@ -1,8 +1,8 @@
-use crate::{tables::Collider, ColliderType};
+use crate::{math::Vec3, tables::Collider, ColliderType};
 use parry3d::{
     bounding_volume::{Aabb, BoundingVolume},
     na::Isometry3,
-    query::{contact, intersection_test, Contact, Ray, RayCast, RayIntersection},
+    query::{contact, intersection_test, Contact, PointQuery, Ray, RayCast, RayIntersection},
     shape::{Ball, Capsule, Cone, Cuboid, Cylinder, HalfSpace, Shape, Triangle},
 };
 
@@ -79,6 +79,16 @@ impl ShapeWrapper {
         }
     }
 
+    /// The point of the shape closest to `point`, in world space.
+    /// If `solid` is true a point inside the shape is returned as is, otherwise it is projected
+    /// on the surface.
+    pub fn project_point(&self, isometry: &Isometry3<f32>, point: Vec3, solid: bool) -> Vec3 {
+        self.as_parry_shape()
+            .project_point(isometry, &point.into(), solid)
+            .point
+            .into()
+    }
+
     pub fn contact(
         &self,
         isometry_a: &Isometry3<f32>,
@@ -0,0 +1,272 @@
+use std::collections::{HashMap, HashSet};
+
+use parry3d::{
+    bounding_volume::{Aabb, BoundingVolume},
+    math::Point,
+    partitioning::Qbvh,
+    query::visitors::BoundingVolumeIntersectionsSimultaneousVisitor,
+};
+
+use super::collision_detection::Collidable;
+
+/// Above this number of cells, a collidable is considered too large for the grid
+/// and is tested against every other collidable instead (e.g. planes).
+const MAX_CELLS_PER_COLLIDABLE: i64 = 64;
+
+/// Orders a pair by collidable index, so the same pair is always stored the same way.
+fn ordered_pair(a: &Collidable, b: &Collidable) -> (Collidable, Collidable) {
+    if a.collidable_index < b.collidable_index {
+        (*a, *b)
+    } else {
+        (*b, *a)
+    }
+}
+
+/// Finds the overlapping pairs of the collidables stored in `qbvh`, by traversing it against
+/// itself. `stack` is reused between calls to avoid allocating it every step.
+pub(crate) fn qbvh_pairs(
+    qbvh: &Qbvh<Collidable>,
+    stack: &mut Vec<(u32, u32)>,
+) -> HashSet<(Collidable, Collidable)> {
+    let mut pairs = HashSet::new();
+    let mut visitor =
+        BoundingVolumeIntersectionsSimultaneousVisitor::new(|a: &Collidable, b: &Collidable| {
+            if a != b {
+                pairs.insert(ordered_pair(a, b));
+            }
+            true
+        });
+    qbvh.traverse_bvtt_with_stack(qbvh, &mut visitor, stack);
+    pairs
+}
+
+/// Finds the overlapping pairs by bucketing the AABBs in a uniform grid.
+/// Cheap for evenly distributed content of a size close to `cell_size`.
+pub(crate) fn grid_pairs(
+    collidables: &[(Collidable, Aabb)],
+    cell_size: f32,
+) -> HashSet<(Collidable, Collidable)> {
+    let mut cells: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+    let mut oversized = Vec::new();
+    let cell_size = cell_size.max(f32::EPSILON);
+
+    for (i, (_, aabb)) in collidables.iter().enumerate() {
+        let min = cell_coords(&aabb.mins, cell_size);
+        let max = cell_coords(&aabb.maxs, cell_size);
+
+        let span = |min: i64, max: i64| max.saturating_sub(min).saturating_add(1);
+        let cell_count = span(min.0, max.0)
+            .saturating_mul(span(min.1, max.1))
+            .saturating_mul(span(min.2, max.2));
+        if cell_count > MAX_CELLS_PER_COLLIDABLE {
+            oversized.push(i);
+            continue;
+        }
+
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    cells.entry((x, y, z)).or_default().push(i);
+                }
+            }
+        }
+    }
+
+    let mut pairs = HashSet::new();
+    for indices in cells.values() {
+        for (n, &i) in indices.iter().enumerate() {
+            for &j in &indices[n + 1..] {
+                let (a, aabb_a) = &collidables[i];
+                let (b, aabb_b) = &collidables[j];
+                if aabb_a.intersects(aabb_b) {
+                    pairs.insert(ordered_pair(a, b));
+                }
+            }
+        }
+    }
+
+    for &i in &oversized {
+        let (a, aabb_a) = &collidables[i];
+        for (j, (b, aabb_b)) in collidables.iter().enumerate() {
+            if i != j && aabb_a.intersects(aabb_b) {
+                pairs.insert(ordered_pair(a, b));
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Finds the overlapping pairs by sorting the AABBs along the X axis and sweeping over them.
+pub(crate) fn sweep_and_prune_pairs(
+    collidables: &[(Collidable, Aabb)],
+) -> HashSet<(Collidable, Collidable)> {
+    let mut order: Vec<usize> = (0..collidables.len()).collect();
+    order.sort_by(|&a, &b| collidables[a].1.mins.x.total_cmp(&collidables[b].1.mins.x));
+
+    let mut pairs = HashSet::new();
+    let mut active: Vec<usize> = Vec::new();
+
+    for &i in &order {
+        let (a, aabb_a) = &collidables[i];
+
+        // Drop the AABBs that end before this one starts, they can't overlap anything anymore
+        active.retain(|&j| collidables[j].1.maxs.x >= aabb_a.mins.x);
+
+        for &j in &active {
+            let (b, aabb_b) = &collidables[j];
+            if aabb_a.intersects(aabb_b) {
+                pairs.insert(ordered_pair(a, b));
+            }
+        }
+
+        active.push(i);
+    }
+
+    pairs
+}
+
+fn cell_coords(point: &Point<f32>, cell_size: f32) -> (i64, i64, i64) {
+    // Float to int casts saturate, so huge AABBs end up oversized instead of overflowing
+    let cell = |v: f32| (v / cell_size).floor() as i64;
+    (cell(point.x), cell(point.y), cell(point.z))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use parry3d::{
+        bounding_volume::{Aabb, BoundingVolume},
+        math::Point,
+        partitioning::{IndexedData, Qbvh},
+    };
+
+    use super::{grid_pairs, ordered_pair, qbvh_pairs, sweep_and_prune_pairs};
+    use crate::{engine::collision_detection::Collidable, PhysicsWorld};
+
+    /// Scattered boxes of various sizes, plus a huge floor that overflows the grid.
+    fn scattered_collidables() -> Vec<(Collidable, Aabb)> {
+        // Small LCG so the layout is the same on every run
+        let mut seed = 12345u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 8) as f32 / (1 << 24) as f32
+        };
+
+        let mut collidables: Vec<_> = (0..200)
+            .map(|_| {
+                let center = Point::new(next() * 40.0 - 20.0, next() * 10.0, next() * 40.0 - 20.0);
+                let half = 0.2 + next() * 1.5;
+                Aabb::from_half_extents(center, [half, half * 0.5, half].into())
+            })
+            .collect();
+        collidables.push(Aabb::new(
+            Point::new(-100.0, -1.0, -100.0),
+            Point::new(100.0, 0.0, 100.0),
+        ));
+
+        collidables
+            .into_iter()
+            .enumerate()
+            .map(|(i, aabb)| {
+                let collidable = Collidable {
+                    id: i as u64 + 1,
+                    rigidbody_index: i,
+                    collidable_index: i,
+                    ..<Collidable as IndexedData>::default()
+                };
+                (collidable, aabb)
+            })
+            .collect()
+    }
+
+    /// The layout of the sample server: 2000 spheres of radius 1 and 15000 unit cube triggers,
+    /// spread over a 10 km square at the same height.
+    fn sample_collidables() -> Vec<(Collidable, Aabb)> {
+        let mut seed = 12345u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 8) as f32 / (1 << 24) as f32 * 10000.0
+        };
+
+        (0..17000)
+            .map(|i| {
+                let center = Point::new(next(), 100.0, next());
+                let collidable = Collidable {
+                    id: i as u64 + 1,
+                    rigidbody_index: i.min(1999),
+                    trigger_index: i.saturating_sub(2000),
+                    is_trigger: i >= 2000,
+                    collidable_index: i,
+                    ..<Collidable as IndexedData>::default()
+                };
+                (collidable, Aabb::from_half_extents(center, [1.0; 3].into()))
+            })
+            .collect()
+    }
+
+    fn brute_force_pairs(collidables: &[(Collidable, Aabb)]) -> HashSet<(Collidable, Collidable)> {
+        let mut pairs = HashSet::new();
+        for (i, (a, aabb_a)) in collidables.iter().enumerate() {
+            for (b, aabb_b) in &collidables[i + 1..] {
+                if aabb_a.intersects(aabb_b) {
+                    pairs.insert(ordered_pair(a, b));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn strategies_find_the_same_pairs_as_brute_force() {
+        let collidables = scattered_collidables();
+        let expected = brute_force_pairs(&collidables);
+        assert!(expected.len() > 20, "the layout should have plenty of overlaps");
+
+        assert_eq!(sweep_and_prune_pairs(&collidables), expected);
+        for cell_size in [0.5, 2.0, 8.0] {
+            assert_eq!(grid_pairs(&collidables, cell_size), expected, "cell size {}", cell_size);
+        }
+    }
+
+    /// Runs `find_pairs` `runs` times, prints the average time and returns the pair count.
+    fn time(name: &str, runs: u32, mut find_pairs: impl FnMut() -> usize) -> usize {
+        let start = std::time::Instant::now();
+        let mut pairs = 0;
+        for _ in 0..runs {
+            pairs = find_pairs();
+        }
+        println!(
+            "{name:>15}: {:?} per step, {pairs} pairs",
+            start.elapsed() / runs
+        );
+        pairs
+    }
+
+    /// Times each strategy on the sample's layout, including the QBVH rebuild it needs every
+    /// step. Run with `cargo test --release broad_phase_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn broad_phase_benchmark() {
+        let collidables = sample_collidables();
+        let world = PhysicsWorld::builder().build();
+
+        let mut qbvh = Qbvh::new();
+        let mut stack = Vec::new();
+        let qbvh_count = time("Qbvh", 20, || {
+            qbvh.clear_and_rebuild(collidables.iter().copied(), world.qvbh_dilation_factor);
+            qbvh_pairs(&qbvh, &mut stack).len()
+        });
+        let grid_count = time("Grid", 20, || {
+            grid_pairs(&collidables, world.broad_phase_cell_size).len()
+        });
+        let sap_count = time("SweepAndPrune", 20, || {
+            sweep_and_prune_pairs(&collidables).len()
+        });
+
+        assert_eq!(grid_count, sap_count);
+        // The QBVH dilates its AABBs, it can only find more pairs
+        assert!(qbvh_count >= grid_count);
+    }
+}
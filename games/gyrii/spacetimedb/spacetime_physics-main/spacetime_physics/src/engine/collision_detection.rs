@@ -4,19 +4,20 @@ use log::debug;
 use parry3d::{
     bounding_volume::Aabb,
     partitioning::{IndexedData, Qbvh as QbvhImpl},
-    query::{
-        visitors::{BoundingVolumeIntersectionsSimultaneousVisitor, RayIntersectionsVisitor},
-        Ray,
-    },
+    query::{visitors::RayIntersectionsVisitor, Ray},
 };
 use spacetimedb::ReducerContext;
 
 use crate::{
-    test_collision, utils::get_bodies_direct, PhysicsWorld, RayCast, RayCastHit, RaycastId,
+    test_collision, utils::get_bodies_direct, BroadPhase, PhysicsWorld, RayCast, RayCastHit,
+    RaycastId,
 };
 
 use super::{
-    constraints::PenetrationConstraint, rigid_body_data::RigidBodyData, trigger_data::TriggerData,
+    broad_phase::{grid_pairs, qbvh_pairs, sweep_and_prune_pairs},
+    constraints::PenetrationConstraint,
+    rigid_body_data::RigidBodyData,
+    trigger_data::TriggerData,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -25,6 +26,8 @@ pub struct Collidable {
     pub rigidbody_index: usize,
     pub trigger_index: usize,
     pub is_trigger: bool,
+    /// The body can't be moved by the solver (static or frozen).
+    pub is_static: bool,
     pub collidable_index: usize,
 }
 
@@ -35,6 +38,7 @@ impl IndexedData for Collidable {
             rigidbody_index: 0,
             trigger_index: 0,
             is_trigger: false,
+            is_static: false,
             collidable_index: 0,
         }
     }
@@ -81,13 +85,46 @@ impl CollisionDetection {
         let sw = world.stopwatch("broad_phase");
         let prediction_distance = world.prediction_distance();
 
-        let rebuild_sw = world.stopwatch("broad_phase_rebuild");
-        let collidables = self.collect_collidables(bodies, triggers, prediction_distance);
-        self.qbvh
-            .clear_and_rebuild(collidables.into_iter(), world.qvbh_dilation_factor);
-        rebuild_sw.end();
+        // With speculative contacts, the AABBs cover the distance travelled during the step
+        let speculative_time = if world.speculative_contacts {
+            world.time_step
+        } else {
+            0.0
+        };
+
+        let collidables =
+            self.collect_collidables(bodies, triggers, prediction_distance, speculative_time);
+
+        match world.broad_phase {
+            BroadPhase::Qbvh => {}
+            BroadPhase::Grid => {
+                let grid_sw = world.stopwatch("broad_phase_grid");
+                self.pairs = grid_pairs(&collidables, world.broad_phase_cell_size);
+                grid_sw.end();
+            }
+            BroadPhase::SweepAndPrune => {
+                let sap_sw = world.stopwatch("broad_phase_sweep_and_prune");
+                self.pairs = sweep_and_prune_pairs(&collidables);
+                sap_sw.end();
+            }
+        }
+
+        // Raycasts are always resolved against the QBVH
+        if world.broad_phase == BroadPhase::Qbvh || !raycasts.is_empty() {
+            let rebuild_sw = world.stopwatch("broad_phase_rebuild");
+            self.qbvh
+                .clear_and_rebuild(collidables.into_iter(), world.qvbh_dilation_factor);
+            rebuild_sw.end();
+        }
+
+        if world.broad_phase == BroadPhase::Qbvh {
+            self.run_broad_phase_pairs(world);
+        }
 
-        self.run_broad_phase_pairs(world);
+        if world.skip_static_pairs {
+            // Two bodies that can't move can't generate a new contact response
+            self.pairs.retain(|(a, b)| !(a.is_static && b.is_static));
+        }
         self.run_broad_phase_raycast_pairs(world, raycasts);
 
         sw.end();
@@ -100,6 +137,7 @@ impl CollisionDetection {
     ) -> Vec<PenetrationConstraint> {
         let sw = world.stopwatch("narrow_phase");
         let mut constraints = Vec::new();
+        let sub_dt = world.sub_time_step();
 
         for (a, b) in &self.pairs {
             if a.is_trigger || b.is_trigger {
@@ -108,15 +146,38 @@ impl CollisionDetection {
 
             let (body_a, body_b) = get_bodies_direct(a.rigidbody_index, b.rigidbody_index, bodies);
 
-            if let Some(collision) = test_collision(body_a, body_b, world.precision) {
+            let speculative = world.speculative_contacts && (a.is_static != b.is_static);
+            let relative_velocity = body_b.linear_velocity() - body_a.linear_velocity();
+            let prediction = if speculative {
+                world.contact_precision() + relative_velocity.length() * sub_dt
+            } else {
+                world.contact_precision()
+            };
+
+            if let Some(mut collision) = test_collision(body_a, body_b, prediction) {
+                if speculative && collision.distance >= 0.0 {
+                    // The penetration there will be once the bodies moved for this substep,
+                    // the normal points from A towards B so approaching bodies close the gap
+                    collision.distance += relative_velocity.dot(collision.normal) * sub_dt;
+                }
+
                 if collision.distance >= 0.0 {
                     continue; // No penetration
                 }
 
-                constraints.push(PenetrationConstraint::new(body_a, body_b, collision, 0.0));
+                let compliance = (world.contact_compliance
+                    + body_a.combine_contact_compliance(body_b))
+                .max(0.0);
+                constraints.push(PenetrationConstraint::new(
+                    body_a, body_b, collision, compliance,
+                ));
             }
         }
 
+        // The pairs come from a HashSet, sort them so the solve order (and the result)
+        // is the same on every run.
+        constraints.sort_unstable_by_key(|c| (c.a, c.b));
+
         if world.debug_narrow_phase() {
             debug!(
                 "[PhysicsWorld#{}] [NarrowPhase] constraints: {:?}",
@@ -136,9 +197,26 @@ impl CollisionDetection {
         triggers: &mut [TriggerData],
     ) {
         let sw = world.stopwatch("narrow_phase_triggers");
+        self.trigger_overlaps(world, bodies, triggers);
+        for trigger in triggers {
+            trigger.update(ctx);
+        }
+        sw.end();
+    }
+
+    /// Fills the entities inside each trigger, and the ones added and removed since the
+    /// previous step, from the broad phase pairs. Nothing is written to the database.
+    pub(crate) fn trigger_overlaps(
+        &self,
+        world: &PhysicsWorld,
+        bodies: &[RigidBodyData],
+        triggers: &mut [TriggerData],
+    ) {
         for (a, b) in &self.pairs {
-            if !a.is_trigger && !b.is_trigger {
-                continue; // Skip non-trigger pairs
+            // Only trigger / body pairs matter here. In a trigger / trigger pair neither side
+            // is a body, their `rigidbody_index` is a placeholder that must not index `bodies`.
+            if a.is_trigger == b.is_trigger {
+                continue;
             }
 
             let (trigger, body) = if a.is_trigger {
@@ -177,10 +255,7 @@ impl CollisionDetection {
                     world.id, trigger.trigger_id, trigger.current_entities_inside, trigger.added_entities, trigger.removed_entities
                 );
             }
-
-            trigger.update(ctx);
         }
-        sw.end();
     }
 
     pub fn narrow_phase_raycast(
@@ -193,7 +268,8 @@ impl CollisionDetection {
         let sw = world.stopwatch("narrow_phase_raycast");
         for (raycast_id, broad_hits) in self.raycasts_pairs.iter() {
             let raycast = &mut raycasts[raycast_id.raycast_index];
-            let ray = Ray::new(raycast.origin.into(), raycast.direction.into());
+            // Rays built without `RayCast::new` may not be normalized, which would scale `max_distance`
+            let ray = Ray::new(raycast.origin.into(), raycast.direction.normalize().into());
             let mut hits = HashSet::new();
 
             for broad_hit in broad_hits {
@@ -232,31 +308,15 @@ impl CollisionDetection {
 
     fn run_broad_phase_pairs(&mut self, world: &PhysicsWorld) {
         let traverse_sw = world.stopwatch("broad_phase_traverse");
-        let mut pairs = HashSet::new();
-        let mut visitor = BoundingVolumeIntersectionsSimultaneousVisitor::new(
-            |a: &Collidable, b: &Collidable| {
-                if a != b {
-                    let (min_id, max_id) = if a.collidable_index < b.collidable_index {
-                        (a, b)
-                    } else {
-                        (b, a)
-                    };
-                    pairs.insert((*min_id, *max_id));
-                }
-                true
-            },
-        );
-        self.qbvh
-            .traverse_bvtt_with_stack(&self.qbvh, &mut visitor, &mut self.stack);
+        self.pairs = qbvh_pairs(&self.qbvh, &mut self.stack);
         traverse_sw.end();
-
-        self.pairs = pairs;
     }
 
     fn run_broad_phase_raycast_pairs(&mut self, world: &PhysicsWorld, raycasts: &[RayCast]) {
         let traverse_sw = world.stopwatch("broad_phase_raycast_traverse");
         for (i, raycast) in raycasts.iter().enumerate() {
-            let ray = Ray::new(raycast.origin.into(), raycast.direction.into());
+            // Rays built without `RayCast::new` may not be normalized, which would scale `max_distance`
+            let ray = Ray::new(raycast.origin.into(), raycast.direction.normalize().into());
             let mut entities = HashSet::new();
             let mut callback = |collidable: &Collidable| {
                 if !collidable.is_trigger {
@@ -287,6 +347,7 @@ impl CollisionDetection {
         bodies: &[RigidBodyData],
         triggers: &[TriggerData],
         prediction_distance: f32,
+        speculative_time: f32,
     ) -> Vec<(Collidable, Aabb)> {
         let mut collidables: Vec<(Collidable, Aabb)> =
             Vec::with_capacity(bodies.len() + triggers.len());
@@ -297,11 +358,15 @@ impl CollisionDetection {
                     rigidbody_index: i,
                     trigger_index: 0,
                     is_trigger: false,
+                    is_static: !entity.is_dynamic() && !entity.is_kinematic(),
                     collidable_index: i,
                 },
-                entity
-                    .shape()
-                    .collision_aabb(&entity.into(), prediction_distance),
+                entity.shape().collision_aabb(
+                    &entity.into(),
+                    prediction_distance
+                        + entity.margin()
+                        + entity.linear_velocity().length() * speculative_time,
+                ),
             ));
         }
         let entities_count = bodies.len();
@@ -312,6 +377,7 @@ impl CollisionDetection {
                     rigidbody_index: 0,
                     trigger_index: i,
                     is_trigger: true,
+                    is_static: false,
                     collidable_index: i + entities_count,
                 },
                 trigger
@@ -323,3 +389,168 @@ impl CollisionDetection {
         collidables
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::CollisionDetection;
+    use crate::{
+        engine::{test_utils::*, trigger_data::TriggerData},
+        math::Vec3,
+        Collider, PhysicsScene, PhysicsWorld, RigidBody, RigidBodyData, RigidBodyProperties,
+        RigidBodyType, Trigger,
+    };
+
+    /// Three slightly offset boxes stacked on a floor.
+    fn stacked_scene() -> PhysicsScene {
+        let mut scene = scene_with_floor(1);
+        let properties = RigidBodyProperties::builder().build();
+        let collider = Collider::cuboid(1, Vec3::new(1.0, 1.0, 1.0));
+        for (id, y) in [(2, 0.5), (3, 1.5), (4, 2.5)] {
+            let offset = 0.1 * (id as f32 - 3.0);
+            let position = Vec3::new(offset, y, -offset);
+            assert!(scene.add_body(dynamic_body(id, position), &properties, &collider));
+        }
+        scene
+    }
+
+    #[test]
+    fn repeated_runs_of_a_stack_are_identical() {
+        let runs: Vec<Vec<_>> = (0..3)
+            .map(|_| {
+                let mut scene = stacked_scene();
+                scene.step_n(60);
+                scene
+                    .bodies()
+                    .iter()
+                    .map(|body| (body.position(), body.rotation()))
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(runs[0], runs[1]);
+        assert_eq!(runs[0], runs[2]);
+    }
+
+    #[test]
+    fn overlapping_triggers_only_report_bodies() {
+        let world = PhysicsWorld::builder().build();
+        let properties = RigidBodyProperties::builder().build();
+        let mut floor = dynamic_body(1, Vec3::new(0.0, -0.5, 0.0));
+        floor.body_type = RigidBodyType::Static;
+        let bodies = vec![
+            RigidBodyData::new(
+                floor,
+                &properties,
+                &Collider::cuboid(1, Vec3::new(10.0, 1.0, 10.0)),
+            ),
+            RigidBodyData::new(
+                dynamic_body(2, Vec3::new(0.0, 0.5, 0.0)),
+                &properties,
+                &Collider::sphere(1, 0.5),
+            ),
+        ];
+
+        // The zone covers the floor and the ball, the beacon only overlaps the zone
+        let trigger = |id, y, radius| {
+            let trigger = Trigger::builder()
+                .id(id)
+                .position(Vec3::new(0.0, y, 0.0))
+                .collider_id(0)
+                .build();
+            TriggerData::new(&trigger, &Collider::sphere(1, radius))
+        };
+        let mut triggers = vec![trigger(10, 0.0, 2.0), trigger(11, 3.0, 1.5)];
+
+        let mut collision_detection = CollisionDetection::new();
+        collision_detection.broad_phase(&world, &bodies, &triggers, &[]);
+        collision_detection.trigger_overlaps(&world, &bodies, &mut triggers);
+
+        assert_eq!(triggers[0].current_entities_inside, HashSet::from([1, 2]));
+        assert!(triggers[1].current_entities_inside.is_empty());
+    }
+
+    #[test]
+    fn static_static_pairs_produce_no_constraints() {
+        let properties = RigidBodyProperties::builder().build();
+        let wall = |id, x| {
+            let mut body = dynamic_body(id, Vec3::new(x, 0.0, 0.0));
+            body.body_type = RigidBodyType::Static;
+            RigidBodyData::new(
+                body,
+                &properties,
+                &Collider::cuboid(1, Vec3::new(2.0, 2.0, 2.0)),
+            )
+        };
+        // The walls overlap each other, the sphere is sunk into the second one
+        let mut bodies = vec![
+            wall(1, 0.0),
+            wall(2, 1.5),
+            RigidBodyData::new(
+                dynamic_body(3, Vec3::new(2.9, 0.0, 0.0)),
+                &properties,
+                &Collider::sphere(1, 0.5),
+            ),
+        ];
+
+        for skip_static_pairs in [true, false] {
+            let world = PhysicsWorld::builder()
+                .skip_static_pairs(skip_static_pairs)
+                .build();
+            let mut collision_detection = CollisionDetection::new();
+            collision_detection.broad_phase(&world, &bodies, &[], &[]);
+            let broad_phase_pairs: HashSet<_> = collision_detection
+                .broad_phase_pairs()
+                .iter()
+                .map(|(a, b)| (a.id.min(b.id), a.id.max(b.id)))
+                .collect();
+            assert_eq!(broad_phase_pairs.contains(&(1, 2)), !skip_static_pairs);
+
+            let pairs: HashSet<_> = collision_detection
+                .narrow_phase_constraints(&world, &mut bodies)
+                .iter()
+                .map(|c| (c.a.min(c.b), c.a.max(c.b)))
+                .collect();
+            assert!(pairs.contains(&(2, 3)));
+            if skip_static_pairs {
+                assert_eq!(pairs, HashSet::from([(2, 3)]));
+            }
+        }
+    }
+
+    #[test]
+    fn speculative_contacts_stop_a_ball_before_a_thin_wall() {
+        // A 60 m/s ball moves 1 m per step, the wall is 10 cm thick
+        let final_x = |speculative_contacts: bool| {
+            let world = PhysicsWorld::builder()
+                .gravity(Vec3::ZERO)
+                .speculative_contacts(speculative_contacts)
+                .build();
+            let mut scene = PhysicsScene::new(world);
+            let wall = RigidBody::builder()
+                .id(1)
+                .body_type(RigidBodyType::Static)
+                .collider_id(0)
+                .properties_id(0)
+                .build();
+            let properties = RigidBodyProperties::builder().build();
+            let wall_collider = Collider::cuboid(1, Vec3::new(0.1, 4.0, 4.0));
+            scene.add_body(wall, &properties, &wall_collider);
+
+            let mut ball = dynamic_body(2, Vec3::new(-1.5, 0.0, 0.0));
+            ball.linear_velocity = Vec3::new(60.0, 0.0, 0.0);
+            scene.add_body(ball, &properties, &Collider::sphere(1, 0.2));
+
+            scene.step_n(10);
+            scene.body(2).unwrap().position().x
+        };
+
+        assert!(
+            final_x(false) > 0.5,
+            "the ball should tunnel without speculation"
+        );
+        let stopped = final_x(true);
+        assert!(stopped < -0.2, "ball at x = {}", stopped);
+    }
+}
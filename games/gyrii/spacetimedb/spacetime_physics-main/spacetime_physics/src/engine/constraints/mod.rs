@@ -25,7 +25,7 @@ pub(crate) trait Constraint {
             .enumerate()
             .fold(0.0, |acc, (i, w)| acc + w * gradients[i].length_squared());
 
-        if w_sum == f32::EPSILON {
+        if w_sum <= f32::EPSILON {
             return 0.0;
         }
 
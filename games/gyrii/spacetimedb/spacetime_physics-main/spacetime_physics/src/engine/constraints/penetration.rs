@@ -75,8 +75,16 @@ impl PenetrationConstraint {
         let gradients = [normal, -normal];
         let w = [wa, wb];
 
-        let delta_lagrange =
+        let mut delta_lagrange =
             self.compute_lagrange_update(lagrange, penetraion, &gradients, &w, compliance, dt);
+
+        // The bodies separate by delta_lagrange * (wa + wb), limit it so deep overlaps resolve
+        // over several substeps instead of launching the bodies apart
+        let max_correction = world.max_correction();
+        if max_correction > 0.0 && wa + wb > 0.0 {
+            let max_delta = max_correction / (wa + wb);
+            delta_lagrange = delta_lagrange.clamp(-max_delta, max_delta);
+        }
         self.normal_lagrange += delta_lagrange;
         self.normal_force = self.normal_lagrange * normal / dt.powi(2);
 
@@ -1,51 +1,290 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use collision_detection::CollisionDetection;
-use log::debug;
+use constraints::PenetrationConstraint;
+use log::{debug, warn};
 use spacetimedb::ReducerContext;
+use substeps::SubstepRecorder;
 use trigger_data::TriggerData;
-use xpbd::{integrate_bodies, recompute_velocities, solve_constraints, solve_velocities};
+use xpbd::{
+    apply_fluid_forces, apply_rolling_resistance, integrate_bodies, recompute_velocities,
+    solve_constraints, solve_velocities,
+};
 
 use crate::{
     math::{Quat, Vec3},
     tables::PhysicsWorld,
-    Collider, RayCast,
+    Collider, ColliderId, CollisionEvent, FluidVolume, PhysicsWorldId, RayCast, RigidBodyId,
 };
 
+/// Distance in meters a contact point can move between substeps and still be warm started.
+const WARM_START_TOLERANCE: f32 = 0.01;
+
+mod broad_phase;
 mod collision_detection;
 mod constraints;
 mod rigid_body_data;
+mod scene;
+mod substeps;
 mod trigger_data;
 mod xpbd;
 
 pub use rigid_body_data::RigidBodyData;
+pub use scene::PhysicsScene;
+#[cfg(test)]
+pub(crate) use scene::test_utils;
+pub(crate) use xpbd::clamp_restitution;
 
 pub type KinematicBody = (u64, (Vec3, Quat));
 
+/// What happened during a step, returned by `step_world`.
+#[derive(Debug, Clone, Default)]
+pub struct StepReport {
+    /// The pairs of bodies that touched during the step, ordered by id.
+    pub contact_pairs: HashSet<(RigidBodyId, RigidBodyId)>,
+    /// The position of each moving body before the step and after every substep.
+    /// Empty unless `record_substep_positions` is set.
+    pub substep_positions: HashMap<RigidBodyId, Vec<Vec3>>,
+    /// Bodies left out of the step, they keep their stored state.
+    pub skipped_bodies: Vec<PhysicsError>,
+}
+
+impl StepReport {
+    /// The positions of a body during the step, starting with its position before the step.
+    /// Lets games detect hits that happened between two steps.
+    pub fn positions_of(&self, body_id: RigidBodyId) -> &[Vec3] {
+        self.substep_positions
+            .get(&body_id)
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Why a world couldn't be stepped, or why a body was left out of a step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhysicsError {
+    /// The world row no longer exists, it was deleted before the step.
+    WorldNotFound(PhysicsWorldId),
+    /// The world gravity is NaN or infinite.
+    NonFiniteGravity(PhysicsWorldId),
+    /// The broad phase found more pairs than `max_broad_phase_pairs` and
+    /// `abort_on_broad_phase_overflow` is set.
+    BroadPhaseOverflow {
+        world_id: PhysicsWorldId,
+        pairs: usize,
+    },
+    /// A body references a collider or properties that aren't in its world.
+    MissingReference {
+        body_id: RigidBodyId,
+        collider_id: ColliderId,
+        properties_id: u64,
+    },
+    /// A body's position, rotation or velocity is NaN or infinite.
+    NonFiniteState(RigidBodyId),
+}
+
+impl std::fmt::Display for PhysicsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WorldNotFound(id) => write!(f, "PhysicsWorld {} not found", id),
+            Self::NonFiniteGravity(id) => write!(f, "PhysicsWorld {} has a non-finite gravity", id),
+            Self::BroadPhaseOverflow { world_id, pairs } => write!(
+                f,
+                "PhysicsWorld {} found {} broad phase pairs, above its limit",
+                world_id, pairs
+            ),
+            Self::MissingReference {
+                body_id,
+                collider_id,
+                properties_id,
+            } => write!(
+                f,
+                "RigidBody {} references collider {} or properties {} outside of its world",
+                body_id, collider_id, properties_id
+            ),
+            Self::NonFiniteState(id) => write!(
+                f,
+                "RigidBody {} has a non-finite position, rotation or velocity",
+                id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PhysicsError {}
+
+/// A force and torque applied to a body for one step only: body id, force, torque.
+pub type ExternalForce = (u64, Vec3, Vec3);
+
+/// A contact between two rigid bodies, as seen by the solver.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactPair {
+    pub body_a: RigidBodyId,
+    pub body_b: RigidBodyId,
+    /// The contact point on body A, in world space.
+    pub point_a: Vec3,
+    /// The contact point on body B, in world space.
+    pub point_b: Vec3,
+    /// The contact point on body A, relative to its center and rotation.
+    pub local_a: Vec3,
+    /// The contact point on body B, relative to its center and rotation.
+    pub local_b: Vec3,
+    /// The contact normal, pointing from body A towards body B. See `normal_for` to get it
+    /// from the point of view of one body.
+    pub normal: Vec3,
+    /// Negative when the bodies overlap.
+    pub penetration_depth: f32,
+}
+
+impl ContactPair {
+    /// The point halfway between both contact points, in world space.
+    pub fn contact_point(&self) -> Vec3 {
+        (self.point_a + self.point_b) * 0.5
+    }
+
+    /// The contact normal pointing out of the other body towards `body_id`, the direction that
+    /// pushes it away. For a sphere resting on a floor, `normal_for(sphere)` is +Y.
+    pub fn normal_for(&self, body_id: RigidBodyId) -> Vec3 {
+        if body_id == self.body_a {
+            -self.normal
+        } else {
+            self.normal
+        }
+    }
+}
+
+/// Steps a world and writes the result back to the tables.
+///
+/// Fails without touching the tables if the world row was deleted, its gravity isn't finite
+/// or the step was skipped because of `abort_on_broad_phase_overflow`. Bodies that can't be
+/// simulated are left out and listed in `StepReport::skipped_bodies` instead.
 pub fn step_world(
     ctx: &ReducerContext,
     world: &PhysicsWorld,
     kinematic_entities: impl Iterator<Item = KinematicBody>,
-) {
+) -> Result<StepReport, PhysicsError> {
+    step_world_with_forces(ctx, world, kinematic_entities, std::iter::empty())
+}
+
+/// Same as `step_world`, with extra forces applied to some bodies during this step only.
+///
+/// The forces act on every substep like gravity does and are never written to the tables,
+/// so gameplay forces (thrust, wind) don't need to be stored on the bodies every frame.
+/// Forces on the same body are summed, forces on bodies that aren't dynamic are ignored.
+pub fn step_world_with_forces(
+    ctx: &ReducerContext,
+    world: &PhysicsWorld,
+    kinematic_entities: impl Iterator<Item = KinematicBody>,
+    forces: impl Iterator<Item = ExternalForce>,
+) -> Result<StepReport, PhysicsError> {
+    check_world(ctx, world)?;
     let sw = world.stopwatch("step_world");
 
     let load_sw = world.stopwatch("load_data");
-    let colliders = Collider::all(ctx, world.id);
-    let mut triggers = TriggerData::collect(ctx, world.id, &colliders);
-    let mut entities = RigidBodyData::collect(ctx, world.id, &colliders);
-    let mut raycasts = RayCast::all(ctx, world.id);
+    let colliders = Collider::map_in_world(ctx, world.id);
+    let mut triggers = if world.process_triggers {
+        TriggerData::collect(ctx, world.id, &colliders)
+    } else {
+        Vec::new()
+    };
+    let (mut entities, skipped_bodies) = RigidBodyData::collect(ctx, world.id, &colliders);
+    let mut raycasts = RayCast::collect_in_world(ctx, world.id);
+    let fluids = FluidVolume::collect_in_world(ctx, world.id);
 
     let entities = entities.as_mut_slice();
     let triggers = triggers.as_mut_slice();
     let raycasts = raycasts.as_mut_slice();
     load_sw.end();
 
-    let dt = world.time_step / world.sub_step as f32;
-
     sync_kinematic_bodies(kinematic_entities, entities);
+    let external_forces = sum_external_forces(forces);
 
-    // TODO: Include triggers in the entities list
     let mut collision_detection = CollisionDetection::new();
+    let mut report = match simulate(
+        world,
+        &mut collision_detection,
+        entities,
+        triggers,
+        raycasts,
+        &fluids,
+        &external_forces,
+    ) {
+        Ok(report) => report,
+        Err(err) => {
+            sw.end();
+            return Err(err);
+        }
+    };
+    report.skipped_bodies = skipped_bodies;
+
+    CollisionEvent::sync(ctx, world.id, &report.contact_pairs);
+
+    collision_detection.narrow_phase_triggers(ctx, world, entities, triggers);
+    collision_detection.narrow_phase_raycast(ctx, world, entities, raycasts);
+
+    if world.debug {
+        debug!("---------- End of substeps ----------");
+    }
+
+    let update_sw = world.stopwatch("update_bodies");
+    for entity in entities {
+        // Resting bodies don't need to be written back, and frozen bodies keep
+        // their stored state so they thaw with the velocity they had
+        if entity.is_frozen() || !entity.is_dirty() {
+            continue;
+        }
+
+        if world.debug {
+            debug!(
+                "Updating {} position: {} -> {}, velocity: {}, rotation: {}",
+                entity.id,
+                entity.previous_position(),
+                entity.position(),
+                entity.linear_velocity(),
+                entity.rotation(),
+            );
+        }
+        entity.update(ctx);
+    }
+    update_sw.end();
+
+    if world.debug {
+        debug!("-------------------------------------------------------------");
+    }
+
+    sw.end();
+    Ok(report)
+}
+
+/// Everything that prevents a whole world from stepping.
+fn check_world(ctx: &ReducerContext, world: &PhysicsWorld) -> Result<(), PhysicsError> {
+    check_world_row(PhysicsWorld::find(ctx, world.id).is_some(), world)
+}
+
+/// Same as `check_world`, `exists` tells if the world row is still in the table.
+fn check_world_row(exists: bool, world: &PhysicsWorld) -> Result<(), PhysicsError> {
+    if !exists {
+        return Err(PhysicsError::WorldNotFound(world.id));
+    }
+    if !world.gravity.is_finite() {
+        return Err(PhysicsError::NonFiniteGravity(world.id));
+    }
+    Ok(())
+}
+
+/// Runs the broad phase and every substep on bodies already loaded in memory.
+/// Fails if the step was skipped because of `abort_on_broad_phase_overflow`.
+fn simulate(
+    world: &PhysicsWorld,
+    collision_detection: &mut CollisionDetection,
+    entities: &mut [RigidBodyData],
+    triggers: &[TriggerData],
+    raycasts: &[RayCast],
+    fluids: &[FluidVolume],
+    external_forces: &HashMap<RigidBodyId, (Vec3, Vec3)>,
+) -> Result<StepReport, PhysicsError> {
+    let dt = world.sub_time_step();
+
+    // TODO: Include triggers in the entities list
     collision_detection.broad_phase(world, entities, triggers, raycasts);
 
     if world.debug_broad_phase() {
@@ -56,7 +295,40 @@ pub fn step_world(
         );
     }
 
-    for i in 0..world.sub_step {
+    let pair_count = collision_detection.broad_phase_pairs().len();
+    if world.broad_phase_overflow(pair_count) {
+        warn!(
+            "[PhysicsWorld#{}] [BroadPhase] {} pairs found, above the limit of {}{}",
+            world.id,
+            pair_count,
+            world.max_broad_phase_pairs,
+            if world.abort_on_broad_phase_overflow {
+                ", skipping this step"
+            } else {
+                ""
+            }
+        );
+        if world.abort_on_broad_phase_overflow {
+            return Err(PhysicsError::BroadPhaseOverflow {
+                world_id: world.id,
+                pairs: pair_count,
+            });
+        }
+    }
+
+    // Contacts are regenerated every substep, accumulate them to report per-step events
+    let mut contact_pairs = HashSet::new();
+    // Normal multipliers of the previous substep, used for warm starting
+    let mut previous_contacts: HashMap<(RigidBodyId, RigidBodyId), Vec<(Vec3, f32)>> =
+        HashMap::new();
+    let mut recorder = world
+        .record_substep_positions
+        .then(SubstepRecorder::default);
+    if let Some(recorder) = &mut recorder {
+        recorder.record(entities);
+    }
+
+    for i in 0..world.sub_step_count() {
         let sw = world.stopwatch(&format!("substep_{}", i));
         if world.debug_substep() {
             debug!("---------- substep: {} ----------", i);
@@ -65,20 +337,45 @@ pub fn step_world(
         // TODO: Ignore trigger bodies in the narrow phase
         let mut penetration_constraints =
             collision_detection.narrow_phase_constraints(world, entities);
+        if world.warm_starting {
+            warm_start(world, &mut penetration_constraints, &previous_contacts);
+        }
         let penetration_constraints = penetration_constraints.as_mut_slice();
 
         if world.debug_substep() {
             debug!("Collisions detected: {:?}", penetration_constraints);
         }
 
-        integrate_bodies(entities, world, dt);
+        contact_pairs.extend(
+            penetration_constraints
+                .iter()
+                .map(|c| (c.a.min(c.b), c.a.max(c.b))),
+        );
+
+        apply_fluid_forces(entities, fluids, world);
+        integrate_bodies(entities, external_forces, world, dt);
 
-        for _ in 0..world.position_iterations {
+        for _ in 0..world.position_iteration_count() {
             solve_constraints(world, penetration_constraints, entities, dt);
         }
 
         recompute_velocities(world, entities, dt);
         solve_velocities(world, penetration_constraints, entities, dt);
+        apply_rolling_resistance(world, penetration_constraints, entities, dt);
+
+        if world.warm_starting {
+            previous_contacts.clear();
+            for constraint in penetration_constraints.iter() {
+                previous_contacts
+                    .entry((constraint.a, constraint.b))
+                    .or_default()
+                    .push((constraint.local_a, constraint.normal_lagrange));
+            }
+        }
+
+        if let Some(recorder) = &mut recorder {
+            recorder.record(entities);
+        }
 
         if world.debug {
             debug_bodies(entities);
@@ -87,34 +384,73 @@ pub fn step_world(
         sw.end();
     }
 
-    collision_detection.narrow_phase_triggers(ctx, world, entities, triggers);
-    collision_detection.narrow_phase_raycast(ctx, world, entities, raycasts);
-
-    if world.debug {
-        debug!("---------- End of substeps ----------");
-    }
+    Ok(StepReport {
+        contact_pairs,
+        substep_positions: recorder
+            .map(SubstepRecorder::into_positions)
+            .unwrap_or_default(),
+        skipped_bodies: Vec::new(),
+    })
+}
 
-    let update_sw = world.stopwatch("update_bodies");
-    for entity in entities {
-        if world.debug {
-            debug!(
-                "Updating {} position: {} -> {}, velocity: {}, rotation: {}",
-                entity.id,
-                entity.previous_position(),
-                entity.position(),
-                entity.linear_velocity(),
-                entity.rotation(),
-            );
+/// Seeds the normal multiplier of each contact with the one of the closest contact of the
+/// same pair in the previous substep, if its point on body A moved less than the tolerance.
+fn warm_start(
+    world: &PhysicsWorld,
+    constraints: &mut [PenetrationConstraint],
+    previous_contacts: &HashMap<(RigidBodyId, RigidBodyId), Vec<(Vec3, f32)>>,
+) {
+    let tolerance = WARM_START_TOLERANCE * world.length_unit;
+    for constraint in constraints {
+        let Some(previous) = previous_contacts.get(&(constraint.a, constraint.b)) else {
+            continue;
+        };
+        let closest = previous
+            .iter()
+            .map(|(local_a, lagrange)| ((*local_a - constraint.local_a).length(), *lagrange))
+            .filter(|(distance, _)| *distance < tolerance)
+            .min_by(|(d1, _), (d2, _)| d1.total_cmp(d2));
+        if let Some((_, lagrange)) = closest {
+            constraint.normal_lagrange = lagrange;
         }
-        entity.update(ctx);
     }
-    update_sw.end();
+}
 
-    if world.debug {
-        debug!("-------------------------------------------------------------");
-    }
+/// Returns every contact of the world in its current state, sorted by body ids.
+/// This runs the collision detection only, nothing is simulated or written back.
+pub fn world_contacts(ctx: &ReducerContext, world: &PhysicsWorld) -> Vec<ContactPair> {
+    let colliders = Collider::map_in_world(ctx, world.id);
+    let (mut entities, _) = RigidBodyData::collect(ctx, world.id, &colliders);
+    contacts(world, &mut entities)
+}
 
-    sw.end();
+/// The contacts between `entities`, sorted by body ids, see `world_contacts`.
+pub(crate) fn contacts(world: &PhysicsWorld, entities: &mut [RigidBodyData]) -> Vec<ContactPair> {
+    let mut collision_detection = CollisionDetection::new();
+    collision_detection.broad_phase(world, &entities, &[], &[]);
+
+    let mut contacts: Vec<_> = collision_detection
+        .narrow_phase_constraints(world, entities)
+        .into_iter()
+        .map(|constraint| ContactPair {
+            body_a: constraint.a,
+            body_b: constraint.b,
+            point_a: constraint.world_a,
+            point_b: constraint.world_b,
+            local_a: constraint.local_a,
+            local_b: constraint.local_b,
+            normal: constraint.normal,
+            penetration_depth: constraint.penetration_depth,
+        })
+        .collect();
+
+    contacts.sort_by_key(|contact| {
+        (
+            contact.body_a.min(contact.body_b),
+            contact.body_a.max(contact.body_b),
+        )
+    });
+    contacts
 }
 
 fn debug_bodies(bodies: &[RigidBodyData]) {
@@ -126,6 +462,21 @@ fn debug_bodies(bodies: &[RigidBodyData]) {
     }
 }
 
+/// Sums the forces and torques given for each body.
+fn sum_external_forces(
+    forces: impl Iterator<Item = ExternalForce>,
+) -> HashMap<RigidBodyId, (Vec3, Vec3)> {
+    let mut external_forces: HashMap<RigidBodyId, (Vec3, Vec3)> = HashMap::new();
+    for (id, force, torque) in forces {
+        let total = external_forces
+            .entry(id)
+            .or_insert((Vec3::ZERO, Vec3::ZERO));
+        total.0 += force;
+        total.1 += torque;
+    }
+    external_forces
+}
+
 fn sync_kinematic_bodies(
     kinematic_entities: impl Iterator<Item = KinematicBody>,
     entities: &mut [RigidBodyData],
@@ -148,3 +499,290 @@ fn sync_kinematic_bodies(
         entity.set_position(*position);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        check_world_row, contacts, simulate,
+        test_utils::{add_floor, add_sphere, dynamic_body},
+        warm_start, CollisionDetection, PhysicsError, PhysicsScene, RigidBodyData,
+    };
+    use crate::{math::Vec3, Collider, PhysicsWorld, RigidBodyProperties, RigidBodyType};
+
+    #[test]
+    fn touching_bodies_produce_one_contact_pair() {
+        let properties = RigidBodyProperties::builder().build();
+        let mut floor = dynamic_body(1, Vec3::new(0.0, -0.5, 0.0));
+        floor.body_type = RigidBodyType::Static;
+        let mut entities = vec![
+            RigidBodyData::new(
+                floor,
+                &properties,
+                &Collider::cuboid(1, Vec3::new(10.0, 1.0, 10.0)),
+            ),
+            // Slightly sunk into the floor
+            RigidBodyData::new(
+                dynamic_body(2, Vec3::new(0.0, 0.49, 0.0)),
+                &properties,
+                &Collider::sphere(1, 0.5),
+            ),
+            RigidBodyData::new(
+                dynamic_body(3, Vec3::new(0.0, 5.0, 0.0)),
+                &properties,
+                &Collider::sphere(1, 0.5),
+            ),
+        ];
+
+        let contacts = contacts(&PhysicsWorld::builder().build(), &mut entities);
+
+        assert_eq!(contacts.len(), 1);
+        let contact = &contacts[0];
+        assert_eq!(contact.body_a.min(contact.body_b), 1);
+        assert_eq!(contact.body_a.max(contact.body_b), 2);
+        assert!(contact.penetration_depth < 0.0);
+    }
+
+    #[test]
+    fn off_center_contact_reports_local_points_away_from_the_center() {
+        let properties = RigidBodyProperties::builder().build();
+        let mut floor = dynamic_body(1, Vec3::new(0.0, -0.5, 0.0));
+        floor.body_type = RigidBodyType::Static;
+        let mut entities = vec![
+            RigidBodyData::new(
+                floor,
+                &properties,
+                &Collider::cuboid(1, Vec3::new(10.0, 1.0, 10.0)),
+            ),
+            // Near the edge of the floor, slightly sunk into it
+            RigidBodyData::new(
+                dynamic_body(2, Vec3::new(3.0, 0.49, 0.0)),
+                &properties,
+                &Collider::sphere(1, 0.5),
+            ),
+        ];
+
+        let contacts = contacts(&PhysicsWorld::builder().build(), &mut entities);
+
+        assert_eq!(contacts.len(), 1);
+        let contact = &contacts[0];
+        let (floor_local, floor_world) = if contact.body_a == 1 {
+            (contact.local_a, contact.point_a)
+        } else {
+            (contact.local_b, contact.point_b)
+        };
+        // Under the sphere, 3m from the center of the floor
+        assert!((floor_local.x - 3.0).abs() < 0.01, "local {}", floor_local);
+        assert!((floor_local.y - 0.5).abs() < 0.02, "local {}", floor_local);
+        assert!((floor_world - (Vec3::new(0.0, -0.5, 0.0) + floor_local)).length() < 1e-4);
+        assert!((contact.normal_for(2) - Vec3::Y).length() < 1e-3);
+    }
+
+    /// Keeps the warnings logged by every test, `log` only accepts one logger per process.
+    struct WarningLog(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for WarningLog {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static WARNINGS: WarningLog = WarningLog(std::sync::Mutex::new(Vec::new()));
+
+    #[test]
+    fn deleted_or_non_finite_worlds_are_rejected() {
+        let world = PhysicsWorld::builder().id(4).build();
+        assert_eq!(check_world_row(true, &world), Ok(()));
+        assert_eq!(
+            check_world_row(false, &world),
+            Err(PhysicsError::WorldNotFound(4))
+        );
+
+        let floating = PhysicsWorld::builder()
+            .id(4)
+            .gravity(Vec3::new(0.0, f32::NAN, 0.0))
+            .build();
+        assert_eq!(
+            check_world_row(true, &floating),
+            Err(PhysicsError::NonFiniteGravity(4))
+        );
+    }
+
+    #[test]
+    fn broad_phase_overflow_warns_and_can_skip_the_step() {
+        let _ = log::set_logger(&WARNINGS);
+        log::set_max_level(log::LevelFilter::Warn);
+
+        // 20 overlapping spheres make 190 pairs
+        let pile = |abort: bool| {
+            let world = PhysicsWorld::builder()
+                .id(if abort { 101 } else { 102 })
+                .max_broad_phase_pairs(10)
+                .abort_on_broad_phase_overflow(abort)
+                .build();
+            let mut scene = PhysicsScene::new(world);
+            for id in 1..=20 {
+                let position = Vec3::new(id as f32 * 0.01, 0.0, 0.0);
+                add_sphere(&mut scene, id, position, 0.5);
+            }
+            scene
+        };
+        let warned = |world_id: u64| {
+            let prefix = format!("[PhysicsWorld#{}] [BroadPhase] 190 pairs found", world_id);
+            let warnings = WARNINGS.0.lock().unwrap();
+            warnings.iter().any(|warning| warning.starts_with(&prefix))
+        };
+
+        let mut skipped = pile(true);
+        skipped.step();
+        assert!(warned(101));
+        assert!(skipped.contact_pairs().is_empty());
+        for body in skipped.bodies() {
+            assert_eq!(body.position(), Vec3::new(body.id as f32 * 0.01, 0.0, 0.0));
+        }
+
+        // `step_world` reports the skipped step
+        let properties = RigidBodyProperties::builder().build();
+        let mut bodies: Vec<_> = (1..=20)
+            .map(|id| {
+                let position = Vec3::new(id as f32 * 0.01, 0.0, 0.0);
+                RigidBodyData::new(
+                    dynamic_body(id, position),
+                    &properties,
+                    &Collider::sphere(1, 0.5),
+                )
+            })
+            .collect();
+        let result = simulate(
+            skipped.world(),
+            &mut CollisionDetection::new(),
+            &mut bodies,
+            &[],
+            &[],
+            &[],
+            &HashMap::new(),
+        );
+        let overflow = PhysicsError::BroadPhaseOverflow {
+            world_id: 101,
+            pairs: 190,
+        };
+        assert_eq!(result.err(), Some(overflow));
+
+        let mut degraded = pile(false);
+        degraded.step();
+        assert!(warned(102));
+        assert_eq!(degraded.contact_pairs().len(), 190);
+        assert!(degraded
+            .bodies()
+            .iter()
+            .all(|body| body.position().is_finite()));
+    }
+
+    #[test]
+    fn warm_start_reuses_the_multiplier_of_a_persistent_contact_only() {
+        let world = PhysicsWorld::builder().build();
+        let properties = RigidBodyProperties::builder().build();
+        let mut floor = dynamic_body(1, Vec3::new(0.0, -0.5, 0.0));
+        floor.body_type = RigidBodyType::Static;
+        let mut entities = vec![
+            RigidBodyData::new(
+                floor,
+                &properties,
+                &Collider::cuboid(1, Vec3::new(10.0, 1.0, 10.0)),
+            ),
+            RigidBodyData::new(
+                dynamic_body(2, Vec3::new(0.0, 0.49, 0.0)),
+                &properties,
+                &Collider::sphere(1, 0.5),
+            ),
+        ];
+        let mut collision_detection = CollisionDetection::new();
+        collision_detection.broad_phase(&world, &entities, &[], &[]);
+        let constraints = |entities: &mut [RigidBodyData]| {
+            collision_detection.narrow_phase_constraints(&world, entities)
+        };
+
+        let mut persistent = constraints(&mut entities);
+        assert_eq!(persistent.len(), 1);
+        let pair = (persistent[0].a, persistent[0].b);
+        let local_a = persistent[0].local_a;
+        let previous = HashMap::from([(pair, vec![(local_a + Vec3::new(0.001, 0.0, 0.0), 3.0)])]);
+        warm_start(&world, &mut persistent, &previous);
+        assert_eq!(persistent[0].normal_lagrange, 3.0);
+
+        // The point moved further than the tolerance, it's a new contact
+        let mut moved = constraints(&mut entities);
+        let previous = HashMap::from([(pair, vec![(local_a + Vec3::new(0.5, 0.0, 0.0), 3.0)])]);
+        warm_start(&world, &mut moved, &previous);
+        assert_eq!(moved[0].normal_lagrange, 0.0);
+    }
+
+    #[test]
+    fn warm_started_stack_settles_no_later() {
+        // Steps until every box of a three box stack is nearly at rest
+        let steps_to_settle = |warm_starting: bool| {
+            let world = PhysicsWorld::builder().warm_starting(warm_starting).build();
+            let mut scene = PhysicsScene::new(world);
+            add_floor(&mut scene, 1);
+            let properties = RigidBodyProperties::builder().build();
+            let collider = Collider::cuboid(1, Vec3::new(1.0, 1.0, 1.0));
+            for (id, y) in [(2, 0.55), (3, 1.6), (4, 2.65)] {
+                let body = dynamic_body(id, Vec3::new(0.0, y, 0.0));
+                assert!(scene.add_body(body, &properties, &collider));
+            }
+
+            (1..=600)
+                .find(|_| {
+                    scene.step();
+                    scene
+                        .bodies()
+                        .iter()
+                        .all(|body| body.linear_velocity().length() < 0.01)
+                })
+                .expect("the stack never settled")
+        };
+
+        let cold = steps_to_settle(false);
+        let warm = steps_to_settle(true);
+        assert!(warm <= cold, "warm: {} steps, cold: {} steps", warm, cold);
+    }
+
+    #[test]
+    fn normals_of_a_sphere_on_a_floor_point_up() {
+        let properties = RigidBodyProperties::builder().build();
+        // Whichever body comes first, the sphere is pushed up
+        for (floor_id, sphere_id) in [(1, 2), (2, 1)] {
+            let mut floor = dynamic_body(floor_id, Vec3::new(0.0, -0.5, 0.0));
+            floor.body_type = RigidBodyType::Static;
+            let mut entities = vec![
+                RigidBodyData::new(
+                    floor,
+                    &properties,
+                    &Collider::cuboid(1, Vec3::new(10.0, 1.0, 10.0)),
+                ),
+                RigidBodyData::new(
+                    dynamic_body(sphere_id, Vec3::new(0.0, 0.49, 0.0)),
+                    &properties,
+                    &Collider::sphere(1, 0.5),
+                ),
+            ];
+            entities.sort_by_key(|entity| entity.id);
+
+            let contacts = contacts(&PhysicsWorld::builder().build(), &mut entities);
+            assert_eq!(contacts.len(), 1);
+            let up = contacts[0].normal_for(sphere_id);
+            assert!((up - Vec3::Y).length() < 1e-4, "sphere {sphere_id}: {up}");
+            let down = contacts[0].normal_for(floor_id);
+            assert!((down + Vec3::Y).length() < 1e-4, "floor {floor_id}: {down}");
+        }
+    }
+}
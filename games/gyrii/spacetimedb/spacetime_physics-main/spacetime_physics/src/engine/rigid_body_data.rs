@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use log::warn;
 use parry3d::na::Isometry3;
 use spacetimedb::ReducerContext;
 
@@ -8,6 +9,14 @@ use crate::{
     Collider, ColliderId, PhysicsWorldId, RigidBody, RigidBodyProperties, ShapeWrapper,
 };
 
+use super::{xpbd::clamp_restitution, PhysicsError};
+
+/// Changes smaller than this are not considered worth writing back to the database.
+const DIRTY_EPSILON: f32 = 1e-5;
+
+/// Bodies with linear and angular speeds below this are considered at rest.
+const REST_VELOCITY: f32 = 1e-4;
+
 /// Represents a rigid body in the physics engine, containing its properties and state.
 /// This struct is used as an abstraction layer to the RigidBody storage in the database,
 /// it's also used to store properties exclusive to the physics engine algorithms,
@@ -15,12 +24,20 @@ use crate::{
 pub struct RigidBodyData {
     pub id: u64,
     rb: RigidBody,
+    /// The body as it was loaded from the database, used to detect changes.
+    loaded: RigidBody,
     mass: f32,
     inv_mass: f32,
     friction_static_coefficient: f32,
     friction_dynamic_coefficient: f32,
     restitution_coefficient: f32,
+    contact_compliance: f32,
+    rolling_resistance: f32,
     shape: ShapeWrapper,
+    volume: f32,
+    margin: f32,
+    /// Dynamic bodies without a positive mass are treated as static.
+    massless: bool,
     inertia_tensor: Mat3,
     inv_inertia_tensor: Mat3,
     pre_solve_linear_velocity: Vec3,
@@ -36,18 +53,36 @@ impl RigidBodyData {
         rb_properties: &RigidBodyProperties,
         collider: &Collider,
     ) -> Self {
-        let inertia_tensor = collider.inertia_tensor(rb_properties.mass);
+        let inertia = inertia_tensors(collider, rb_properties.mass);
+        Self::with_inertia(rigid_body, rb_properties, collider, inertia)
+    }
+
+    fn with_inertia(
+        rigid_body: RigidBody,
+        rb_properties: &RigidBodyProperties,
+        collider: &Collider,
+        (inertia_tensor, inv_inertia_tensor): (Mat3, Mat3),
+    ) -> Self {
+        // Both are reported once when the properties are written, not on every load
+        let massless = !(rb_properties.mass > 0.0);
+
         Self {
             id: rigid_body.id,
             rb: rigid_body,
+            loaded: rigid_body,
             shape: ShapeWrapper::from(collider),
+            volume: collider.volume(),
+            margin: collider.margin,
+            massless,
             mass: rb_properties.mass,
-            inv_mass: rb_properties.inv_mass,
+            inv_mass: rb_properties.inverse_mass(),
             friction_static_coefficient: rb_properties.friction_static_coefficient,
             friction_dynamic_coefficient: rb_properties.friction_dynamic_coefficient,
-            restitution_coefficient: rb_properties.restitution_coefficient,
+            restitution_coefficient: clamp_restitution(rb_properties.restitution_coefficient),
+            contact_compliance: rb_properties.contact_compliance,
+            rolling_resistance: rb_properties.rolling_resistance,
             inertia_tensor,
-            inv_inertia_tensor: inertia_tensor.inverse(),
+            inv_inertia_tensor,
             pre_solve_linear_velocity: rigid_body.linear_velocity,
             pre_solve_angular_velocity: rigid_body.angular_velocity,
             previous_position: rigid_body.position,
@@ -56,28 +91,61 @@ impl RigidBodyData {
         }
     }
 
+    /// Loads the bodies of a world, see `from_rows`.
     pub fn collect(
         ctx: &ReducerContext,
         world_id: PhysicsWorldId,
         colliders: &HashMap<ColliderId, Collider>,
-    ) -> Vec<Self> {
-        let rb_properties = RigidBodyProperties::all(ctx, world_id)
+    ) -> (Vec<Self>, Vec<PhysicsError>) {
+        let rb_properties = RigidBodyProperties::all_in_world(ctx, world_id)
             .map(|props| (props.id, props))
             .collect::<HashMap<_, _>>();
+        let bodies: Vec<_> = RigidBody::all_in_world(ctx, world_id).collect();
 
-        let mut entities: Vec<_> = RigidBody::all(ctx, world_id)
-            .map(move |rb| {
-                RigidBodyData::new(
-                    rb,
-                    rb_properties.get(&rb.properties_id).unwrap(),
-                    colliders.get(&rb.collider_id).unwrap(),
-                )
-            })
-            .collect();
+        Self::from_rows(world_id, bodies, &rb_properties, colliders)
+    }
+
+    /// Builds the bodies of a world from its rows, sorted by id.
+    /// `properties` and `colliders` must only hold rows of `world_id`.
+    ///
+    /// Bodies that can't be simulated are left out and returned with the reason, along with
+    /// the loaded ones.
+    pub(crate) fn from_rows(
+        world_id: PhysicsWorldId,
+        bodies: Vec<RigidBody>,
+        rb_properties: &HashMap<u64, RigidBodyProperties>,
+        colliders: &HashMap<ColliderId, Collider>,
+    ) -> (Vec<Self>, Vec<PhysicsError>) {
+        // Bodies often share a collider and a mass, only compute their inertia once
+        let mut inertia_cache: HashMap<(ColliderId, u32), (Mat3, Mat3)> =
+            HashMap::with_capacity(colliders.len());
+
+        // Rows are much smaller than the loaded data, size the result from them to allocate it once
+        let mut entities = Vec::with_capacity(bodies.len());
+        let mut skipped = Vec::new();
+
+        // Only rows of this world are loaded, so a body pointing at another world's
+        // collider or properties is skipped instead of leaking across worlds.
+        entities.extend(bodies.into_iter().filter_map(|rb| {
+            let (properties, collider) = match check_body(&rb, rb_properties, colliders) {
+                Ok(found) => found,
+                Err(err) => {
+                    warn!("[PhysicsWorld#{}] {}, skipping", world_id, err);
+                    skipped.push(err);
+                    return None;
+                }
+            };
+            let inertia = *inertia_cache
+                .entry((collider.id, properties.mass.to_bits()))
+                .or_insert_with(|| inertia_tensors(collider, properties.mass));
+            Some(RigidBodyData::with_inertia(
+                rb, properties, collider, inertia,
+            ))
+        }));
 
         entities.sort_by_key(|e| e.id);
 
-        entities
+        (entities, skipped)
     }
 
     pub fn effective_mass(&self) -> Vec3 {
@@ -86,10 +154,14 @@ impl RigidBodyData {
 
     pub fn effective_inverse_mass(&self) -> Vec3 {
         // TODO: Take into account locked axes
-        Vec3::splat(self.inv_mass)
+        Vec3::splat(self.inv_mass())
     }
 
     pub fn effective_inverse_inertia(&self) -> Mat3 {
+        if self.rb.frozen || self.massless {
+            return Mat3::ZERO;
+        }
+
         // TODO: Take into account locked axes
         let r = self.rb.rotation.to_mat3();
         r * self.inv_inertia_tensor * r.transpose()
@@ -107,6 +179,10 @@ impl RigidBodyData {
         (self.restitution_coefficient + other.restitution_coefficient) / 2.0
     }
 
+    pub fn combine_contact_compliance(&self, other: &Self) -> f32 {
+        self.contact_compliance + other.contact_compliance
+    }
+
     pub fn previous_position(&self) -> Vec3 {
         self.previous_position
     }
@@ -123,8 +199,21 @@ impl RigidBodyData {
         self.rb.rotation
     }
 
+    /// Frozen and massless dynamic bodies are treated as static by the solver.
     pub fn is_dynamic(&self) -> bool {
-        self.rb.is_dynamic()
+        self.rb.is_dynamic() && !self.rb.frozen && !self.massless
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.rb.frozen
+    }
+
+    pub fn rolling_resistance(&self) -> f32 {
+        self.rolling_resistance
+    }
+
+    pub fn upright_stiffness(&self) -> f32 {
+        self.rb.upright_stiffness
     }
 
     pub fn is_kinematic(&self) -> bool {
@@ -132,6 +221,9 @@ impl RigidBodyData {
     }
 
     pub fn inv_mass(&self) -> f32 {
+        if self.rb.frozen || self.massless {
+            return 0.0;
+        }
         self.inv_mass
     }
 
@@ -139,6 +231,14 @@ impl RigidBodyData {
         &self.shape
     }
 
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn margin(&self) -> f32 {
+        self.margin
+    }
+
     pub fn linear_velocity(&self) -> Vec3 {
         self.rb.linear_velocity
     }
@@ -191,6 +291,7 @@ impl RigidBodyData {
 
     pub fn set_linear_velocity(&mut self, velocity: Vec3) {
         self.rb.linear_velocity = velocity;
+        self.is_dirty = true;
     }
 
     pub fn set_angular_velocity(&mut self, velocity: Vec3) {
@@ -216,8 +317,50 @@ impl RigidBodyData {
         self.pre_solve_angular_velocity = velocity;
     }
 
+    /// Returns true if the body was modified and is either still moving or differs from the
+    /// loaded state by more than a small epsilon, meaning it should be written back.
+    pub fn is_dirty(&self) -> bool {
+        let rb = &self.rb;
+        let loaded = &self.loaded;
+
+        // A body that moved last step still has to catch its previous pose up
+        let previous_stale = vec_changed(loaded.previous_position, loaded.position)
+            || quat_changed(loaded.previous_rotation, loaded.rotation);
+        if previous_stale {
+            return true;
+        }
+
+        if !self.is_dirty {
+            return false;
+        }
+
+        // A body too slow to move by the epsilon in one step would be reloaded at the same
+        // pose every step and never move, only skip the ones at rest
+        let moving = rb.linear_velocity.length_squared() > REST_VELOCITY * REST_VELOCITY
+            || rb.angular_velocity.length_squared() > REST_VELOCITY * REST_VELOCITY;
+        if moving {
+            return true;
+        }
+
+        vec_changed(rb.position, loaded.position)
+            || quat_changed(rb.rotation, loaded.rotation)
+            || vec_changed(rb.linear_velocity, loaded.linear_velocity)
+            || vec_changed(rb.angular_velocity, loaded.angular_velocity)
+            || vec_changed(rb.force, loaded.force)
+            || vec_changed(rb.torque, loaded.torque)
+    }
+
     pub fn update(&self, ctx: &ReducerContext) {
-        self.rb.update(ctx);
+        let mut rb = self.rb;
+        rb.previous_position = self.loaded.position;
+        rb.previous_rotation = self.loaded.rotation;
+        if sanitize(&mut rb, &self.loaded) {
+            warn!(
+                "[RigidBody#{}] Diverged to a non-finite state, reset to its loaded pose",
+                rb.id
+            );
+        }
+        rb.update(ctx);
     }
 }
 
@@ -226,3 +369,365 @@ impl From<&RigidBodyData> for Isometry3<f32> {
         value.rb.into()
     }
 }
+
+/// The inertia tensor of a collider for a given mass, and its inverse.
+fn inertia_tensors(collider: &Collider, mass: f32) -> (Mat3, Mat3) {
+    let inertia_tensor = collider.inertia_tensor(mass);
+    // A zero inertia tensor can't be inverted, a massless body just doesn't rotate
+    let inv_inertia_tensor = if mass > 0.0 {
+        inertia_tensor.inverse()
+    } else {
+        Mat3::ZERO
+    };
+    (inertia_tensor, inv_inertia_tensor)
+}
+
+/// Replaces NaNs and infinities before they are stored, they would poison every following step.
+/// The pose falls back to `loaded`, velocities and forces to zero.
+/// The properties and collider of a body that can be simulated.
+fn check_body<'a>(
+    rb: &RigidBody,
+    rb_properties: &'a HashMap<u64, RigidBodyProperties>,
+    colliders: &'a HashMap<ColliderId, Collider>,
+) -> Result<(&'a RigidBodyProperties, &'a Collider), PhysicsError> {
+    let (Some(properties), Some(collider)) = (
+        rb_properties.get(&rb.properties_id),
+        colliders.get(&rb.collider_id),
+    ) else {
+        return Err(PhysicsError::MissingReference {
+            body_id: rb.id,
+            collider_id: rb.collider_id,
+            properties_id: rb.properties_id,
+        });
+    };
+    let finite = rb.position.is_finite()
+        && rb.rotation.is_finite()
+        && rb.linear_velocity.is_finite()
+        && rb.angular_velocity.is_finite();
+    if !finite {
+        return Err(PhysicsError::NonFiniteState(rb.id));
+    }
+    Ok((properties, collider))
+}
+
+fn sanitize(rb: &mut RigidBody, loaded: &RigidBody) -> bool {
+    let finite = rb.position.is_finite()
+        && rb.rotation.is_finite()
+        && rb.linear_velocity.is_finite()
+        && rb.angular_velocity.is_finite()
+        && rb.force.is_finite()
+        && rb.torque.is_finite();
+    if finite {
+        return false;
+    }
+    rb.position = rb.position.sanitize(loaded.position.sanitize(Vec3::ZERO));
+    rb.rotation = rb
+        .rotation
+        .sanitize(loaded.rotation.sanitize(Quat::IDENTITY));
+    rb.linear_velocity = rb.linear_velocity.sanitize(Vec3::ZERO);
+    rb.angular_velocity = rb.angular_velocity.sanitize(Vec3::ZERO);
+    rb.force = rb.force.sanitize(Vec3::ZERO);
+    rb.torque = rb.torque.sanitize(Vec3::ZERO);
+    true
+}
+
+fn vec_changed(a: Vec3, b: Vec3) -> bool {
+    (a - b).length_squared() > DIRTY_EPSILON * DIRTY_EPSILON
+}
+
+fn quat_changed(a: Quat, b: Quat) -> bool {
+    let d = a - b;
+    d.x.abs() > DIRTY_EPSILON
+        || d.y.abs() > DIRTY_EPSILON
+        || d.z.abs() > DIRTY_EPSILON
+        || d.w.abs() > DIRTY_EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::RigidBodyData;
+    use crate::{
+        engine::test_utils::*, math::Vec3, Collider, PhysicsError, PhysicsScene, PhysicsWorld,
+        RigidBodyProperties,
+    };
+
+    #[test]
+    fn resting_body_is_not_dirty() {
+        let mut scene = scene_with_floor(1);
+        add_sphere(&mut scene, 2, Vec3::new(0.0, 0.5, 0.0), 0.5);
+        scene.step_n(120);
+        let settled = scene.body(2).unwrap().position();
+
+        // Loaded again at its settled pose, like the next step would
+        let mut scene = scene_with_floor(1);
+        add_sphere(&mut scene, 2, settled, 0.5);
+        scene.step();
+
+        assert!(!scene.body(2).unwrap().is_dirty());
+    }
+
+    #[test]
+    fn slow_body_is_dirty() {
+        // Moves 5e-6 per step, less than DIRTY_EPSILON
+        let world = PhysicsWorld::builder().gravity(Vec3::ZERO).build();
+        let mut scene = PhysicsScene::new(world);
+        let mut body = dynamic_body(1, Vec3::ZERO);
+        body.linear_velocity = Vec3::new(3e-4, 0.0, 0.0);
+        scene.add_body(
+            body,
+            &RigidBodyProperties::builder().build(),
+            &Collider::sphere(1, 0.5),
+        );
+
+        scene.step();
+
+        assert!(scene.body(1).unwrap().is_dirty());
+    }
+
+    #[test]
+    fn frozen_body_stops_mid_air_and_falls_again_once_thawed() {
+        let mut scene = PhysicsScene::new(PhysicsWorld::builder().build());
+        add_sphere(&mut scene, 1, Vec3::new(0.0, 100.0, 0.0), 0.5);
+        scene.step_n(10);
+        let falling = scene.body(1).unwrap().linear_velocity();
+        assert!(falling.y < 0.0);
+
+        scene.body_mut(1).unwrap().rb.frozen = true;
+        let frozen_at = scene.body(1).unwrap().position();
+        scene.step_n(10);
+        assert_eq!(scene.body(1).unwrap().position(), frozen_at);
+        assert_eq!(scene.body(1).unwrap().linear_velocity(), falling);
+
+        scene.body_mut(1).unwrap().rb.frozen = false;
+        scene.step_n(10);
+        let body = scene.body(1).unwrap();
+        assert!(body.position().y < frozen_at.y);
+        assert!(body.linear_velocity().y < falling.y);
+    }
+
+    #[test]
+    fn bodies_referencing_another_worlds_rows_are_skipped() {
+        let collider = Collider {
+            id: 10,
+            ..Collider::sphere(1, 0.5)
+        };
+        let properties = RigidBodyProperties::builder().id(20).build();
+        let colliders = HashMap::from([(collider.id, collider)]);
+        let rb_properties = HashMap::from([(properties.id, properties)]);
+
+        // Collider 11 and properties 21 belong to world 2, they are not in this world's maps
+        let body = |id, collider_id, properties_id| {
+            let mut body = dynamic_body(id, Vec3::ZERO);
+            body.collider_id = collider_id;
+            body.properties_id = properties_id;
+            body
+        };
+        let rows = vec![body(3, 10, 20), body(1, 11, 20), body(2, 10, 21)];
+
+        let (loaded, skipped) = RigidBodyData::from_rows(1, rows, &rb_properties, &colliders);
+
+        let ids: Vec<_> = loaded.iter().map(|body| body.id).collect();
+        assert_eq!(ids, vec![3]);
+        let missing = |body_id, collider_id, properties_id| PhysicsError::MissingReference {
+            body_id,
+            collider_id,
+            properties_id,
+        };
+        assert_eq!(skipped, vec![missing(1, 11, 20), missing(2, 10, 21)]);
+    }
+
+    #[test]
+    fn non_finite_bodies_are_skipped() {
+        let collider = Collider {
+            id: 10,
+            ..Collider::sphere(1, 0.5)
+        };
+        let properties = RigidBodyProperties::builder().id(20).build();
+        let colliders = HashMap::from([(collider.id, collider)]);
+        let rb_properties = HashMap::from([(properties.id, properties)]);
+
+        let mut rows = Vec::new();
+        for id in 1..=3 {
+            let mut body = dynamic_body(id, Vec3::ZERO);
+            body.collider_id = collider.id;
+            body.properties_id = properties.id;
+            rows.push(body);
+        }
+        rows[0].position.x = f32::NAN;
+        rows[2].linear_velocity.y = f32::INFINITY;
+
+        let (loaded, skipped) = RigidBodyData::from_rows(1, rows, &rb_properties, &colliders);
+
+        let ids: Vec<_> = loaded.iter().map(|body| body.id).collect();
+        assert_eq!(ids, vec![2]);
+        assert_eq!(
+            skipped,
+            vec![
+                PhysicsError::NonFiniteState(1),
+                PhysicsError::NonFiniteState(3)
+            ]
+        );
+    }
+
+    #[test]
+    fn worlds_step_in_isolation() {
+        // Same place, different worlds: the spheres would push each other apart if they shared one
+        let world = PhysicsWorld::builder().gravity(Vec3::ZERO).build();
+        let mut scenes = [PhysicsScene::new(world), PhysicsScene::new(world)];
+        for (id, scene) in (1..).zip(&mut scenes) {
+            add_sphere(scene, id, Vec3::ZERO, 0.5);
+        }
+
+        for scene in &mut scenes {
+            scene.step_n(10);
+            assert!(scene.contact_pairs().is_empty());
+            assert_eq!(scene.bodies()[0].position(), Vec3::ZERO);
+        }
+    }
+
+    #[test]
+    fn zero_mass_dynamic_body_stays_put_without_nans() {
+        let mut scene = PhysicsScene::new(PhysicsWorld::builder().build());
+        let massless = RigidBodyProperties::builder().mass(0.0).build();
+        let position = Vec3::new(0.0, 2.0, 0.0);
+        assert!(scene.add_body(
+            dynamic_body(1, position),
+            &massless,
+            &Collider::sphere(1, 0.5)
+        ));
+        // Lands on the massless body
+        add_sphere(&mut scene, 2, Vec3::new(0.0, 4.0, 0.0), 0.5);
+
+        scene.step_n(120);
+
+        let body = scene.body(1).unwrap();
+        assert_eq!(body.position(), position);
+        assert_eq!(body.linear_velocity(), Vec3::ZERO);
+        let landed = scene.body(2).unwrap();
+        assert!(landed.position().is_finite() && landed.linear_velocity().is_finite());
+        assert!(
+            landed.position().y > 2.5,
+            "landed at {}",
+            landed.position().y
+        );
+    }
+
+    #[test]
+    fn cached_inertia_matches_the_direct_computation() {
+        let colliders = HashMap::from([
+            (
+                10,
+                Collider {
+                    id: 10,
+                    ..Collider::sphere(1, 0.5)
+                },
+            ),
+            (
+                11,
+                Collider {
+                    id: 11,
+                    ..Collider::cuboid(1, Vec3::new(1.0, 2.0, 3.0))
+                },
+            ),
+        ]);
+        let rb_properties = HashMap::from([
+            (20, RigidBodyProperties::builder().id(20).mass(2.0).build()),
+            (21, RigidBodyProperties::builder().id(21).mass(5.0).build()),
+        ]);
+
+        // Every collider and mass combination, twice, so most bodies hit the cache
+        let mut rows = Vec::new();
+        for (id, (collider_id, properties_id)) in
+            (1..).zip([(10, 20), (11, 20), (10, 21), (11, 21)].repeat(2))
+        {
+            let mut body = dynamic_body(id, Vec3::ZERO);
+            body.collider_id = collider_id;
+            body.properties_id = properties_id;
+            rows.push(body);
+        }
+
+        let (loaded, _) = RigidBodyData::from_rows(1, rows.clone(), &rb_properties, &colliders);
+
+        assert_eq!(loaded.len(), rows.len());
+        for (body, row) in loaded.iter().zip(rows) {
+            let direct = RigidBodyData::new(
+                row,
+                &rb_properties[&row.properties_id],
+                &colliders[&row.collider_id],
+            );
+            assert_eq!(body.inertia_tensor(), direct.inertia_tensor());
+            assert_eq!(body.inv_inertia_tensor(), direct.inv_inertia_tensor());
+        }
+    }
+
+    /// Times `from_rows` against computing every inertia tensor, on the 2000 spheres of the
+    /// sample server which share one collider and one mass. Run with
+    /// `cargo test --release inertia_cache_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn inertia_cache_benchmark() {
+        let collider = Collider {
+            id: 10,
+            ..Collider::sphere(1, 1.0)
+        };
+        let properties = RigidBodyProperties::builder().id(20).build();
+        let colliders = HashMap::from([(collider.id, collider)]);
+        let rb_properties = HashMap::from([(properties.id, properties)]);
+        let rows: Vec<_> = (1..=2000)
+            .map(|id| {
+                let mut body = dynamic_body(id, Vec3::new(id as f32 * 3.0, 100.0, 0.0));
+                body.collider_id = collider.id;
+                body.properties_id = properties.id;
+                body
+            })
+            .collect();
+        let runs = 100;
+
+        let start = std::time::Instant::now();
+        for _ in 0..runs {
+            let (loaded, _) = RigidBodyData::from_rows(1, rows.clone(), &rb_properties, &colliders);
+            assert_eq!(loaded.len(), rows.len());
+        }
+        let cached = start.elapsed() / runs;
+
+        let start = std::time::Instant::now();
+        for _ in 0..runs {
+            let mut loaded: Vec<_> = rows
+                .clone()
+                .into_iter()
+                .map(|row| RigidBodyData::new(row, &properties, &collider))
+                .collect();
+            loaded.sort_by_key(|body| body.id);
+            assert_eq!(loaded.len(), rows.len());
+        }
+        let uncached = start.elapsed() / runs;
+
+        println!("  cached: {cached:?} per collect");
+        println!("uncached: {uncached:?} per collect");
+    }
+
+    #[test]
+    fn non_finite_components_fall_back_to_the_loaded_pose() {
+        let loaded = dynamic_body(1, Vec3::new(1.0, 2.0, 3.0));
+        let mut rb = loaded;
+        rb.position = Vec3::new(f32::NAN, 2.5, 3.0);
+        rb.linear_velocity = Vec3::new(f32::INFINITY, 0.0, 0.0);
+        rb.force = Vec3::new(0.0, f32::NAN, 0.0);
+
+        assert!(super::sanitize(&mut rb, &loaded));
+
+        // Only the broken component falls back
+        assert_eq!(rb.position, Vec3::new(1.0, 2.5, 3.0));
+        assert_eq!(rb.linear_velocity, Vec3::ZERO);
+        assert_eq!(rb.force, Vec3::ZERO);
+        assert_eq!(rb.rotation, loaded.rotation);
+
+        // A finite body is left alone
+        let mut finite = loaded;
+        finite.linear_velocity = Vec3::new(0.0, -1.0, 0.0);
+        assert!(!super::sanitize(&mut finite, &loaded));
+        assert_eq!(finite.linear_velocity, Vec3::new(0.0, -1.0, 0.0));
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+
+use log::{debug, warn};
+use spacetimedb::ReducerContext;
+
+use crate::{
+    tables::PhysicsWorld, Collider, CollisionEvent, FluidVolume, RigidBody, RigidBodyId,
+    RigidBodyProperties,
+};
+
+use super::{
+    collision_detection::CollisionDetection, simulate, sum_external_forces, ExternalForce,
+    RigidBodyData, StepReport,
+};
+
+/// A physics world held in memory, stepped without touching the database.
+///
+/// Bodies can be added directly or loaded from the tables, stepped any number of times,
+/// then written back with `sync`. Triggers and raycasts are not simulated, they need the
+/// database to report their events.
+///
+/// The previous pose written by `sync` is the pose at load time, not the one of the last step.
+pub struct PhysicsScene {
+    world: PhysicsWorld,
+    bodies: Vec<RigidBodyData>,
+    fluids: Vec<FluidVolume>,
+    last_step: StepReport,
+}
+
+impl PhysicsScene {
+    /// An empty scene, bodies are added with `add_body`.
+    pub fn new(world: PhysicsWorld) -> Self {
+        Self {
+            world,
+            bodies: Vec::new(),
+            fluids: Vec::new(),
+            last_step: StepReport::default(),
+        }
+    }
+
+    /// A scene holding every body and fluid of a world, as `step_world` would load them.
+    pub fn load(ctx: &ReducerContext, world: PhysicsWorld) -> Self {
+        let colliders = Collider::map_in_world(ctx, world.id);
+        Self {
+            bodies: RigidBodyData::collect(ctx, world.id, &colliders).0,
+            fluids: FluidVolume::collect_in_world(ctx, world.id),
+            last_step: StepReport::default(),
+            world,
+        }
+    }
+
+    /// Adds a body, returns false and leaves the scene unchanged if its id is already used.
+    /// The properties and collider are only read, they don't need to be inserted.
+    pub fn add_body(
+        &mut self,
+        rigid_body: RigidBody,
+        properties: &RigidBodyProperties,
+        collider: &Collider,
+    ) -> bool {
+        // The solver looks bodies up by binary search, keep them sorted by id
+        let index = self.bodies.partition_point(|body| body.id < rigid_body.id);
+        if self.bodies.get(index).is_some_and(|body| body.id == rigid_body.id) {
+            warn!(
+                "[PhysicsScene#{}] RigidBody {} is already in the scene, skipping",
+                self.world.id, rigid_body.id
+            );
+            return false;
+        }
+        self.bodies
+            .insert(index, RigidBodyData::new(rigid_body, properties, collider));
+        true
+    }
+
+    pub fn add_fluid(&mut self, fluid: FluidVolume) {
+        self.fluids.push(fluid);
+    }
+
+    pub fn world(&self) -> &PhysicsWorld {
+        &self.world
+    }
+
+    pub fn bodies(&self) -> &[RigidBodyData] {
+        &self.bodies
+    }
+
+    pub fn body(&self, id: RigidBodyId) -> Option<&RigidBodyData> {
+        let index = self.bodies.binary_search_by_key(&id, |body| body.id).ok()?;
+        Some(&self.bodies[index])
+    }
+
+    pub fn body_mut(&mut self, id: RigidBodyId) -> Option<&mut RigidBodyData> {
+        let index = self.bodies.binary_search_by_key(&id, |body| body.id).ok()?;
+        Some(&mut self.bodies[index])
+    }
+
+    /// The pairs of bodies that touched during the last step, ordered by id.
+    pub fn contact_pairs(&self) -> &HashSet<(RigidBodyId, RigidBodyId)> {
+        &self.last_step.contact_pairs
+    }
+
+    /// What happened during the last step, including the substep positions if the world
+    /// records them.
+    pub fn last_step(&self) -> &StepReport {
+        &self.last_step
+    }
+
+    /// Runs one step of `world.time_step`, with the same solver as `step_world`.
+    pub fn step(&mut self) {
+        self.step_with_forces(std::iter::empty());
+    }
+
+    /// Runs one step with extra forces applied during this step only, see
+    /// `step_world_with_forces`.
+    pub fn step_with_forces(&mut self, forces: impl Iterator<Item = ExternalForce>) {
+        let sw = self.world.stopwatch("scene_step");
+        let mut collision_detection = CollisionDetection::new();
+        if let Ok(report) = simulate(
+            &self.world,
+            &mut collision_detection,
+            &mut self.bodies,
+            &[],
+            &[],
+            &self.fluids,
+            &sum_external_forces(forces),
+        ) {
+            self.last_step = report;
+        }
+        sw.end();
+    }
+
+    /// Runs `steps` steps in a row.
+    pub fn step_n(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Writes the modified bodies and the contacts of the last step to the database.
+    /// The bodies must already exist in the tables.
+    pub fn sync(&self, ctx: &ReducerContext) {
+        CollisionEvent::sync(ctx, self.world.id, &self.last_step.contact_pairs);
+
+        for body in &self.bodies {
+            if body.is_frozen() || !body.is_dirty() {
+                continue;
+            }
+
+            if self.world.debug {
+                debug!(
+                    "[PhysicsScene#{}] Updating {} position: {}, velocity: {}",
+                    self.world.id,
+                    body.id,
+                    body.position(),
+                    body.linear_velocity(),
+                );
+            }
+            body.update(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use crate::{
+        math::Vec3, Collider, PhysicsWorld, RigidBody, RigidBodyId, RigidBodyProperties,
+        RigidBodyType,
+    };
+
+    use super::PhysicsScene;
+
+    /// A scene with the default world settings and a static floor whose top is at y = 0.
+    pub fn scene_with_floor(floor_id: RigidBodyId) -> PhysicsScene {
+        let mut scene = PhysicsScene::new(PhysicsWorld::builder().build());
+        add_floor(&mut scene, floor_id);
+        scene
+    }
+
+    /// A 40x1x40 static cuboid whose top face is at y = 0.
+    pub fn add_floor(scene: &mut PhysicsScene, id: RigidBodyId) {
+        let floor = RigidBody::builder()
+            .id(id)
+            .position(Vec3::new(0.0, -0.5, 0.0))
+            .body_type(RigidBodyType::Static)
+            .collider_id(0)
+            .properties_id(0)
+            .build();
+        let collider = Collider::cuboid(1, Vec3::new(40.0, 1.0, 40.0));
+        assert!(scene.add_body(floor, &RigidBodyProperties::builder().build(), &collider));
+    }
+
+    /// A dynamic body row, the collider and properties ids are not read by the scene.
+    pub fn dynamic_body(id: RigidBodyId, position: Vec3) -> RigidBody {
+        RigidBody::builder()
+            .id(id)
+            .position(position)
+            .collider_id(0)
+            .properties_id(0)
+            .build()
+    }
+
+    /// Adds a dynamic sphere with the default properties, a mass of 1.
+    pub fn add_sphere(scene: &mut PhysicsScene, id: RigidBodyId, position: Vec3, radius: f32) {
+        let properties = RigidBodyProperties::builder().build();
+        let collider = Collider::sphere(1, radius);
+        assert!(scene.add_body(dynamic_body(id, position), &properties, &collider));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use crate::{math::Vec3, Collider, PhysicsWorld, RigidBodyProperties};
+
+    use super::PhysicsScene;
+
+    #[test]
+    fn ball_falls_and_rests_on_floor() {
+        let mut scene = scene_with_floor(1);
+        add_sphere(&mut scene, 2, Vec3::new(0.0, 3.0, 0.0), 0.5);
+
+        scene.step_n(180);
+
+        let ball = scene.body(2).unwrap();
+        assert!(
+            (ball.position().y - 0.5).abs() < 0.05,
+            "ball at {}",
+            ball.position()
+        );
+        assert!(ball.linear_velocity().length() < 0.1);
+        assert!(scene.contact_pairs().contains(&(1, 2)));
+    }
+
+    #[test]
+    fn bodies_stay_sorted_and_duplicates_are_rejected() {
+        let mut scene = PhysicsScene::new(PhysicsWorld::builder().build());
+        let properties = RigidBodyProperties::builder().build();
+        let collider = Collider::sphere(1, 0.5);
+        for id in [5, 2, 9] {
+            assert!(scene.add_body(dynamic_body(id, Vec3::ZERO), &properties, &collider));
+        }
+
+        let moved = dynamic_body(2, Vec3::new(0.0, 10.0, 0.0));
+        assert!(!scene.add_body(moved, &properties, &collider));
+
+        let ids: Vec<_> = scene.bodies().iter().map(|body| body.id).collect();
+        assert_eq!(ids, vec![2, 5, 9]);
+        assert_eq!(scene.body(2).unwrap().position(), Vec3::ZERO);
+        assert!(scene.body(3).is_none());
+    }
+
+    #[test]
+    fn external_force_cancels_gravity_for_its_body_only() {
+        let world = PhysicsWorld::builder().build();
+        let mut scene = PhysicsScene::new(world);
+        let start = Vec3::new(0.0, 10.0, 0.0);
+        add_sphere(&mut scene, 1, start, 0.5);
+        add_sphere(&mut scene, 2, Vec3::new(5.0, 10.0, 0.0), 0.5);
+
+        // Both spheres weigh 1 kg
+        for _ in 0..60 {
+            scene.step_with_forces([(1, -world.gravity, Vec3::ZERO)].into_iter());
+        }
+
+        let held = scene.body(1).unwrap();
+        assert!(
+            (held.position() - start).length() < 1e-4,
+            "held at {}",
+            held.position()
+        );
+        assert!(held.linear_velocity().length() < 1e-4);
+        assert_eq!(held.force(), Vec3::ZERO);
+        assert!(scene.body(2).unwrap().position().y < 6.0);
+
+        // The force isn't stored, without it the sphere falls
+        scene.step_n(10);
+        assert!(scene.body(1).unwrap().linear_velocity().y < -0.5);
+    }
+
+    #[test]
+    fn zero_sub_steps_and_iterations_step_like_one() {
+        let drop = |sub_step: u32, position_iterations: u32| {
+            let world = PhysicsWorld::builder()
+                .sub_step(sub_step)
+                .position_iterations(position_iterations)
+                .build();
+            let mut scene = PhysicsScene::new(world);
+            add_floor(&mut scene, 1);
+            add_sphere(&mut scene, 2, Vec3::new(0.0, 2.0, 0.0), 0.5);
+            scene.step_n(60);
+            scene.body(2).unwrap().position()
+        };
+
+        let clamped = drop(0, 0);
+        assert!(clamped.is_finite(), "ball at {clamped}");
+        assert_eq!(clamped, drop(1, 1));
+    }
+
+    #[test]
+    fn substep_positions_span_the_whole_step_of_a_fast_body() {
+        let world = PhysicsWorld::builder()
+            .gravity(Vec3::ZERO)
+            .record_substep_positions(true)
+            .build();
+        let mut scene = PhysicsScene::new(world);
+        let start = Vec3::new(-5.0, 1.0, 0.0);
+        let mut bullet = dynamic_body(1, start);
+        bullet.linear_velocity = Vec3::new(300.0, 0.0, 0.0);
+        let properties = RigidBodyProperties::builder().build();
+        assert!(scene.add_body(bullet, &properties, &Collider::sphere(1, 0.05)));
+
+        scene.step();
+
+        let positions = scene.last_step().positions_of(1);
+        assert_eq!(positions.len(), world.sub_step_count() as usize + 1);
+        assert_eq!(positions[0], start);
+        let end = scene.body(1).unwrap().position();
+        assert_eq!(*positions.last().unwrap(), end);
+        assert!(positions.windows(2).all(|pair| pair[1].x > pair[0].x));
+        assert!(scene.last_step().positions_of(2).is_empty());
+
+        let mut quiet = PhysicsScene::new(PhysicsWorld::builder().build());
+        add_sphere(&mut quiet, 1, start, 0.5);
+        quiet.step();
+        assert!(quiet.last_step().substep_positions.is_empty());
+    }
+}
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use crate::{math::Vec3, RigidBodyId};
+
+use super::RigidBodyData;
+
+/// Collects the position of every moving body at the start of a step and after each substep.
+#[derive(Default)]
+pub(crate) struct SubstepRecorder {
+    positions: HashMap<RigidBodyId, Vec<Vec3>>,
+}
+
+impl SubstepRecorder {
+    pub fn record(&mut self, entities: &[RigidBodyData]) {
+        for entity in entities {
+            if entity.is_dynamic() || entity.is_kinematic() {
+                self.positions
+                    .entry(entity.id)
+                    .or_default()
+                    .push(entity.position());
+            }
+        }
+    }
+
+    pub fn into_positions(self) -> HashMap<RigidBodyId, Vec<Vec3>> {
+        self.positions
+    }
+}
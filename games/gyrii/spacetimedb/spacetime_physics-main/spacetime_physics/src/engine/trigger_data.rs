@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use log::warn;
 use parry3d::na::Isometry3;
 use spacetimedb::ReducerContext;
 
@@ -38,10 +39,16 @@ impl TriggerData {
         world_id: u64,
         colliders: &HashMap<ColliderId, Collider>,
     ) -> Vec<Self> {
-        Trigger::all(ctx, world_id)
-            .map(|trigger| {
-                let collider = colliders.get(&trigger.collider_id).unwrap();
-                TriggerData::new(&trigger, collider)
+        Trigger::all_in_world(ctx, world_id)
+            .filter_map(|trigger| {
+                let Some(collider) = colliders.get(&trigger.collider_id) else {
+                    warn!(
+                        "[PhysicsWorld#{}] Trigger {} references a collider outside of this world, skipping",
+                        world_id, trigger.id
+                    );
+                    return None;
+                };
+                Some(TriggerData::new(&trigger, collider))
             })
             .collect()
     }
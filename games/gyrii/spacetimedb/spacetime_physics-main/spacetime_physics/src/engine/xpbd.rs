@@ -1,9 +1,12 @@
+use std::collections::HashMap;
+
 use log::debug;
+use parry3d::na::Isometry3;
 
 use crate::{
     math::{Mat3, Quat, Vec3},
     utils::get_bodies_mut,
-    PhysicsWorld,
+    FluidVolume, PhysicsWorld, RigidBodyId, Solver,
 };
 
 use super::{
@@ -11,12 +14,62 @@ use super::{
     RigidBodyData,
 };
 
+/// Adds buoyancy and drag forces to the bodies inside fluid volumes,
+/// must be called before `integrate_bodies` which consumes the forces.
+pub(crate) fn apply_fluid_forces(
+    bodies: &mut [RigidBodyData],
+    fluids: &[FluidVolume],
+    world: &PhysicsWorld,
+) {
+    if fluids.is_empty() {
+        return;
+    }
+
+    let sw = world.stopwatch("apply_fluid_forces");
+    for body in bodies {
+        if !body.is_dynamic() {
+            continue;
+        }
+
+        let isometry: Isometry3<f32> = (&*body).into();
+        let aabb = body.shape().collision_aabb(&isometry, 0.0);
+        let (aabb_min, aabb_max) = (Vec3::from(aabb.mins), Vec3::from(aabb.maxs));
+
+        for fluid in fluids {
+            let fraction = fluid.submerged_fraction(aabb_min, aabb_max);
+            if fraction <= 0.0 {
+                continue;
+            }
+
+            // Archimedes: the displaced fluid weight pushes against gravity
+            let buoyancy = -world.gravity * (fluid.density * body.volume() * fraction);
+            let drag = -body.linear_velocity() * (fluid.linear_drag * fraction);
+            let angular_drag = -body.angular_velocity() * (fluid.angular_drag * fraction);
+
+            body.set_force(body.force() + buoyancy + drag);
+            body.set_torque(body.torque() + angular_drag);
+
+            if world.debug_substep() {
+                debug!(
+                    "[Fluid] body {}: fluid: {}, submerged: {}, buoyancy: {}, drag: {}",
+                    body.id, fluid.id, fraction, buoyancy, drag
+                );
+            }
+        }
+    }
+    sw.end();
+}
+
+/// Integrates gravity, the stored forces and the per-step `external_forces` (force, torque by
+/// body id). The stored forces are consumed, the external ones are left for the next substep.
 pub(crate) fn integrate_bodies(
     bodies: &mut [RigidBodyData],
+    external_forces: &HashMap<RigidBodyId, (Vec3, Vec3)>,
     world: &PhysicsWorld,
     delta_time: f32,
 ) {
     let sw = world.stopwatch("integrate_bodies");
+    let world_up = (-world.gravity).normalize_or(Vec3::Y);
     for body in bodies {
         if !body.is_dynamic() {
             continue;
@@ -24,9 +77,14 @@ pub(crate) fn integrate_bodies(
 
         // --- Linear integration ---
 
+        let (external_force, external_torque) = external_forces
+            .get(&body.id)
+            .copied()
+            .unwrap_or((Vec3::ZERO, Vec3::ZERO));
+
         body.set_previous_position(body.position());
         let weight = world.gravity * body.effective_mass();
-        let total_force = body.force() + weight;
+        let total_force = body.force() + external_force + weight;
 
         // v ← v + h * fext / m
         body.set_linear_velocity(
@@ -44,8 +102,14 @@ pub(crate) fn integrate_bodies(
         let inv_inertia_tensor = body.inv_inertia_tensor();
         let omega = body.angular_velocity();
 
+        // Upright spring, pulls the local up axis towards the world up
+        let mut torque = body.torque() + external_torque;
+        if body.upright_stiffness() > 0.0 {
+            let body_up = body.rotation().rotate(Vec3::Y);
+            torque += body_up.cross(world_up) * body.upright_stiffness();
+        }
+
         // gyroscopic torque: ω × (Iω)
-        let torque = body.torque();
         let i_omega = i * omega;
         let gyro = omega.cross(i_omega);
 
@@ -79,12 +143,66 @@ pub(crate) fn solve_constraints(
     delta_time: f32,
 ) {
     let sw = world.stopwatch("solve_constraints");
-    contact_constraints
-        .iter_mut()
-        .for_each(|constraint| constraint.solve(world, bodies, delta_time));
+    match world.solver {
+        Solver::GaussSeidel => contact_constraints
+            .iter_mut()
+            .for_each(|constraint| constraint.solve(world, bodies, delta_time)),
+        Solver::Jacobi => solve_constraints_jacobi(world, contact_constraints, bodies, delta_time),
+    }
     sw.end();
 }
 
+/// Solves every constraint from the pose the bodies had at the start of the iteration, then
+/// moves each body by the average of the corrections it received.
+fn solve_constraints_jacobi(
+    world: &PhysicsWorld,
+    contact_constraints: &mut [PenetrationConstraint],
+    bodies: &mut [RigidBodyData],
+    delta_time: f32,
+) {
+    let start: Vec<(Vec3, Quat)> = bodies
+        .iter()
+        .map(|body| (body.position(), body.rotation()))
+        .collect();
+    // Sum of the position and rotation corrections of each body, and how many it received
+    let mut corrections = vec![(Vec3::ZERO, Vec3::ZERO, 0u32); bodies.len()];
+
+    for constraint in contact_constraints.iter_mut() {
+        constraint.solve(world, bodies, delta_time);
+
+        for id in [constraint.a, constraint.b] {
+            // Bodies are sorted by id
+            let Ok(i) = bodies.binary_search_by_key(&id, |body| body.id) else {
+                continue;
+            };
+            let body = &mut bodies[i];
+            if !body.is_dynamic() {
+                continue;
+            }
+            let (position, rotation) = start[i];
+            corrections[i].0 += body.position() - position;
+            corrections[i].1 += (body.rotation() * rotation.inverse()).as_radians();
+            corrections[i].2 += 1;
+
+            // The next constraints must see the starting pose
+            body.set_position(position);
+            body.set_rotation(rotation);
+        }
+    }
+
+    for (body, (i, (delta_position, delta_rotation, count))) in
+        bodies.iter_mut().zip(corrections.into_iter().enumerate())
+    {
+        if count == 0 {
+            continue;
+        }
+        let (position, rotation) = start[i];
+        let count = count as f32;
+        body.set_position(position + delta_position / count);
+        body.set_rotation(Quat::from_scaled_axis(delta_rotation / count) * rotation);
+    }
+}
+
 pub(crate) fn recompute_velocities(world: &PhysicsWorld, bodies: &mut [RigidBodyData], dt: f32) {
     let sw = world.stopwatch("recompute_velocities");
     for body in bodies {
@@ -116,6 +234,46 @@ pub(crate) fn recompute_velocities(world: &PhysicsWorld, bodies: &mut [RigidBody
     sw.end();
 }
 
+/// Damps the spin and the velocity along the contact plane of the bodies touching something,
+/// according to their `rolling_resistance`. Must be called after `solve_velocities`.
+pub(crate) fn apply_rolling_resistance(
+    world: &PhysicsWorld,
+    penetration_constraints: &[PenetrationConstraint],
+    bodies: &mut [RigidBodyData],
+    dt: f32,
+) {
+    if !bodies.iter().any(|body| body.rolling_resistance() > 0.0) {
+        return;
+    }
+
+    let sw = world.stopwatch("apply_rolling_resistance");
+    // The normal of one contact per body, the sign doesn't matter
+    let mut contact_normals: HashMap<u64, Vec3> = HashMap::new();
+    for constraint in penetration_constraints {
+        if constraint.penetration_depth >= 0.0 {
+            continue;
+        }
+        contact_normals.entry(constraint.a).or_insert(constraint.normal);
+        contact_normals.entry(constraint.b).or_insert(constraint.normal);
+    }
+
+    for body in bodies {
+        if !body.is_dynamic() || body.rolling_resistance() <= 0.0 {
+            continue;
+        }
+        let Some(&normal) = contact_normals.get(&body.id) else {
+            continue;
+        };
+
+        let factor = 1.0 / (1.0 + dt * body.rolling_resistance());
+        let velocity = body.linear_velocity();
+        let normal_velocity = normal * velocity.dot(normal);
+        body.set_linear_velocity(normal_velocity + (velocity - normal_velocity) * factor);
+        body.set_angular_velocity(body.angular_velocity() * factor);
+    }
+    sw.end();
+}
+
 pub(crate) fn solve_velocities(
     world: &PhysicsWorld,
     penetration_constraints: &[PenetrationConstraint],
@@ -199,6 +357,11 @@ pub(crate) fn solve_velocities(
         let w2 =
             constraint.compute_generalized_inverse_mass(body2, &constraint.world_b, &delta_v_dir);
 
+        // Two immovable bodies (static, frozen or massless) can't exchange an impulse
+        if w1 + w2 <= f32::EPSILON {
+            continue;
+        }
+
         // Compute velocity impulse and apply velocity updates (equation 33)
         let p = delta_v / (w1 + w2);
         if body1.is_dynamic() {
@@ -269,10 +432,17 @@ fn get_restitution(
     sub_dt: f32,
 ) -> Vec3 {
     // If normal velocity is small enough, use restitution of 0 to avoid jittering
-    if normal_vel.abs() <= 2.0 * gravity.length() * sub_dt {
+    let threshold = world
+        .restitution_threshold()
+        .max(2.0 * gravity.length() * sub_dt);
+    if normal_vel.abs() <= threshold {
         coefficient = 0.0;
     }
 
+    // A coefficient above 1 would add energy on every bounce
+    coefficient = clamp_restitution(coefficient);
+
+    // The bounce speed can't exceed the approach speed before the solve
     let restitution = normal * (-normal_vel + (-coefficient * pre_solve_normal_vel).min(0.0));
 
     if world.debug {
@@ -284,3 +454,326 @@ fn get_restitution(
 
     restitution
 }
+
+/// Restricts a restitution coefficient to [0, 1], a non-finite coefficient doesn't bounce.
+pub(crate) fn clamp_restitution(coefficient: f32) -> f32 {
+    if coefficient.is_finite() {
+        coefficient.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        engine::test_utils::*,
+        math::{Quat, Vec3},
+        Collider, PhysicsScene, PhysicsWorld, RigidBody, RigidBodyProperties, RigidBodyType,
+        Solver,
+    };
+
+    #[test]
+    fn only_contacts_above_the_restitution_threshold_bounce() {
+        let world = PhysicsWorld::builder()
+            .gravity(Vec3::ZERO)
+            .restitution_velocity_threshold(2.0)
+            .build();
+        let mut scene = PhysicsScene::new(world);
+        let bouncy = RigidBodyProperties::builder()
+            .restitution_coefficient(1.0)
+            .build();
+
+        let floor = RigidBody::builder()
+            .id(1)
+            .position(Vec3::new(0.0, -0.5, 0.0))
+            .body_type(RigidBodyType::Static)
+            .collider_id(0)
+            .properties_id(0)
+            .build();
+        scene.add_body(floor, &bouncy, &Collider::cuboid(1, Vec3::new(40.0, 1.0, 40.0)));
+
+        for (id, x, speed) in [(2, -5.0, 5.0), (3, 5.0, 1.0)] {
+            let mut ball = dynamic_body(id, Vec3::new(x, 0.6, 0.0));
+            ball.linear_velocity = Vec3::new(0.0, -speed, 0.0);
+            scene.add_body(ball, &bouncy, &Collider::sphere(1, 0.5));
+        }
+
+        scene.step_n(30);
+
+        let fast = scene.body(2).unwrap();
+        assert!(fast.linear_velocity().y > 4.0, "fast ball: {}", fast.linear_velocity());
+        let slow = scene.body(3).unwrap();
+        assert!(slow.linear_velocity().y.abs() < 0.1, "slow ball: {}", slow.linear_velocity());
+        assert!((slow.position().y - 0.5).abs() < 0.05, "slow ball at {}", slow.position());
+    }
+
+    #[test]
+    fn scaled_worlds_have_the_same_relative_dynamics() {
+        // Heights of a bouncing ball, in meters, in a world where 1 meter is `scale` units
+        let heights = |scale: f32| {
+            let world = PhysicsWorld::builder()
+                .gravity(Vec3::new(0.0, -9.81 * scale, 0.0))
+                .restitution_velocity_threshold(1.5)
+                .length_unit(scale)
+                .build();
+            let mut scene = PhysicsScene::new(world);
+            let bouncy = RigidBodyProperties::builder()
+                .restitution_coefficient(0.8)
+                .build();
+
+            let floor = RigidBody::builder()
+                .id(1)
+                .position(Vec3::new(0.0, -0.5 * scale, 0.0))
+                .body_type(RigidBodyType::Static)
+                .collider_id(0)
+                .properties_id(0)
+                .build();
+            let floor_size = Vec3::new(40.0, 1.0, 40.0) * scale;
+            scene.add_body(floor, &bouncy, &Collider::cuboid(1, floor_size));
+            let ball = dynamic_body(2, Vec3::new(0.0, 2.0 * scale, 0.0));
+            scene.add_body(ball, &bouncy, &Collider::sphere(1, 0.5 * scale));
+
+            (0..12)
+                .map(|_| {
+                    scene.step_n(10);
+                    scene.body(2).unwrap().position().y / scale
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let meters = heights(1.0);
+        let pixels = heights(100.0);
+        for (m, px) in meters.iter().zip(&pixels) {
+            assert!((m - px).abs() < 0.02, "{:?} != {:?}", meters, pixels);
+        }
+    }
+
+    #[test]
+    fn compliant_contacts_let_a_heavy_body_sink_deeper() {
+        // Penetration of a 10 kg ball resting on the floor
+        let penetration = |world_compliance: f32, body_compliance: f32| {
+            let world = PhysicsWorld::builder()
+                .contact_compliance(world_compliance)
+                .build();
+            let mut scene = PhysicsScene::new(world);
+            add_floor(&mut scene, 1);
+            let properties = RigidBodyProperties::builder()
+                .mass(10.0)
+                .contact_compliance(body_compliance)
+                .build();
+            let ball = dynamic_body(2, Vec3::new(0.0, 0.5, 0.0));
+            scene.add_body(ball, &properties, &Collider::sphere(1, 0.5));
+
+            scene.step_n(120);
+            0.5 - scene.body(2).unwrap().position().y
+        };
+
+        let rigid = penetration(0.0, 0.0);
+        let soft_world = penetration(1e-3, 0.0);
+        let soft_body = penetration(0.0, 1e-3);
+        let softer = penetration(1e-3, 1e-3);
+
+        assert!(rigid < 0.01, "rigid: {rigid}");
+        assert!(
+            soft_world > rigid + 0.02,
+            "world: {soft_world}, rigid: {rigid}"
+        );
+        assert!(
+            soft_body > rigid + 0.02,
+            "body: {soft_body}, rigid: {rigid}"
+        );
+        assert!(softer > soft_world && softer > soft_body, "both: {softer}");
+    }
+
+    #[test]
+    fn over_unit_restitution_never_bounces_above_the_drop_height() {
+        let mut scene = PhysicsScene::new(PhysicsWorld::builder().build());
+        let too_bouncy = RigidBodyProperties::builder()
+            .restitution_coefficient(5.0)
+            .build();
+
+        let floor = RigidBody::builder()
+            .id(1)
+            .position(Vec3::new(0.0, -0.5, 0.0))
+            .body_type(RigidBodyType::Static)
+            .collider_id(0)
+            .properties_id(0)
+            .build();
+        let floor_collider = Collider::cuboid(1, Vec3::new(40.0, 1.0, 40.0));
+        scene.add_body(floor, &too_bouncy, &floor_collider);
+        let drop_height = 3.0;
+        let ball = dynamic_body(2, Vec3::new(0.0, drop_height, 0.0));
+        scene.add_body(ball, &too_bouncy, &Collider::sphere(1, 0.5));
+
+        let mut bounces = 0;
+        let mut falling = true;
+        for _ in 0..600 {
+            scene.step();
+            let ball = scene.body(2).unwrap();
+            assert!(
+                ball.position().y <= drop_height + 1e-3,
+                "ball bounced up to {}",
+                ball.position().y
+            );
+            if falling && ball.linear_velocity().y > 0.0 {
+                bounces += 1;
+            }
+            falling = ball.linear_velocity().y <= 0.0;
+        }
+        assert!(bounces >= 3, "only {} bounces", bounces);
+    }
+
+    #[test]
+    fn tilted_body_with_upright_stiffness_rights_itself() {
+        let mut scene = PhysicsScene::new(PhysicsWorld::builder().gravity(Vec3::ZERO).build());
+        let mut body = dynamic_body(1, Vec3::ZERO);
+        body.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_4);
+        body.upright_stiffness = 5.0;
+        let cube = Collider::cuboid(1, Vec3::new(1.0, 1.0, 1.0));
+        scene.add_body(body, &RigidBodyProperties::builder().build(), &cube);
+        let tilt = |scene: &PhysicsScene| {
+            let up = scene.body(1).unwrap().rotation().rotate(Vec3::Y);
+            up.y.clamp(-1.0, 1.0).acos()
+        };
+
+        // The spring has no damping of its own, the game brakes the spin
+        let step = |scene: &mut PhysicsScene| {
+            let brake = -scene.body(1).unwrap().angular_velocity();
+            scene.step_with_forces([(1, Vec3::ZERO, brake)].into_iter());
+        };
+
+        let mut previous = tilt(&scene);
+        for _ in 0..5 {
+            step(&mut scene);
+            let current = tilt(&scene);
+            assert!(current < previous, "tilt went from {previous} to {current}");
+            previous = current;
+        }
+        assert!(previous > 0.5, "righted too fast, tilt {previous}");
+
+        for _ in 0..175 {
+            step(&mut scene);
+        }
+        assert!(tilt(&scene) < 0.05, "still tilted by {}", tilt(&scene));
+        assert!(scene.body(1).unwrap().angular_velocity().length() < 0.05);
+    }
+
+    #[test]
+    fn deeply_overlapping_spheres_separate_smoothly_with_a_correction_limit() {
+        // Two 1 m spheres spawned 0.2 m apart, overlapping by 0.8 m
+        let spawn = |max_correction: f32| {
+            let world = PhysicsWorld::builder()
+                .gravity(Vec3::ZERO)
+                .max_correction_per_substep(max_correction)
+                .build();
+            let mut scene = PhysicsScene::new(world);
+            add_sphere(&mut scene, 1, Vec3::new(-0.1, 0.0, 0.0), 0.5);
+            add_sphere(&mut scene, 2, Vec3::new(0.1, 0.0, 0.0), 0.5);
+            scene
+        };
+        let gap = |scene: &PhysicsScene| {
+            (scene.body(2).unwrap().position() - scene.body(1).unwrap().position()).length()
+        };
+        let speed = |scene: &PhysicsScene| {
+            (scene.body(2).unwrap().linear_velocity() - scene.body(1).unwrap().linear_velocity())
+                .length()
+        };
+
+        // Without a limit the whole overlap is pushed out in the first substep
+        let mut unlimited = spawn(0.0);
+        unlimited.step();
+        let jump = gap(&unlimited) - 0.2;
+        assert!(jump > 0.7, "unlimited jump: {jump}");
+
+        // At most 20 substeps of 2 mm per step
+        let mut limited = spawn(0.002);
+        let mut previous = gap(&limited);
+        for _ in 0..60 {
+            limited.step();
+            let current = gap(&limited);
+            let step = current - previous;
+            assert!((-1e-4..0.05).contains(&step), "gap {previous} -> {current}");
+            previous = current;
+        }
+        assert!(previous > 1.0 - 1e-3, "still overlapping, gap {previous}");
+        assert!(speed(&limited) < 0.5, "separating at {}", speed(&limited));
+    }
+
+    #[test]
+    fn jacobi_and_gauss_seidel_both_hold_a_stack_of_boxes() {
+        // Heights of five 1 m boxes stacked on the floor, after two seconds
+        let settle = |solver: Solver, position_iterations: u32| {
+            let world = PhysicsWorld::builder()
+                .solver(solver)
+                .position_iterations(position_iterations)
+                .build();
+            let mut scene = PhysicsScene::new(world);
+            add_floor(&mut scene, 1);
+            let properties = RigidBodyProperties::builder().build();
+            let cube = Collider::cuboid(1, Vec3::new(1.0, 1.0, 1.0));
+            for level in 0..5 {
+                let position = Vec3::new(0.0, 0.5 + level as f32, 0.0);
+                assert!(scene.add_body(dynamic_body(2 + level, position), &properties, &cube));
+            }
+
+            scene.step_n(120);
+            (0..5)
+                .map(|level| {
+                    let position = scene.body(2 + level).unwrap().position();
+                    assert!(
+                        position.x.abs() < 0.05 && position.z.abs() < 0.05,
+                        "{solver:?}: box {level} slid to {position}"
+                    );
+                    position.y
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let gauss_seidel = settle(Solver::GaussSeidel, 1);
+        let jacobi = settle(Solver::Jacobi, 4);
+        for (level, (gs, j)) in gauss_seidel.iter().zip(&jacobi).enumerate() {
+            let rest = 0.5 + level as f32;
+            assert!((gs - rest).abs() < 0.05, "gauss-seidel: {gauss_seidel:?}");
+            assert!((j - rest).abs() < 0.05, "jacobi: {jacobi:?}");
+        }
+    }
+
+    #[test]
+    fn rolling_resistance_slows_a_rolling_ball_until_it_stops() {
+        let mut scene = scene_with_floor(1);
+        // Rolling without slipping along X, 0.5 m from the floor
+        for (id, z, rolling_resistance) in [(2, -2.0, 0.0), (3, 2.0, 2.0)] {
+            let mut ball = dynamic_body(id, Vec3::new(0.0, 0.5, z));
+            ball.linear_velocity = Vec3::new(3.0, 0.0, 0.0);
+            ball.angular_velocity = Vec3::new(0.0, 0.0, -6.0);
+            let properties = RigidBodyProperties::builder()
+                .rolling_resistance(rolling_resistance)
+                .build();
+            assert!(scene.add_body(ball, &properties, &Collider::sphere(1, 0.5)));
+        }
+        let speed = |scene: &PhysicsScene, id| scene.body(id).unwrap().linear_velocity().length();
+
+        let mut previous = speed(&scene, 3);
+        for _ in 0..60 {
+            scene.step();
+            let current = speed(&scene, 3);
+            assert!(
+                current <= previous + 1e-3,
+                "sped up from {previous} to {current}"
+            );
+            previous = current;
+        }
+        assert!(
+            previous < 0.5 * speed(&scene, 2),
+            "resisted: {previous}, free: {}",
+            speed(&scene, 2)
+        );
+
+        scene.step_n(540);
+        let ball = scene.body(3).unwrap();
+        let speed = ball.linear_velocity().length();
+        assert!(speed < 0.01, "still rolling at {speed}");
+        assert!(ball.angular_velocity().length() < 0.02);
+    }
+}
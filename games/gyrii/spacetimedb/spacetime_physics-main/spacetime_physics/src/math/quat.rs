@@ -54,6 +54,41 @@ impl Quat {
         }
     }
 
+    /// Rotation of `angle` radians around the X axis.
+    pub fn from_rotation_x(angle: f32) -> Self {
+        Self::from_axis_angle(Vec3::X, angle)
+    }
+
+    /// Rotation of `angle` radians around the Y axis.
+    pub fn from_rotation_y(angle: f32) -> Self {
+        Self::from_axis_angle(Vec3::Y, angle)
+    }
+
+    /// Rotation of `angle` radians around the Z axis.
+    pub fn from_rotation_z(angle: f32) -> Self {
+        Self::from_axis_angle(Vec3::Z, angle)
+    }
+
+    /// The axis of rotation of a unit quaternion, `Vec3::Y` for the identity.
+    pub fn axis(&self) -> Vec3 {
+        let s = (1.0 - self.w * self.w).max(0.0).sqrt();
+        if s > 1e-6 {
+            Vec3::new(self.x / s, self.y / s, self.z / s)
+        } else {
+            Vec3::Y
+        }
+    }
+
+    /// The angle of rotation of a unit quaternion in radians, between 0 and 2π.
+    pub fn angle(&self) -> f32 {
+        2.0 * self.w.clamp(-1.0, 1.0).acos()
+    }
+
+    /// The inverse of `from_axis_angle`.
+    pub fn to_axis_angle(&self) -> (Vec3, f32) {
+        (self.axis(), self.angle())
+    }
+
     pub fn from_euler_angles_deg(x_deg: f32, y_deg: f32, z_deg: f32) -> Self {
         let (x, y, z) = (
             x_deg.to_radians(),
@@ -106,6 +141,23 @@ impl Quat {
         }
     }
 
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite() && self.w.is_finite()
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.x.is_nan() || self.y.is_nan() || self.z.is_nan() || self.w.is_nan()
+    }
+
+    /// Returns `fallback` if any component is NaN or infinite, a partly valid rotation means nothing.
+    pub fn sanitize(self, fallback: Quat) -> Self {
+        if self.is_finite() {
+            self
+        } else {
+            fallback
+        }
+    }
+
     pub fn inverse(&self) -> Self {
         Self {
             x: -self.x,
@@ -115,6 +167,49 @@ impl Quat {
         }
     }
 
+    pub fn dot(&self, other: Quat) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    /// Spherical linear interpolation, always taking the shortest path.
+    pub fn slerp(self, other: Quat, t: f32) -> Quat {
+        let mut dot = self.dot(other);
+        let other = if dot < 0.0 {
+            dot = -dot;
+            -1.0 * other
+        } else {
+            other
+        };
+
+        // Fall back to a normalized lerp for nearly identical rotations
+        if dot > 0.9995 {
+            return (self + (other - self) * t).normalize();
+        }
+
+        let theta = dot.acos();
+        let sin_theta = theta.sin();
+        let wa = ((1.0 - t) * theta).sin() / sin_theta;
+        let wb = (t * theta).sin() / sin_theta;
+
+        self * wa + other * wb
+    }
+
+    /// Returns the angle in radians between two rotations, in `[0, PI]`.
+    pub fn angle_between(&self, other: Quat) -> f32 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    /// Rotates towards `target` by at most `max_radians`.
+    /// Returns `target` when it is already within `max_radians`.
+    pub fn rotate_towards(self, target: Quat, max_radians: f32) -> Quat {
+        let max_radians = max_radians.max(0.0);
+        let angle = self.angle_between(target);
+        if angle <= max_radians || angle <= 1e-6 {
+            return target;
+        }
+        self.slerp(target, max_radians / angle)
+    }
+
     pub fn xyz(&self) -> Vec3 {
         Vec3::new(self.x, self.y, self.z)
     }
@@ -310,3 +405,54 @@ impl Display for Quat {
         write!(f, "Quat({}, {}, {}, {})", self.x, self.y, self.z, self.w)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate_towards_converges_without_overshooting() {
+        let target = Quat::from_axis_angle(Vec3::Y, 2.0);
+        let step = 0.1;
+
+        let mut current = Quat::IDENTITY;
+        let mut remaining = current.angle_between(target);
+        let mut steps = 0;
+        while current != target {
+            let next = current.rotate_towards(target, step);
+            assert!(current.angle_between(next) <= step + 1e-3);
+            let next_remaining = next.angle_between(target);
+            assert!(next_remaining <= remaining, "moved away from the target");
+
+            current = next;
+            remaining = next_remaining;
+            steps += 1;
+            assert!(steps <= 25, "did not reach the target");
+        }
+    }
+
+    #[test]
+    fn rotate_towards_returns_target_within_max_angle() {
+        let target = Quat::from_axis_angle(Vec3::Y, 0.05);
+        assert_eq!(Quat::IDENTITY.rotate_towards(target, 0.1), target);
+    }
+
+    #[test]
+    fn quarter_turn_around_y_takes_x_to_minus_z() {
+        let rotated = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2).rotate(Vec3::X);
+        assert!(
+            (rotated - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-6,
+            "{rotated:?}"
+        );
+    }
+
+    #[test]
+    fn axis_and_angle_round_trip() {
+        let axis = Vec3::new(1.0, 2.0, -2.0).normalize();
+        let (back_axis, back_angle) = Quat::from_axis_angle(axis, 1.3).to_axis_angle();
+        assert!((back_axis - axis).length() < 1e-5, "{back_axis:?}");
+        assert!((back_angle - 1.3).abs() < 1e-5, "{back_angle}");
+
+        assert_eq!(Quat::IDENTITY.to_axis_angle(), (Vec3::Y, 0.0));
+    }
+}
@@ -137,6 +137,24 @@ impl Vec3 {
         }
     }
 
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.x.is_nan() || self.y.is_nan() || self.z.is_nan()
+    }
+
+    /// Replaces NaN or infinite components with the matching component of `fallback`.
+    pub fn sanitize(self, fallback: Vec3) -> Self {
+        let pick = |value: f32, fallback: f32| if value.is_finite() { value } else { fallback };
+        Self {
+            x: pick(self.x, fallback.x),
+            y: pick(self.y, fallback.y),
+            z: pick(self.z, fallback.z),
+        }
+    }
+
     pub fn min(self, other: Vec3) -> Self {
         Self {
             x: self.x.min(other.x),
@@ -144,6 +162,25 @@ impl Vec3 {
             z: self.z.min(other.z),
         }
     }
+
+    /// Moves towards `target` by at most `max_delta`, snapping to it when within range.
+    pub fn move_towards(self, target: Vec3, max_delta: f32) -> Self {
+        let delta = target - self;
+        let distance = delta.length();
+        if distance <= max_delta || distance < 1e-5 {
+            return target;
+        }
+        self + delta * (max_delta / distance)
+    }
+
+    /// Scales the vector down to `max` length, shorter vectors are returned unchanged.
+    pub fn clamp_length(self, max: f32) -> Self {
+        let length_squared = self.length_squared();
+        if length_squared <= max * max || length_squared < 1e-10 {
+            return self;
+        }
+        self * (max / length_squared.sqrt())
+    }
 }
 
 impl Hash for Vec3 {
@@ -540,3 +577,31 @@ impl Display for Vec3 {
         write!(f, "Vec3({}, {}, {})", self.x, self.y, self.z)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Vec3;
+
+    #[test]
+    fn clamp_length_shortens_only_long_vectors() {
+        assert_eq!(Vec3::new(3.0, 4.0, 0.0).clamp_length(2.5), Vec3::new(1.5, 2.0, 0.0));
+        assert_eq!(Vec3::new(0.0, 1.0, 1.0).clamp_length(2.0), Vec3::new(0.0, 1.0, 1.0));
+        assert_eq!(Vec3::ZERO.clamp_length(0.0), Vec3::ZERO);
+    }
+
+    #[test]
+    fn move_towards_lands_exactly_on_target() {
+        let target = Vec3::new(3.0, 4.0, 0.0);
+
+        let first = Vec3::ZERO.move_towards(target, 2.0);
+        assert!((first.length() - 2.0).abs() < 1e-6);
+
+        // The last step is shorter than max_delta and snaps onto the target.
+        let mut position = first;
+        for _ in 0..2 {
+            position = position.move_towards(target, 2.0);
+        }
+        assert_eq!(position, target);
+        assert_eq!(target.move_towards(target, 2.0), target);
+    }
+}
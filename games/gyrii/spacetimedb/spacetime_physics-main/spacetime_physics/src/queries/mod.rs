@@ -1,3 +1,7 @@
+mod point;
 mod ray;
+mod slide;
 
+pub use point::{closest_point, closest_point_on};
 pub use ray::raycast_all;
+pub use slide::{slide_move, MAX_SLIDE_PLANES};
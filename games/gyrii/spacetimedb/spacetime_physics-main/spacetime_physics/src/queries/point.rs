@@ -0,0 +1,52 @@
+use parry3d::na::Isometry3;
+use spacetimedb::ReducerContext;
+
+use crate::{math::Vec3, Collider, RigidBody, RigidBodyId, ShapeWrapper};
+
+/// The point of a body's collider closest to `point`, in world space.
+/// If `solid` is true a point inside the collider is returned as is, otherwise it is projected
+/// on the surface. Returns `None` if the body or its collider doesn't exist in the world.
+pub fn closest_point(
+    ctx: &ReducerContext,
+    world_id: u64,
+    body_id: RigidBodyId,
+    point: Vec3,
+    solid: bool,
+) -> Option<Vec3> {
+    let body = RigidBody::find(ctx, body_id).filter(|body| body.world_id == world_id)?;
+    let collider = Collider::find(ctx, body.collider_id)?;
+    Some(closest_point_on(&body, &collider, point, solid))
+}
+
+/// `closest_point` for an already loaded body and collider.
+pub fn closest_point_on(body: &RigidBody, collider: &Collider, point: Vec3, solid: bool) -> Vec3 {
+    let isometry: Isometry3<f32> = body.into();
+    ShapeWrapper::from(collider).project_point(&isometry, point, solid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::closest_point_on;
+    use crate::{engine::test_utils::dynamic_body, math::Vec3, Collider};
+
+    #[test]
+    fn point_outside_a_sphere_projects_on_its_surface() {
+        let body = dynamic_body(1, Vec3::new(1.0, 0.0, 0.0));
+        let sphere = Collider::sphere(1, 0.5);
+
+        let closest = closest_point_on(&body, &sphere, Vec3::new(1.0, 3.0, 0.0), true);
+        assert!(
+            (closest - Vec3::new(1.0, 0.5, 0.0)).length() < 1e-5,
+            "{closest:?}"
+        );
+
+        // Inside, a solid shape keeps the point and a hollow one projects it on the surface
+        let inside = Vec3::new(1.2, 0.0, 0.0);
+        assert_eq!(closest_point_on(&body, &sphere, inside, true), inside);
+        let surface = closest_point_on(&body, &sphere, inside, false);
+        assert!(
+            (surface - Vec3::new(1.5, 0.0, 0.0)).length() < 1e-5,
+            "{surface:?}"
+        );
+    }
+}
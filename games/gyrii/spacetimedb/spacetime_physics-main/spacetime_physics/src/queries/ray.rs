@@ -9,6 +9,7 @@ use crate::{math::Vec3, Collider, RigidBodyData};
 pub struct RacyCastHit {
     pub distance: f32,
     pub position: Vec3,
+    /// Points away from the hit surface, like `RayCastHit::normal`.
     pub normal: Vec3,
     pub rigid_body_id: u64,
 }
@@ -31,8 +32,9 @@ pub fn raycast_all(
     max_distance: f32,
     solid: bool,
 ) -> impl Iterator<Item = RacyCastHit> {
-    let colliders = Collider::all(ctx, world_id);
-    let bodies = RigidBodyData::collect(ctx, world_id, &colliders).into_iter();
+    let colliders = Collider::map_in_world(ctx, world_id);
+    let (bodies, _) = RigidBodyData::collect(ctx, world_id, &colliders);
+    let bodies = bodies.into_iter();
     raycast_all_with_rigid_bodies(bodies, origin, direction, max_distance, solid)
 }
 
@@ -58,3 +60,60 @@ pub fn raycast_all_with_rigid_bodies(
             })
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::raycast_all_with_rigid_bodies;
+    use crate::{
+        engine::test_utils::dynamic_body, math::Vec3, Collider, RayCast, RigidBodyData,
+        RigidBodyProperties, RigidBodyType,
+    };
+
+    #[test]
+    fn from_to_ray_hits_the_target_and_nothing_beyond() {
+        let cast = |ray: &RayCast| {
+            let properties = RigidBodyProperties::builder().build();
+            let bodies = [(1, 5.0), (2, 8.0)].map(|(id, x)| {
+                let body = dynamic_body(id, Vec3::new(x, 0.0, 0.0));
+                RigidBodyData::new(body, &properties, &Collider::sphere(1, 0.5))
+            });
+            raycast_all_with_rigid_bodies(
+                bodies.into_iter(),
+                ray.origin,
+                ray.direction,
+                ray.max_distance,
+                ray.solid,
+            )
+            .collect::<Vec<_>>()
+        };
+
+        let aimed = RayCast::from_to(1, Vec3::ZERO, Vec3::new(5.0, 0.0, 0.0), true);
+        let hits = cast(&aimed);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].rigid_body_id, 1);
+        assert!((hits[0].distance - 4.5).abs() < 1e-4, "hit at {}", hits[0]);
+
+        let mut short = aimed.clone();
+        short.set_from_to(Vec3::ZERO, Vec3::new(4.4, 0.0, 0.0));
+        assert!((short.max_distance - 4.4).abs() < 1e-6);
+        assert!(cast(&short).is_empty());
+    }
+
+    #[test]
+    fn ray_cast_down_onto_a_floor_gets_an_up_normal() {
+        let mut floor = dynamic_body(1, Vec3::new(0.0, -0.5, 0.0));
+        floor.body_type = RigidBodyType::Static;
+        let floor = RigidBodyData::new(
+            floor,
+            &RigidBodyProperties::builder().build(),
+            &Collider::cuboid(1, Vec3::new(10.0, 1.0, 10.0)),
+        );
+
+        let origin = Vec3::new(3.0, 5.0, 0.0);
+        let hit = raycast_all_with_rigid_bodies([floor].into_iter(), origin, -Vec3::Y, 10.0, true)
+            .next()
+            .unwrap();
+        assert!((hit.distance - 5.0).abs() < 1e-4, "{hit}");
+        assert!((hit.normal - Vec3::Y).length() < 1e-4, "{hit}");
+    }
+}
@@ -0,0 +1,86 @@
+use crate::math::Vec3;
+
+/// Contact planes considered by `slide_move`, more than this and the extra ones are ignored.
+pub const MAX_SLIDE_PLANES: usize = 4;
+
+/// Velocities into a plane smaller than this are treated as parallel to it.
+const SLIDE_EPSILON: f32 = 1e-5;
+
+/// Moves `position` by `velocity` for `dt`, sliding along the contact planes given by their
+/// normals instead of going through them. This is the core of a kinematic character step.
+///
+/// The velocity is projected on every plane it moves into. When two planes form a corner the
+/// body slides along their crease, and it stops when a third plane blocks the crease.
+/// Returns the new position and the velocity left after sliding.
+pub fn slide_move(position: Vec3, velocity: Vec3, normals: &[Vec3], dt: f32) -> (Vec3, Vec3) {
+    let planes: Vec<Vec3> = normals
+        .iter()
+        .take(MAX_SLIDE_PLANES)
+        .map(|normal| normal.normalize_or_zero())
+        .filter(|normal| *normal != Vec3::ZERO)
+        .collect();
+
+    let mut velocity = velocity;
+    for (i, &plane) in planes.iter().enumerate() {
+        if velocity.dot(plane) >= -SLIDE_EPSILON {
+            continue;
+        }
+
+        let mut clipped = velocity.project_onto_plane(plane);
+        for (j, &other) in planes.iter().enumerate() {
+            if j == i || clipped.dot(other) >= -SLIDE_EPSILON {
+                continue;
+            }
+
+            // Sliding along this plane pushes into another one, follow their crease
+            let crease = plane.cross(other).normalize_or_zero();
+            clipped = crease * crease.dot(velocity);
+
+            let blocked = planes
+                .iter()
+                .enumerate()
+                .any(|(k, &third)| k != i && k != j && clipped.dot(third) < -SLIDE_EPSILON);
+            if blocked {
+                return (position, Vec3::ZERO);
+            }
+            break;
+        }
+        velocity = clipped;
+    }
+
+    (position + velocity * dt, velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slide_move;
+    use crate::math::Vec3;
+
+    #[test]
+    fn velocity_into_a_45_degree_wall_slides_along_it() {
+        // A wall through the origin, facing up and back towards -x, the body starts against it
+        let normal = Vec3::new(-1.0, 1.0, 0.0).normalize();
+        let start = Vec3::new(0.0, 0.0, 0.5);
+
+        let mut position = start;
+        for _ in 0..10 {
+            let (next, velocity) = slide_move(position, Vec3::new(1.0, 0.0, 0.0), &[normal], 0.1);
+            assert!(velocity.dot(normal).abs() < 1e-5, "velocity {velocity:?}");
+            assert!((velocity - Vec3::new(0.5, 0.5, 0.0)).length() < 1e-5);
+            position = next;
+        }
+
+        // Half the speed goes up the slope, none goes through it
+        assert!(
+            (position - Vec3::new(0.5, 0.5, 0.5)).length() < 1e-5,
+            "{position:?}"
+        );
+        assert!(position.dot(normal) > -1e-5);
+
+        // Pushed into the crease between two 45° walls, the body stops
+        let other = Vec3::new(1.0, 1.0, 0.0).normalize();
+        let (stuck, velocity) = slide_move(start, Vec3::new(0.0, -1.0, 0.0), &[normal, other], 0.1);
+        assert!(velocity.length() < 1e-5, "velocity {velocity:?}");
+        assert!((stuck - start).length() < 1e-5);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, f32::consts::PI, fmt::Display};
 
 use spacetimedb::{table, ReducerContext, SpacetimeType, Table};
 
@@ -34,6 +34,10 @@ pub struct Collider {
     pub point_b: Vec3,
     pub point_c: Vec3,
     pub collider_type: ColliderType,
+    /// Extra distance around the shape at which contacts are generated, on top of the world
+    /// precision. Bodies are kept apart by the sum of their margins, which helps small fast
+    /// objects from tunneling.
+    pub margin: f32,
 }
 
 impl Collider {
@@ -41,19 +45,61 @@ impl Collider {
         ctx.db.physics_colliders().insert(self)
     }
 
+    /// Returns a collider of the same world with the same shape if there is one, so bodies
+    /// with identical shapes share a single row. Otherwise inserts this collider.
+    pub fn find_or_insert(self, ctx: &ReducerContext) -> Self {
+        let existing = Self::all_in_world(ctx, self.world_id);
+        self.find_or_insert_with(existing, |collider| collider.insert(ctx))
+    }
+
+    /// `find_or_insert` over the `existing` colliders, with a custom `insert` which returns
+    /// the row with its assigned id.
+    pub fn find_or_insert_with(
+        self,
+        existing: impl IntoIterator<Item = Self>,
+        insert: impl FnOnce(Self) -> Self,
+    ) -> Self {
+        existing
+            .into_iter()
+            .find(|collider| collider.same_shape(&self))
+            .unwrap_or_else(|| insert(self))
+    }
+
+    /// True if both colliders have the same shape, margin and world, whatever their ids.
+    pub fn same_shape(&self, other: &Self) -> bool {
+        Self { id: other.id, ..*self } == *other
+    }
+
     pub fn find(ctx: &ReducerContext, id: u64) -> Option<Self> {
         ctx.db.physics_colliders().id().find(id)
     }
 
-    pub fn all(ctx: &ReducerContext, world_id: u64) -> HashMap<u64, Self> {
-        ctx.db
-            .physics_colliders()
-            .world_id()
-            .filter(world_id)
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.physics_colliders().world_id().filter(world_id)
+    }
+
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    /// The colliders of a world by id, as the engine looks them up.
+    pub fn map_in_world(ctx: &ReducerContext, world_id: u64) -> HashMap<u64, Self> {
+        Self::map_by_id(Self::all_in_world(ctx, world_id))
+    }
+
+    /// Same as `map_in_world`, over already loaded colliders.
+    pub fn map_by_id(colliders: impl IntoIterator<Item = Self>) -> HashMap<u64, Self> {
+        colliders
+            .into_iter()
             .map(|collider| (collider.id, collider))
             .collect()
     }
 
+    #[deprecated(note = "use `map_in_world`, `all_in_world` or `collect_in_world`")]
+    pub fn all(ctx: &ReducerContext, world_id: u64) -> HashMap<u64, Self> {
+        Self::map_in_world(ctx, world_id)
+    }
+
     pub fn update(self, ctx: &ReducerContext) -> Self {
         ctx.db.physics_colliders().id().update(self)
     }
@@ -71,6 +117,11 @@ impl Collider {
         self
     }
 
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self
+    }
+
     pub fn sphere(world_id: u64, radius: f32) -> Self {
         Self {
             world_id,
@@ -128,6 +179,16 @@ impl Collider {
         }
     }
 
+    /// The end points of a capsule's segment, `height` is the distance between them along Y.
+    /// This is the shape used for collisions, `point_a` and `point_b` are ignored for capsules.
+    pub fn capsule_points(&self) -> (Vec3, Vec3) {
+        let half_height = self.height / 2.0;
+        (
+            Vec3::new(0.0, -half_height, 0.0),
+            Vec3::new(0.0, half_height, 0.0),
+        )
+    }
+
     pub fn triangle(world_id: u64, point_a: Vec3, point_b: Vec3, point_c: Vec3) -> Self {
         Self {
             world_id,
@@ -139,6 +200,38 @@ impl Collider {
         }
     }
 
+    /// Planes and triangles have no volume.
+    pub fn volume(&self) -> f32 {
+        let r = self.radius;
+        let h = self.height;
+        match self.collider_type {
+            ColliderType::Plane | ColliderType::Triangle => 0.0,
+            ColliderType::Sphere => (4.0 / 3.0) * PI * r * r * r,
+            ColliderType::Cuboid => self.size.x * self.size.y * self.size.z,
+            ColliderType::Cylinder => PI * r * r * h,
+            ColliderType::Cone => PI * r * r * h / 3.0,
+            // Cylinder of length `height` capped by two half spheres
+            ColliderType::Capsule => PI * r * r * h + (4.0 / 3.0) * PI * r * r * r,
+        }
+    }
+
+    /// Planes and triangles are treated as degenerate and return 0.
+    pub fn surface_area(&self) -> f32 {
+        let r = self.radius;
+        let h = self.height;
+        match self.collider_type {
+            ColliderType::Plane | ColliderType::Triangle => 0.0,
+            ColliderType::Sphere => 4.0 * PI * r * r,
+            ColliderType::Cuboid => {
+                let s = self.size;
+                2.0 * (s.x * s.y + s.y * s.z + s.x * s.z)
+            }
+            ColliderType::Cylinder => 2.0 * PI * r * (r + h),
+            ColliderType::Cone => PI * r * (r + (r * r + h * h).sqrt()),
+            ColliderType::Capsule => 2.0 * PI * r * h + 4.0 * PI * r * r,
+        }
+    }
+
     pub fn inertia_tensor(&self, mass: f32) -> Mat3 {
         match self.collider_type {
             ColliderType::Plane => Mat3::ZERO,
@@ -147,7 +240,8 @@ impl Collider {
             ColliderType::Cylinder => cylinder_inertia_tensor(mass, self.radius, self.height),
             ColliderType::Cone => cone_inertia_tensor(mass, self.radius, self.height),
             ColliderType::Capsule => {
-                capsule_inertia_tensor(mass, self.radius, self.point_a, self.point_b)
+                let (point_a, point_b) = self.capsule_points();
+                capsule_inertia_tensor(mass, self.radius, point_a, point_b)
             }
             ColliderType::Triangle => {
                 triangle_inertia_tensor(mass, self.point_a, self.point_b, self.point_c)
@@ -172,8 +266,8 @@ impl Display for Collider {
             }
             ColliderType::Capsule => write!(
                 f,
-                "Capsule(radius: {}, point_a: {}, point_b: {})",
-                self.radius, self.point_a, self.point_b
+                "Capsule(radius: {}, height: {})",
+                self.radius, self.height
             ),
             ColliderType::Triangle => write!(
                 f,
@@ -224,3 +318,90 @@ fn triangle_inertia_tensor(mass: f32, point_a: Vec3, point_b: Vec3, point_c: Vec
     let factor = (1.0 / 6.0) * mass * area * area;
     Mat3::from_diagonal(Vec3::splat(factor))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::Collider;
+    use crate::math::Vec3;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn unit_sphere_volume_and_area() {
+        let sphere = Collider::sphere(1, 1.0);
+        assert_close(sphere.volume(), 4.0 / 3.0 * PI);
+        assert_close(sphere.surface_area(), 4.0 * PI);
+    }
+
+    #[test]
+    fn unit_cube_volume_and_area() {
+        let cube = Collider::cuboid(1, Vec3::new(1.0, 1.0, 1.0));
+        assert_close(cube.volume(), 1.0);
+        assert_close(cube.surface_area(), 6.0);
+    }
+
+    #[test]
+    fn capsule_is_a_cylinder_plus_a_sphere() {
+        let capsule = Collider::capsule(1, 0.5, 2.0);
+        let cylinder = Collider::cylinder(1, 0.5, 2.0);
+        let sphere = Collider::sphere(1, 0.5);
+        assert_close(capsule.volume(), cylinder.volume() + sphere.volume());
+    }
+
+    #[test]
+    fn map_and_list_forms_hold_the_same_colliders() {
+        let colliders: Vec<Collider> = [
+            Collider::sphere(1, 0.5),
+            Collider::cuboid(1, Vec3::new(1.0, 2.0, 3.0)),
+            Collider::capsule(1, 0.5, 1.0),
+        ]
+        .into_iter()
+        .zip(1..)
+        .map(|(collider, id)| Collider { id, ..collider })
+        .collect();
+
+        let map = Collider::map_by_id(colliders.clone());
+
+        assert_eq!(map.len(), colliders.len());
+        for collider in &colliders {
+            assert_eq!(map.get(&collider.id), Some(collider));
+        }
+    }
+
+    #[test]
+    fn capsule_points_are_half_the_height_apart_from_the_center() {
+        let (a, b) = Collider::capsule(1, 0.25, 1.5).capsule_points();
+        assert_eq!(a, Vec3::new(0.0, -0.75, 0.0));
+        assert_eq!(b, Vec3::new(0.0, 0.75, 0.0));
+    }
+
+    #[test]
+    fn identical_shapes_share_one_collider_row() {
+        let mut table: Vec<Collider> = Vec::new();
+        let mut find_or_insert = |collider: Collider| {
+            let existing = table.clone();
+            collider.find_or_insert_with(existing, |collider| {
+                let inserted = Collider {
+                    id: table.len() as u64 + 1,
+                    ..collider
+                };
+                table.push(inserted);
+                inserted
+            })
+        };
+
+        let first = find_or_insert(Collider::sphere(1, 0.5));
+        let second = find_or_insert(Collider::sphere(1, 0.5));
+        let larger = find_or_insert(Collider::sphere(1, 0.6));
+        let other_world = find_or_insert(Collider::sphere(2, 0.5));
+
+        assert_eq!(second.id, first.id);
+        assert_ne!(larger.id, first.id);
+        assert_ne!(other_world.id, first.id);
+        assert_eq!(table.len(), 3);
+    }
+}
@@ -0,0 +1,178 @@
+use std::{collections::HashSet, fmt::Display};
+
+use spacetimedb::{table, ReducerContext, SpacetimeType, Table};
+
+use super::RigidBodyId;
+
+pub type CollisionEventId = u64;
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionPhase {
+    /// The bodies started touching during this step.
+    Started,
+    /// The bodies were touching during the previous step and still are.
+    Persisted,
+    /// The bodies stopped touching during this step.
+    Stopped,
+}
+
+impl CollisionPhase {
+    /// The phase of a pair during this step, from its phase during the previous step (`None`
+    /// without an event) and whether it touches now. `None` means the event is removed.
+    pub fn advance(previous: Option<Self>, touching: bool) -> Option<Self> {
+        match (previous, touching) {
+            // A pair touching again right after it stopped starts over.
+            (None, true) | (Some(Self::Stopped), true) => Some(Self::Started),
+            (Some(_), true) => Some(Self::Persisted),
+            (None, false) | (Some(Self::Stopped), false) => None,
+            (Some(_), false) => Some(Self::Stopped),
+        }
+    }
+}
+
+/// A contact between two rigid bodies, tracked across steps.
+/// There is at most one row per pair of bodies, the row is removed the step after it was
+/// reported as `Stopped`.
+#[table(name = physics_collision_events, public)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionEvent {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+    #[index(btree)]
+    pub world_id: u64,
+
+    /// The lower of the two rigid body ids.
+    pub body_a: RigidBodyId,
+
+    /// The higher of the two rigid body ids.
+    pub body_b: RigidBodyId,
+
+    pub phase: CollisionPhase,
+}
+
+impl CollisionEvent {
+    pub fn insert(self, ctx: &ReducerContext) -> Self {
+        ctx.db.physics_collision_events().insert(self)
+    }
+
+    pub fn find(ctx: &ReducerContext, id: u64) -> Option<Self> {
+        ctx.db.physics_collision_events().id().find(id)
+    }
+
+    pub fn update(self, ctx: &ReducerContext) -> Self {
+        ctx.db.physics_collision_events().id().update(self)
+    }
+
+    pub fn delete(&self, ctx: &ReducerContext) {
+        ctx.db.physics_collision_events().id().delete(self.id);
+    }
+
+    pub fn delete_by_id(ctx: &ReducerContext, id: u64) {
+        ctx.db.physics_collision_events().id().delete(id);
+    }
+
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.physics_collision_events().world_id().filter(world_id)
+    }
+
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    #[deprecated(note = "use `all_in_world` or `collect_in_world`")]
+    pub fn all(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        Self::all_in_world(ctx, world_id)
+    }
+
+    pub fn involves(&self, body_id: RigidBodyId) -> bool {
+        self.body_a == body_id || self.body_b == body_id
+    }
+
+    /// Diffs the pairs touching during this step against the events of the previous step.
+    /// `current_pairs` must contain `(lower_id, higher_id)` tuples.
+    pub fn sync(
+        ctx: &ReducerContext,
+        world_id: u64,
+        current_pairs: &HashSet<(RigidBodyId, RigidBodyId)>,
+    ) {
+        let mut seen = HashSet::new();
+
+        for event in Self::collect_in_world(ctx, world_id) {
+            let pair = (event.body_a, event.body_b);
+            let touching = current_pairs.contains(&pair);
+            if touching {
+                seen.insert(pair);
+            }
+
+            match CollisionPhase::advance(Some(event.phase), touching) {
+                Some(phase) if phase != event.phase => {
+                    Self { phase, ..event }.update(ctx);
+                }
+                Some(_) => {}
+                None => event.delete(ctx),
+            }
+        }
+
+        for &(body_a, body_b) in current_pairs.difference(&seen) {
+            Self {
+                id: 0,
+                world_id,
+                body_a,
+                body_b,
+                phase: CollisionPhase::Started,
+            }
+            .insert(ctx);
+        }
+    }
+}
+
+impl Display for CollisionEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CollisionEvent(id: {}, world_id: {}, body_a: {}, body_b: {}, phase: {:?})",
+            self.id, self.world_id, self.body_a, self.body_b, self.phase
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CollisionPhase::{self, *};
+    use crate::{engine::test_utils::*, math::Vec3};
+
+    #[test]
+    fn resting_contact_starts_once_then_persists() {
+        // The ball starts resting on the floor, the pair touches every step
+        let mut scene = scene_with_floor(1);
+        add_sphere(&mut scene, 2, Vec3::new(0.0, 0.5, 0.0), 0.5);
+
+        let mut phase = None;
+        let mut reported = Vec::new();
+        for _ in 0..4 {
+            scene.step();
+            phase = CollisionPhase::advance(phase, scene.contact_pairs().contains(&(1, 2)));
+            reported.push(phase);
+        }
+
+        assert_eq!(
+            reported,
+            vec![Some(Started), Some(Persisted), Some(Persisted), Some(Persisted)]
+        );
+    }
+
+    #[test]
+    fn separated_pair_stops_then_is_removed() {
+        let mut phase = None;
+        let reported: Vec<_> = [true, false, false, true]
+            .into_iter()
+            .map(|touching| {
+                phase = CollisionPhase::advance(phase, touching);
+                phase
+            })
+            .collect();
+
+        assert_eq!(reported, vec![Some(Started), Some(Stopped), None, Some(Started)]);
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt::Display;
+
+use bon::{builder, Builder};
+use spacetimedb::{table, ReducerContext, Table};
+
+use crate::math::Vec3;
+
+pub type FluidVolumeId = u64;
+
+/// An axis-aligned region filled with fluid, its surface is the top (`max.y`) of the region.
+/// Bodies inside it receive a buoyant force and are slowed down by drag.
+#[table(name = physics_fluid_volumes, public)]
+#[derive(Builder, Debug, Clone, Copy, PartialEq)]
+#[builder(derive(Debug, Clone))]
+pub struct FluidVolume {
+    #[builder(default = 0)]
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+    #[index(btree)]
+    #[builder(default = 1)]
+    pub world_id: u64,
+
+    pub min: Vec3,
+    pub max: Vec3,
+
+    /// The density of the fluid, bodies with a lower density float. Water is 1000 kg/m³.
+    #[builder(default = 1000.0)]
+    pub density: f32,
+
+    /// Drag applied against the linear velocity of fully submerged bodies.
+    #[builder(default = 1.0)]
+    pub linear_drag: f32,
+
+    /// Drag applied against the angular velocity of fully submerged bodies.
+    #[builder(default = 0.5)]
+    pub angular_drag: f32,
+}
+
+impl FluidVolume {
+    pub fn insert(self, ctx: &ReducerContext) -> Self {
+        ctx.db.physics_fluid_volumes().insert(self)
+    }
+
+    pub fn find(ctx: &ReducerContext, id: u64) -> Option<Self> {
+        ctx.db.physics_fluid_volumes().id().find(id)
+    }
+
+    pub fn update(self, ctx: &ReducerContext) -> Self {
+        ctx.db.physics_fluid_volumes().id().update(self)
+    }
+
+    pub fn delete(&self, ctx: &ReducerContext) {
+        ctx.db.physics_fluid_volumes().id().delete(self.id);
+    }
+
+    pub fn delete_by_id(ctx: &ReducerContext, id: u64) {
+        ctx.db.physics_fluid_volumes().id().delete(id);
+    }
+
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.physics_fluid_volumes().world_id().filter(world_id)
+    }
+
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    #[deprecated(note = "use `all_in_world` or `collect_in_world`")]
+    pub fn all(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        Self::all_in_world(ctx, world_id)
+    }
+
+    /// Approximates the submerged fraction of a body by how much of its AABB overlaps the fluid.
+    pub fn submerged_fraction(&self, aabb_min: Vec3, aabb_max: Vec3) -> f32 {
+        let overlap = |min: f32, max: f32, fluid_min: f32, fluid_max: f32| {
+            (max.min(fluid_max) - min.max(fluid_min)).max(0.0)
+        };
+
+        let size = aabb_max - aabb_min;
+        let aabb_volume = size.x * size.y * size.z;
+        if aabb_volume <= f32::EPSILON {
+            return 0.0;
+        }
+
+        let submerged = overlap(aabb_min.x, aabb_max.x, self.min.x, self.max.x)
+            * overlap(aabb_min.y, aabb_max.y, self.min.y, self.max.y)
+            * overlap(aabb_min.z, aabb_max.z, self.min.z, self.max.z);
+
+        (submerged / aabb_volume).clamp(0.0, 1.0)
+    }
+}
+
+impl Display for FluidVolume {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "FluidVolume(id: {}, world_id: {}, min: {}, max: {}, density: {})",
+            self.id, self.world_id, self.min, self.max, self.density
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FluidVolume;
+    use crate::{
+        engine::test_utils::*, math::Vec3, Collider, PhysicsScene, PhysicsWorld,
+        RigidBodyProperties,
+    };
+
+    #[test]
+    fn light_body_floats_up_and_dense_body_sinks() {
+        let mut scene = PhysicsScene::new(PhysicsWorld::builder().build());
+        scene.add_fluid(
+            FluidVolume::builder()
+                .min(Vec3::new(-20.0, -20.0, -20.0))
+                .max(Vec3::new(20.0, 0.0, 20.0))
+                .build(),
+        );
+
+        // A 0.5 radius sphere displaces about 524 kg of water
+        let start = Vec3::new(0.0, -5.0, 0.0);
+        for (id, mass) in [(1, 100.0), (2, 2000.0)] {
+            let properties = RigidBodyProperties::builder().mass(mass).build();
+            let position = start + Vec3::new(id as f32 * 5.0, 0.0, 0.0);
+            scene.add_body(dynamic_body(id, position), &properties, &Collider::sphere(1, 0.5));
+        }
+
+        scene.step_n(30);
+
+        let light = scene.body(1).unwrap();
+        assert!(light.position().y > start.y, "light body at {}", light.position());
+        assert!(light.linear_velocity().y > 0.0);
+        let dense = scene.body(2).unwrap();
+        assert!(dense.position().y < start.y, "dense body at {}", dense.position());
+        assert!(dense.linear_velocity().y < 0.0);
+    }
+
+    #[test]
+    fn submerged_fraction_is_the_aabb_overlap() {
+        let fluid = FluidVolume::builder()
+            .min(Vec3::new(-1.0, -1.0, -1.0))
+            .max(Vec3::new(1.0, 0.0, 1.0))
+            .build();
+
+        let half_in = fluid.submerged_fraction(Vec3::new(0.0, -0.5, 0.0), Vec3::new(0.5, 0.5, 0.5));
+        assert!((half_in - 0.5).abs() < 1e-6);
+        let above = fluid.submerged_fraction(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.5, 2.0, 0.5));
+        assert_eq!(above, 0.0);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod colliders;
+pub mod collision_event;
+pub mod fluid_volume;
 pub mod physics_world;
 pub mod raycast;
 pub mod rigid_body;
@@ -6,6 +8,8 @@ pub mod rigid_body_properties;
 pub mod trigger;
 
 pub use colliders::*;
+pub use collision_event::*;
+pub use fluid_volume::*;
 pub use physics_world::*;
 pub use raycast::*;
 pub use rigid_body::*;
@@ -1,12 +1,38 @@
 use std::{fmt::Display, time::Duration};
 
 use bon::{builder, Builder};
-use spacetimedb::{table, ReducerContext, ScheduleAt, Table};
+use log::warn;
+use spacetimedb::{table, ReducerContext, ScheduleAt, SpacetimeType, Table};
 
 use crate::{math::Vec3, utils::LogStopwatch};
 
 pub type PhysicsWorldId = u64;
 
+/// The algorithm used to find the pairs of potentially colliding bodies.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BroadPhase {
+    /// Bounding volume hierarchy rebuilt every step, a good default for most worlds.
+    #[default]
+    Qbvh,
+    /// Uniform grid of `broad_phase_cell_size` cells, cheaper for evenly distributed content.
+    Grid,
+    /// Sorts the bounding boxes along the X axis, cheap for sparse worlds.
+    SweepAndPrune,
+}
+
+/// How the position constraints of one iteration are combined.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Solver {
+    /// Constraints are solved one after the other, each one seeing the corrections of the
+    /// previous ones. Converges fast, but the result depends on the order of the contacts.
+    #[default]
+    GaussSeidel,
+    /// Every constraint is solved from the same state and the corrections of each body are
+    /// averaged, then applied together. Order independent and smoother for tall stacks, but
+    /// needs more `position_iterations` to converge.
+    Jacobi,
+}
+
 pub fn schedule_physics_tick(world: &PhysicsWorld) -> ScheduleAt {
     let duration = Duration::from_secs_f32(1.0 / world.ticks_per_second);
     duration.into()
@@ -44,7 +70,7 @@ pub struct PhysicsWorld {
     pub gravity: Vec3,
 
     /// The precision of the physics simulation, used to determine how close objects need to be
-    /// to collide.
+    /// to collide. In meters, it's scaled by `length_unit`, see `contact_precision`.
     #[builder(default = 1e-3)]
     pub precision: f32,
 
@@ -53,15 +79,58 @@ pub struct PhysicsWorld {
     #[builder(default = 1)]
     pub position_iterations: u32,
 
+    /// How the position constraints are combined in each iteration, see `Solver`.
+    #[builder(default = Solver::default())]
+    pub solver: Solver,
+
+    /// The largest distance a contact can push two bodies apart in one substep, in meters. It's
+    /// scaled by `length_unit`, see `max_correction`. Bodies spawned deeply overlapping then
+    /// separate over several substeps instead of being launched apart. 0 disables the limit.
+    #[builder(default = 0.0)]
+    pub max_correction_per_substep: f32,
+
+    /// The compliance (inverse stiffness) of the contacts, added to the compliance of the
+    /// bodies in contact. 0 means rigid contacts, higher values make every contact of the
+    /// world softer, letting bodies sink into each other under load.
+    #[builder(default = 0.0)]
+    pub contact_compliance: f32,
+
     /// The dilation factor for the QBVH (Quantized Bounding Volume Hierarchy) used for collision detection.
     /// This factor determines how much the bounding volumes are expanded to account for movement
     /// and ensure that fast-moving objects are still detected for collisions.
     #[builder(default = 0.001)]
     pub qvbh_dilation_factor: f32,
 
+    /// The algorithm used by the broad phase. Raycasts always use the QBVH.
+    #[builder(default = BroadPhase::default())]
+    pub broad_phase: BroadPhase,
+
+    /// The size of a cell when using `BroadPhase::Grid`. It should be close to the size of
+    /// the typical collider in the world.
+    #[builder(default = 4.0)]
+    pub broad_phase_cell_size: f32,
+
+    /// If true, pairs of bodies that can't move (static or frozen) are dropped after the
+    /// broad phase, so dense static geometry isn't tested against itself every step.
+    /// These pairs never produce a response, but no collision events are reported for them.
+    #[builder(default = true)]
+    pub skip_static_pairs: bool,
+
+    /// A warning is logged when the broad phase finds more pairs than this, which usually
+    /// means a bad `qvbh_dilation_factor` or a cluster of overlapping bodies. 0 disables the check.
+    #[builder(default = 0)]
+    pub max_broad_phase_pairs: u32,
+
+    /// If true, a step exceeding `max_broad_phase_pairs` is skipped entirely instead of
+    /// running the narrow phase on every pair. Nothing is written back for that step.
+    #[builder(default = false)]
+    pub abort_on_broad_phase_overflow: bool,
+
     /// How many units are in one meter in the physics world. This is used to convert between
     /// game units and real-world units.
     /// For example, if 100px = 1m in the game, then this value should be set to 100.0.
+    /// Internal tolerances given in meters are scaled by it, while positions, sizes, gravity
+    /// and raycast distances are always in world units.
     #[builder(default = 1.0)]
     pub length_unit: f32,
 
@@ -69,6 +138,38 @@ pub struct PhysicsWorld {
     #[builder(default = 0.002)]
     pub normalized_prediction_distance: f32,
 
+    /// If true, contacts between a moving body and a static one are generated ahead of time,
+    /// using the penetration predicted along the body's velocity. This keeps fast bodies from
+    /// tunneling through thin static walls, at the cost of a larger broad phase.
+    #[builder(default = false)]
+    pub speculative_contacts: bool,
+
+    /// If true, the normal multiplier of a contact that persists between substeps starts from
+    /// its value in the previous substep instead of zero. A contact persists when the same pair
+    /// of bodies touches at nearly the same point on the first body. Stacks settle in fewer
+    /// iterations, multipliers are not kept from one step to the next.
+    #[builder(default = false)]
+    pub warm_starting: bool,
+
+    /// If false, triggers are neither loaded nor evaluated during `step_world` and their rows
+    /// are left untouched. Worlds without triggers skip loading the table.
+    #[builder(default = true)]
+    pub process_triggers: bool,
+
+    /// If true, the position of every moving body is recorded at the start of each step and
+    /// after every substep, see `StepReport::positions_of`. Lets games detect hits that
+    /// happened between two steps.
+    #[builder(default = false)]
+    pub record_substep_positions: bool,
+
+    /// Contacts with a normal velocity below this value don't bounce. The solver never uses less
+    /// than `2 * |gravity| * sub_dt`, so resting bodies don't jitter.
+    ///
+    /// In meters per second, it's scaled by `length_unit`, see `restitution_threshold`.
+    /// `sub_dt` is the timestep over `sub_step`.
+    #[builder(default = 0.0)]
+    pub restitution_velocity_threshold: f32,
+
     /// If true, the physics world will log detailed debug information to the console. This is very
     /// verbose and should only be used for debugging purposes.
     #[builder(default = false)]
@@ -108,7 +209,16 @@ pub struct PhysicsWorld {
 }
 
 impl PhysicsWorld {
-    pub fn insert(self, ctx: &ReducerContext) -> Self {
+    /// `sub_step` and `position_iterations` below 1 are raised to 1, with a warning.
+    pub fn insert(mut self, ctx: &ReducerContext) -> Self {
+        if self.sub_step == 0 || self.position_iterations == 0 {
+            warn!(
+                "PhysicsWorld: sub_step ({}) and position_iterations ({}) must be at least 1, clamping them",
+                self.sub_step, self.position_iterations
+            );
+            self.sub_step = self.sub_step_count();
+            self.position_iterations = self.position_iteration_count();
+        }
         ctx.db.physics_world().insert(self)
     }
 
@@ -132,6 +242,40 @@ impl PhysicsWorld {
         self.normalized_prediction_distance * self.length_unit
     }
 
+    /// `sub_step`, at least 1 so the sub time step stays finite.
+    pub fn sub_step_count(&self) -> u32 {
+        self.sub_step.max(1)
+    }
+
+    /// `position_iterations`, at least 1 so contacts are always solved.
+    pub fn position_iteration_count(&self) -> u32 {
+        self.position_iterations.max(1)
+    }
+
+    pub fn sub_time_step(&self) -> f32 {
+        self.time_step / self.sub_step_count() as f32
+    }
+
+    /// The `precision` in world units.
+    pub fn contact_precision(&self) -> f32 {
+        self.precision * self.length_unit
+    }
+
+    /// The `max_correction_per_substep` in world units.
+    pub fn max_correction(&self) -> f32 {
+        self.max_correction_per_substep * self.length_unit
+    }
+
+    /// The `restitution_velocity_threshold` in world units per second.
+    pub fn restitution_threshold(&self) -> f32 {
+        self.restitution_velocity_threshold * self.length_unit
+    }
+
+    /// True if `pair_count` exceeds `max_broad_phase_pairs`, when the limit is enabled.
+    pub fn broad_phase_overflow(&self, pair_count: usize) -> bool {
+        self.max_broad_phase_pairs > 0 && pair_count > self.max_broad_phase_pairs as usize
+    }
+
     pub fn debug_broad_phase(&self) -> bool {
         self.debug || self.debug_broad_phase || self.debug_broad_narrow_phase
     }
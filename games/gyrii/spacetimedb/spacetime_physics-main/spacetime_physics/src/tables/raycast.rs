@@ -18,7 +18,8 @@ pub struct RayCastHit {
     /// The position in world coordinates where the ray hit.
     pub position: Vec3,
 
-    /// The normal vector at the hit point, pointing away from the surface.
+    /// The normal vector at the hit point, pointing away from the surface. A ray cast down onto
+    /// a floor gets a +Y normal, same as `ContactPair::normal_for` of a body resting on it.
     pub normal: Vec3,
 
     /// The ID of the rigid body that was hit by the ray.
@@ -85,6 +86,17 @@ pub struct RayCast {
     pub removed_hits: Vec<RayCastHit>,
 }
 
+/// The parameters of a ray, without any hit state.
+/// `spacetime_rapier` has a `RayDesc` with the same fields, which makes moving
+/// raycasts between the two crates a matter of copying them over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayDesc {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub max_distance: f32,
+    pub solid: bool,
+}
+
 impl RayCast {
     pub fn new(
         world_id: u64,
@@ -93,23 +105,69 @@ impl RayCast {
         max_distance: f32,
         solid: bool,
     ) -> Self {
+        Self::from_desc(
+            world_id,
+            RayDesc {
+                origin,
+                direction,
+                max_distance,
+                solid,
+            },
+        )
+    }
+
+    /// Creates a raycast from a `RayDesc`, the direction is normalized.
+    pub fn from_desc(world_id: u64, desc: RayDesc) -> Self {
         Self {
             id: 0,
             world_id,
-            origin,
-            direction: direction.normalize(),
-            max_distance,
-            solid,
+            origin: desc.origin,
+            direction: desc.direction.normalize(),
+            max_distance: desc.max_distance,
+            solid: desc.solid,
             hits: Vec::new(),
             added_hits: Vec::new(),
             removed_hits: Vec::new(),
         }
     }
 
+    /// A raycast from `origin` to `target`, `max_distance` is the distance between them.
+    pub fn from_to(world_id: u64, origin: Vec3, target: Vec3, solid: bool) -> Self {
+        Self::new(
+            world_id,
+            origin,
+            target - origin,
+            (target - origin).length(),
+            solid,
+        )
+    }
+
+    pub fn set_from_to(&mut self, origin: Vec3, target: Vec3) {
+        self.origin = origin;
+        self.direction = (target - origin).normalize();
+        self.max_distance = (target - origin).length();
+    }
+
+    pub fn desc(&self) -> RayDesc {
+        RayDesc {
+            origin: self.origin,
+            direction: self.direction,
+            max_distance: self.max_distance,
+            solid: self.solid,
+        }
+    }
+
     pub fn insert(self, ctx: &ReducerContext) -> Self {
         ctx.db.physics_raycasts().insert(self)
     }
 
+    /// Inserts every row with a single table handle, returns them with their assigned ids.
+    /// Meant for world setup, where thousands of raycasts are created at once.
+    pub fn insert_many(ctx: &ReducerContext, rows: impl IntoIterator<Item = Self>) -> Vec<Self> {
+        let table = ctx.db.physics_raycasts();
+        rows.into_iter().map(|row| table.insert(row)).collect()
+    }
+
     pub fn find(ctx: &ReducerContext, id: u64) -> Option<Self> {
         ctx.db.physics_raycasts().id().find(id)
     }
@@ -126,11 +184,46 @@ impl RayCast {
         ctx.db.physics_raycasts().id().delete(id);
     }
 
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.physics_raycasts().world_id().filter(world_id)
+    }
+
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    #[deprecated(note = "use `all_in_world` or `collect_in_world`")]
     pub fn all(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
-        ctx.db
-            .physics_raycasts()
-            .world_id()
-            .filter(world_id)
-            .collect()
+        Self::collect_in_world(ctx, world_id)
+    }
+}
+
+impl From<&RayCast> for RayDesc {
+    fn from(value: &RayCast) -> Self {
+        value.desc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RayCast, RayDesc};
+    use crate::math::Vec3;
+
+    #[test]
+    fn ray_desc_round_trips_through_the_constructor() {
+        let desc = RayDesc {
+            origin: Vec3::new(1.0, 2.0, 3.0),
+            direction: Vec3::new(0.0, 0.0, -1.0),
+            max_distance: 25.0,
+            solid: true,
+        };
+
+        let raycast = RayCast::from_desc(1, desc);
+
+        assert_eq!(raycast.desc(), desc);
+        assert_eq!(RayDesc::from(&raycast), desc);
+        // Same ray as the positional constructor
+        let positional = RayCast::new(1, desc.origin, desc.direction, 25.0, true);
+        assert_eq!(positional.desc(), desc);
     }
 }
@@ -1,10 +1,13 @@
 use std::fmt::Display;
 
 use bon::{builder, Builder};
-use parry3d::na::Isometry3;
+use parry3d::{bounding_volume::BoundingVolume, na::Isometry3};
 use spacetimedb::{table, ReducerContext, SpacetimeType, Table};
 
-use crate::math::{Quat, Vec3};
+use crate::{
+    math::{Quat, Vec3},
+    Collider, ShapeWrapper,
+};
 
 pub type RigidBodyId = u64;
 
@@ -47,6 +50,24 @@ pub struct RigidBody {
 
     pub collider_id: u64,
     pub properties_id: u64,
+
+    /// A frozen body is simulated as static but keeps its `body_type`,
+    /// so it resumes where it left off once unfrozen.
+    #[builder(default = false)]
+    pub frozen: bool,
+
+    /// Torque pulling the local up axis of the body back towards the world up (against gravity),
+    /// per unit of sine of the tilt. 0 disables it. Lets upright characters tip over when hit
+    /// and right themselves, where locking the rotation would keep them rigid.
+    #[builder(default = 0.0)]
+    pub upright_stiffness: f32,
+
+    /// The pose at the start of the last step, written back together with the new pose.
+    /// Starts at the initial pose, see `swept_aabb`.
+    #[builder(skip = position)]
+    pub previous_position: Vec3,
+    #[builder(skip = rotation)]
+    pub previous_rotation: Quat,
 }
 
 impl RigidBody {
@@ -54,14 +75,38 @@ impl RigidBody {
         ctx.db.physics_rigid_bodies().insert(self)
     }
 
+    /// Inserts every row with a single table handle, returns them with their assigned ids.
+    /// Meant for world setup, where thousands of bodies are created at once.
+    pub fn insert_many(ctx: &ReducerContext, rows: impl IntoIterator<Item = Self>) -> Vec<Self> {
+        let table = ctx.db.physics_rigid_bodies();
+        Self::insert_many_with(rows, |row| table.insert(row))
+    }
+
+    /// `insert_many` with a custom `insert`, which returns the row with its assigned id.
+    pub fn insert_many_with(
+        rows: impl IntoIterator<Item = Self>,
+        insert: impl FnMut(Self) -> Self,
+    ) -> Vec<Self> {
+        rows.into_iter().map(insert).collect()
+    }
+
     pub fn find(ctx: &ReducerContext, id: u64) -> Option<Self> {
         ctx.db.physics_rigid_bodies().id().find(id)
     }
 
-    pub fn all(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = RigidBody> {
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
         ctx.db.physics_rigid_bodies().world_id().filter(world_id)
     }
 
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    #[deprecated(note = "use `all_in_world` or `collect_in_world`")]
+    pub fn all(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        Self::all_in_world(ctx, world_id)
+    }
+
     pub fn update(self, ctx: &ReducerContext) -> Self {
         ctx.db.physics_rigid_bodies().id().update(self)
     }
@@ -81,6 +126,57 @@ impl RigidBody {
     pub fn is_kinematic(&self) -> bool {
         self.body_type == RigidBodyType::Kinematic
     }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Adds a force given in the body's local frame, e.g. a thruster pushing along its forward.
+    /// The force is rotated into world space and accumulated until the end of the next step.
+    pub fn apply_local_force(ctx: &ReducerContext, id: u64, local_force: Vec3) -> Option<Self> {
+        let mut body = Self::find(ctx, id)?;
+        body.add_local_force(local_force);
+        Some(body.update(ctx))
+    }
+
+    /// Rotates a force from the body's local frame into world space and adds it to `force`.
+    pub fn add_local_force(&mut self, local_force: Vec3) {
+        self.force += self.rotation.rotate(local_force);
+    }
+
+    /// The velocity of a point attached to the body, given in world space.
+    /// Bodies are centered on their center of mass, so this is `linear + angular × (point - position)`.
+    pub fn velocity_at_point(&self, world_point: Vec3) -> Vec3 {
+        self.linear_velocity + self.angular_velocity.cross(world_point - self.position)
+    }
+
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn unfreeze(&mut self) {
+        self.frozen = false;
+    }
+
+    /// The AABB enclosing the body at both its previous and current pose, as `(min, max)`.
+    /// Useful for clients that predict motion or run their own broad phase.
+    pub fn swept_aabb(&self, ctx: &ReducerContext) -> Option<(Vec3, Vec3)> {
+        let collider = Collider::find(ctx, self.collider_id)?;
+        Some(self.swept_aabb_with(&collider))
+    }
+
+    /// Same as `swept_aabb`, with the body's collider already loaded.
+    pub fn swept_aabb_with(&self, collider: &Collider) -> (Vec3, Vec3) {
+        let shape = ShapeWrapper::from(collider);
+
+        let previous =
+            Isometry3::from_parts(self.previous_position.into(), self.previous_rotation.into());
+        let aabb = shape
+            .collision_aabb(&previous, 0.0)
+            .merged(&shape.collision_aabb(&self.into(), 0.0));
+
+        (aabb.mins.into(), aabb.maxs.into())
+    }
 }
 
 impl Display for RigidBody {
@@ -104,3 +200,108 @@ impl From<&RigidBody> for Isometry3<f32> {
         Isometry3::from_parts(value.position.into(), value.rotation.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::RigidBody;
+    use crate::{
+        engine::test_utils::dynamic_body,
+        math::{Quat, Vec3},
+        Collider, PhysicsScene, PhysicsWorld, RigidBodyProperties,
+    };
+
+    #[test]
+    fn local_forward_thrust_of_a_turned_body_pushes_along_world_x() {
+        let mut body = dynamic_body(1, Vec3::ZERO);
+        body.rotation = Quat::from_rotation_y(FRAC_PI_2);
+        body.add_local_force(Vec3::new(0.0, 0.0, 10.0));
+        assert!(
+            (body.force - Vec3::new(10.0, 0.0, 0.0)).length() < 1e-5,
+            "force {}",
+            body.force
+        );
+
+        let world = PhysicsWorld::builder().gravity(Vec3::ZERO).build();
+        let mut scene = PhysicsScene::new(world);
+        let properties = RigidBodyProperties::builder().build();
+        scene.add_body(body, &properties, &Collider::sphere(1, 0.5));
+        scene.step();
+
+        let velocity = scene.body(1).unwrap().linear_velocity();
+        assert!(velocity.x > 0.0, "velocity {}", velocity);
+        assert!(
+            velocity.y.abs() < 1e-5 && velocity.z.abs() < 1e-5,
+            "velocity {}",
+            velocity
+        );
+    }
+
+    #[test]
+    fn swept_aabb_encloses_both_poses() {
+        let mut body = RigidBody::builder()
+            .position(Vec3::new(0.0, 5.0, 0.0))
+            .collider_id(1)
+            .properties_id(1)
+            .build();
+        body.position = Vec3::new(3.0, 4.0, -1.0);
+
+        let (min, max) = body.swept_aabb_with(&Collider::sphere(1, 0.5));
+
+        for center in [body.previous_position, body.position] {
+            let (lower, upper) = (center - Vec3::splat(0.5), center + Vec3::splat(0.5));
+            assert!(min.x <= lower.x && min.y <= lower.y && min.z <= lower.z);
+            assert!(max.x >= upper.x && max.y >= upper.y && max.z >= upper.z);
+        }
+        assert!(
+            (min - Vec3::new(-0.5, 3.5, -1.5)).length() < 1e-4,
+            "min {}",
+            min
+        );
+        assert!(
+            (max - Vec3::new(3.5, 5.5, 0.5)).length() < 1e-4,
+            "max {}",
+            max
+        );
+    }
+
+    #[test]
+    fn rim_of_a_spinning_body_moves_tangentially() {
+        let mut body = dynamic_body(1, Vec3::new(1.0, 0.0, 0.0));
+        body.angular_velocity = Vec3::new(0.0, 2.0, 0.0);
+
+        // ω × r: 2 rad/s at a 0.5 radius is 1 m/s, turning +X towards -Z
+        let rim = Vec3::new(1.5, 0.0, 0.0);
+        assert_eq!(body.velocity_at_point(rim), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(
+            body.velocity_at_point(Vec3::new(1.0, 0.0, 0.5)),
+            Vec3::new(1.0, 0.0, 0.0)
+        );
+
+        // The linear velocity is added everywhere, the center only has that
+        body.linear_velocity = Vec3::new(0.0, 0.0, 1.0);
+        assert_eq!(body.velocity_at_point(rim), Vec3::ZERO);
+        assert_eq!(body.velocity_at_point(body.position), body.linear_velocity);
+    }
+
+    #[test]
+    fn insert_many_inserts_every_body_and_returns_their_ids() {
+        let mut table: Vec<RigidBody> = Vec::new();
+        let rows = (0..100).map(|i| dynamic_body(0, Vec3::new(i as f32, 0.0, 0.0)));
+
+        let inserted = RigidBody::insert_many_with(rows, |row| {
+            let row = RigidBody {
+                id: table.len() as u64 + 1,
+                ..row
+            };
+            table.push(row);
+            row
+        });
+
+        assert_eq!(inserted.len(), 100);
+        assert_eq!(inserted, table);
+        assert!(inserted.iter().zip(1..).all(|(body, id)| body.id == id));
+        assert_eq!(inserted[99].position, Vec3::new(99.0, 0.0, 0.0));
+    }
+}
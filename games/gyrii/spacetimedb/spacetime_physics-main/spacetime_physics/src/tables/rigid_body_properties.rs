@@ -1,9 +1,10 @@
 use std::fmt::Display;
 
 use bon::{builder, Builder};
+use log::warn;
 use spacetimedb::{table, ReducerContext, Table};
 
-use crate::math::Vec3;
+use crate::{engine::clamp_restitution, math::Vec3};
 
 #[table(name = physics_rigid_body_properties, public)]
 #[derive(Builder, Debug, Clone, PartialEq)]
@@ -19,16 +20,30 @@ pub struct RigidBodyProperties {
     pub friction_static_coefficient: f32,
     #[builder(default = 0.5)]
     pub friction_dynamic_coefficient: f32,
+    /// Between 0 (no bounce) and 1 (perfect bounce), the engine clamps values outside of it.
     #[builder(default = 0.0)]
     pub restitution_coefficient: f32,
+    /// Compliance of the contacts of this body, 0 is rigid. A nonzero value makes a spongy
+    /// surface, see `PhysicsWorld::contact_compliance`.
+    #[builder(default = 0.0)]
+    pub contact_compliance: f32,
+    /// Damping of the velocities of the body while it touches something, 0 disables it. Friction
+    /// alone never stops a rolling ball, this slows its spin and its speed along the contact
+    /// together until it stops. Meant for spheres and cylinders.
+    #[builder(default = 0.0)]
+    pub rolling_resistance: f32,
     #[builder(default = 1.0)]
     pub mass: f32,
-    #[builder(skip = if mass > 0.0 { 1.0 / mass } else { 0.0 })]
+    /// Cached inverse of `mass`, use `set_mass` to keep both in sync.
+    /// The engine always derives the inverse mass from `mass`, see `inverse_mass`.
+    #[builder(skip = inverse_mass(mass))]
     pub inv_mass: f32,
 }
 
 impl RigidBodyProperties {
+    /// Warns about values the engine corrects, see `warn_about_corrected_values`.
     pub fn insert(self, ctx: &ReducerContext) -> Self {
+        self.warn_about_corrected_values();
         ctx.db.physics_rigid_body_properties().insert(self)
     }
 
@@ -36,17 +51,43 @@ impl RigidBodyProperties {
         ctx.db.physics_rigid_body_properties().id().find(id)
     }
 
-    pub fn all(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> {
-        ctx.db
-            .physics_rigid_body_properties()
-            .world_id()
-            .filter(world_id)
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.physics_rigid_body_properties().world_id().filter(world_id)
+    }
+
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
     }
 
+    #[deprecated(note = "use `all_in_world` or `collect_in_world`")]
+    pub fn all(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        Self::all_in_world(ctx, world_id)
+    }
+
+    /// Warns about values the engine corrects, see `warn_about_corrected_values`.
     pub fn update(self, ctx: &ReducerContext) -> Self {
+        self.warn_about_corrected_values();
         ctx.db.physics_rigid_body_properties().id().update(self)
     }
 
+    /// Logged when the row is written rather than every time a step loads it.
+    /// Dynamic bodies without a positive mass are treated as static, and the
+    /// restitution coefficient is clamped to [0, 1].
+    fn warn_about_corrected_values(&self) {
+        if !(self.mass > 0.0) {
+            warn!(
+                "RigidBodyProperties {} has a mass of {}, dynamic bodies using it are treated as static",
+                self.id, self.mass
+            );
+        }
+        if clamp_restitution(self.restitution_coefficient) != self.restitution_coefficient {
+            warn!(
+                "RigidBodyProperties {} has a restitution coefficient of {}, clamping it to [0, 1]",
+                self.id, self.restitution_coefficient
+            );
+        }
+    }
+
     pub fn delete(self, ctx: &ReducerContext) {
         ctx.db.physics_rigid_body_properties().id().delete(self.id);
     }
@@ -67,8 +108,32 @@ impl RigidBodyProperties {
         (self.restitution_coefficient + other.restitution_coefficient) / 2.0
     }
 
+    /// Two soft surfaces in contact act like springs in series, so their compliances add up.
+    pub fn combine_contact_compliance(&self, other: &Self) -> f32 {
+        self.contact_compliance + other.contact_compliance
+    }
+
+    /// Sets the mass and updates the inverse mass accordingly, a mass of 0 means infinite mass.
+    pub fn set_mass(&mut self, mass: f32) {
+        self.mass = mass;
+        self.inv_mass = inverse_mass(mass);
+    }
+
+    /// The inverse mass derived from `mass`, so it can't go stale if `mass` was changed directly.
+    pub fn inverse_mass(&self) -> f32 {
+        inverse_mass(self.mass)
+    }
+
     pub fn effective_inverse_mass(&self) -> Vec3 {
-        Vec3::splat(self.inv_mass)
+        Vec3::splat(self.inverse_mass())
+    }
+}
+
+fn inverse_mass(mass: f32) -> f32 {
+    if mass > 0.0 {
+        1.0 / mass
+    } else {
+        0.0
     }
 }
 
@@ -76,14 +141,59 @@ impl Display for RigidBodyProperties {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "RigidBodyProperties {{ id: {}, world_id: {}, friction_static_coefficient: {}, friction_dynamic_coefficient: {}, restitution_coefficient: {}, mass: {}, inv_mass: {} }}",
+            "RigidBodyProperties {{ id: {}, world_id: {}, friction_static_coefficient: {}, friction_dynamic_coefficient: {}, restitution_coefficient: {}, contact_compliance: {}, rolling_resistance: {}, mass: {}, inv_mass: {} }}",
             self.id,
             self.world_id,
             self.friction_static_coefficient,
             self.friction_dynamic_coefficient,
             self.restitution_coefficient,
+            self.contact_compliance,
+            self.rolling_resistance,
             self.mass,
             self.inv_mass
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RigidBodyProperties;
+    use crate::{engine::test_utils::*, math::Vec3, Collider, PhysicsScene, PhysicsWorld};
+
+    #[test]
+    fn set_mass_keeps_the_inverse_in_sync() {
+        let mut properties = RigidBodyProperties::builder().build();
+        properties.set_mass(4.0);
+        assert_eq!(properties.inv_mass, 0.25);
+        properties.set_mass(0.0);
+        assert_eq!(properties.inv_mass, 0.0);
+    }
+
+    #[test]
+    fn changing_mass_changes_acceleration() {
+        let mut scene = PhysicsScene::new(PhysicsWorld::builder().gravity(Vec3::ZERO).build());
+        let light = RigidBodyProperties::builder().build();
+        // Changed after build without `set_mass`, `inv_mass` is stale
+        let mut heavy = RigidBodyProperties::builder().build();
+        heavy.mass = 4.0;
+
+        let collider = Collider::sphere(1, 0.5);
+        scene.add_body(dynamic_body(1, Vec3::ZERO), &light, &collider);
+        scene.add_body(dynamic_body(2, Vec3::new(5.0, 0.0, 0.0)), &heavy, &collider);
+        for id in [1, 2] {
+            scene.body_mut(id).unwrap().set_force(Vec3::new(1.0, 0.0, 0.0));
+        }
+
+        scene.step();
+
+        let light_speed = scene.body(1).unwrap().linear_velocity().x;
+        let heavy_speed = scene.body(2).unwrap().linear_velocity().x;
+        assert!(heavy_speed > 0.0);
+        assert!(
+            (light_speed / heavy_speed - 4.0).abs() < 1e-3,
+            "{} vs {}",
+            light_speed,
+            heavy_speed
+        );
+    }
+}
@@ -46,6 +46,13 @@ impl Trigger {
         ctx.db.physics_triggers().insert(self)
     }
 
+    /// Inserts every row with a single table handle, returns them with their assigned ids.
+    /// Meant for world setup, where thousands of triggers are created at once.
+    pub fn insert_many(ctx: &ReducerContext, rows: impl IntoIterator<Item = Self>) -> Vec<Self> {
+        let table = ctx.db.physics_triggers();
+        rows.into_iter().map(|row| table.insert(row)).collect()
+    }
+
     pub fn find(ctx: &ReducerContext, id: u64) -> Option<Self> {
         ctx.db.physics_triggers().id().find(id)
     }
@@ -62,9 +69,18 @@ impl Trigger {
         ctx.db.physics_triggers().id().delete(id);
     }
 
-    pub fn all(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> {
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
         ctx.db.physics_triggers().world_id().filter(world_id)
     }
+
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    #[deprecated(note = "use `all_in_world` or `collect_in_world`")]
+    pub fn all(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        Self::all_in_world(ctx, world_id)
+    }
 }
 
 impl From<Trigger> for Isometry3<f32> {
@@ -0,0 +1,78 @@
+//! Allocations and time of stepping a freshly loaded world
+//!
+//! Run with `cargo bench --bench load_allocations`. The counting allocator
+//! only lives in this binary, the library and its tests keep the system one.
+//! `WorldRows::load` needs a database, it collects one `Vec` per table and
+//! is left out, the count covers building the Rapier world from the rows
+//! and stepping it.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use spacetime_rapier::engine::{simulate, WorldRows};
+use spacetime_rapier::{Collider, PhysicsWorld, RigidBody, RigidBodyProperties};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// A grid of `count` balls sharing one collider and one set of properties
+fn rows(world_id: u64, count: usize) -> WorldRows {
+    WorldRows {
+        bodies: (0..count)
+            .map(|i| RigidBody {
+                id: i as u64 + 3,
+                world_id,
+                position_x: (i % 100) as f32,
+                position_y: (i / 100) as f32,
+                collider_id: 1,
+                properties_id: 2,
+                ..Default::default()
+            })
+            .collect(),
+        colliders: vec![Collider { id: 1, ..Collider::ball(world_id, 0.25) }],
+        properties: vec![RigidBodyProperties { id: 2, world_id, ..Default::default() }],
+        ..Default::default()
+    }
+}
+
+fn main() {
+    let world = PhysicsWorld { id: 1, ..PhysicsWorld::builder().gravity_y(0.0).build() };
+
+    // Allocations that don't depend on the number of bodies
+    let empty = rows(world.id, 0);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    simulate(&world, &empty, std::iter::empty(), std::iter::empty());
+    let baseline = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("empty world: {baseline} allocations");
+
+    for count in [1000, 5000] {
+        let rows = rows(world.id, count);
+        let start = Instant::now();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let output = simulate(&world, &rows, std::iter::empty(), std::iter::empty());
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        let elapsed = start.elapsed();
+        assert_eq!(output.bodies_stepped, count);
+
+        println!(
+            "{count} bodies: {elapsed:?}, {allocations} allocations, {:.2} per body",
+            allocations.saturating_sub(baseline) as f64 / count as f64
+        );
+    }
+}
@@ -8,8 +8,17 @@ mod world_2d;
 #[cfg(feature = "dim3")]
 mod world_3d;
 
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
 use spacetimedb::ReducerContext;
-use crate::tables::PhysicsWorld;
+use crate::tables::{
+    Collider, ColliderId, CollisionEvent, PhysicsWorld, PhysicsWorldId, PortalEvent, PortalTrigger, RayCast,
+    RigidBody, RigidBodyId, RigidBodyProperties, SensorOverlap, Trigger, COMPILED_DIMENSION,
+};
+
+/// A contact normal at least this aligned with "up" (against gravity) counts as ground, about 45°
+const GROUND_NORMAL_MIN_UP: f32 = 0.7;
 
 #[cfg(feature = "dim3")]
 use crate::math::{Vec3, Quat};
@@ -41,6 +50,302 @@ pub type KinematicBody = (u64, (Vec2, f32));
 #[cfg(feature = "dim3")]
 pub type KinematicBody = (u64, (Vec3, Quat));
 
+/// Type alias for per-tick external forces (2D): body ID, force, torque
+#[cfg(feature = "dim2")]
+pub type ExternalForce = (u64, Vec2, f32);
+
+/// Type alias for per-tick external forces (3D): body ID, force, torque
+#[cfg(feature = "dim3")]
+pub type ExternalForce = (u64, Vec3, Vec3);
+
+/// A contact between two rigid bodies (3D)
+///
+/// `normal` points from body A towards body B, use `normal_for` to get it
+/// from one body's point of view. For a ball resting on a floor,
+/// `normal_for(ball)` is +Y whichever body Rapier reports first.
+#[cfg(feature = "dim3")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactPair {
+    pub body_a_id: u64,
+    pub body_b_id: u64,
+    /// `user_data` of body A
+    pub body_a_user_data: u64,
+    /// `user_data` of body B
+    pub body_b_user_data: u64,
+    /// Deepest contact point on body A, in world space
+    pub point: Vec3,
+    /// Contact normal in world space, pointing from body A towards body B
+    pub normal: Vec3,
+    /// Distance between the bodies at the contact point (negative when overlapping)
+    pub distance: f32,
+}
+
+#[cfg(feature = "dim3")]
+impl ContactPair {
+    /// Contact normal pointing out of the other body towards `body_id`
+    ///
+    /// The direction that pushes `body_id` away, e.g. for knockback.
+    pub fn normal_for(&self, body_id: u64) -> Vec3 {
+        if body_id == self.body_a_id {
+            -self.normal
+        } else {
+            self.normal
+        }
+    }
+}
+
+/// A contact between two rigid bodies (2D)
+///
+/// `normal` points from body A towards body B, use `normal_for` to get it
+/// from one body's point of view. For a ball resting on a floor,
+/// `normal_for(ball)` is +Y whichever body Rapier reports first.
+#[cfg(feature = "dim2")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactPair {
+    pub body_a_id: u64,
+    pub body_b_id: u64,
+    /// `user_data` of body A
+    pub body_a_user_data: u64,
+    /// `user_data` of body B
+    pub body_b_user_data: u64,
+    /// Deepest contact point on body A, in world space
+    pub point: Vec2,
+    /// Contact normal in world space, pointing from body A towards body B
+    pub normal: Vec2,
+    /// Distance between the bodies at the contact point (negative when overlapping)
+    pub distance: f32,
+}
+
+#[cfg(feature = "dim2")]
+impl ContactPair {
+    /// Contact normal pointing out of the other body towards `body_id`
+    ///
+    /// The direction that pushes `body_id` away, e.g. for knockback.
+    pub fn normal_for(&self, body_id: u64) -> Vec2 {
+        if body_id == self.body_a_id {
+            -self.normal
+        } else {
+            self.normal
+        }
+    }
+}
+
+/// Why a world, a body or a collider could not be stepped, inserted or queried
+#[derive(Clone, Debug, PartialEq)]
+pub enum PhysicsError {
+    /// The world row no longer exists, it was deleted before the step
+    WorldNotFound(PhysicsWorldId),
+    /// The world was created for another dimension than the one compiled in
+    DimensionMismatch { world_id: PhysicsWorldId, dimension: u8 },
+    /// The world gravity is NaN or infinite
+    NonFiniteGravity(PhysicsWorldId),
+    /// A world parameter is out of its valid range
+    InvalidWorldParameter { world_id: PhysicsWorldId, reason: String },
+    /// A body references a collider that doesn't exist
+    MissingCollider { body_id: RigidBodyId, collider_id: ColliderId },
+    /// A body references properties that don't exist
+    MissingProperties { body_id: RigidBodyId, properties_id: u64 },
+    /// A body's position, rotation or velocity is NaN or infinite
+    NonFiniteState(RigidBodyId),
+    /// A collider's shape parameters can't build a Rapier shape
+    InvalidShape { collider_id: ColliderId, reason: String },
+    /// A shape cast was given a concave shape to sweep
+    ConcaveShape(ColliderId),
+}
+
+impl fmt::Display for PhysicsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WorldNotFound(id) => write!(f, "world {} not found", id),
+            Self::DimensionMismatch { world_id, dimension } => write!(
+                f,
+                "world {} is {}D but spacetime_rapier was built for {}D",
+                world_id, dimension, COMPILED_DIMENSION
+            ),
+            Self::NonFiniteGravity(id) => write!(f, "world {} has non-finite gravity", id),
+            Self::InvalidWorldParameter { world_id, reason } => write!(f, "world {}: {}", world_id, reason),
+            Self::MissingCollider { body_id, collider_id } => {
+                write!(f, "body {} references missing collider {}", body_id, collider_id)
+            }
+            Self::MissingProperties { body_id, properties_id } => {
+                write!(f, "body {} references missing properties {}", body_id, properties_id)
+            }
+            Self::NonFiniteState(id) => write!(f, "body {} has a non-finite position or velocity", id),
+            Self::InvalidShape { collider_id, reason } => write!(f, "collider {}: {}", collider_id, reason),
+            Self::ConcaveShape(id) => write!(f, "collider {} is concave and can't be cast", id),
+        }
+    }
+}
+
+impl std::error::Error for PhysicsError {}
+
+/// Summary of a completed step
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StepReport {
+    /// Tick of the world after the step
+    pub tick: u64,
+    /// Number of enabled bodies that were simulated
+    pub bodies_stepped: usize,
+    /// Bodies left out of the step, they keep their stored state
+    pub skipped_bodies: Vec<PhysicsError>,
+    /// Bodies deleted because their `expire_at_tick` was reached
+    pub expired_bodies: Vec<RigidBodyId>,
+    /// Bodies deleted because they left a trigger with `auto_despawn_on_exit`
+    pub despawned_bodies: Vec<RigidBodyId>,
+}
+
+/// The rows of a world read by a step
+///
+/// Loaded once per step so the simulation itself never touches the database.
+#[derive(Clone, Debug, Default)]
+pub struct WorldRows {
+    pub bodies: Vec<RigidBody>,
+    pub colliders: Vec<Collider>,
+    pub properties: Vec<RigidBodyProperties>,
+    /// Empty unless the world processes triggers
+    pub triggers: Vec<Trigger>,
+    /// Empty unless the world processes triggers
+    pub portals: Vec<PortalTrigger>,
+    pub raycasts: Vec<RayCast>,
+}
+
+impl WorldRows {
+    /// Load every row of a world that a step reads
+    pub fn load(ctx: &ReducerContext, world: &PhysicsWorld) -> Self {
+        let (triggers, portals) = if world.process_triggers {
+            (
+                Trigger::all_in_world(ctx, world.id).collect(),
+                PortalTrigger::all_in_world(ctx, world.id).collect(),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        Self {
+            bodies: RigidBody::all_in_world(ctx, world.id).collect(),
+            colliders: Collider::all_in_world(ctx, world.id).collect(),
+            properties: RigidBodyProperties::all_in_world(ctx, world.id).collect(),
+            triggers,
+            portals,
+            raycasts: RayCast::all_in_world(ctx, world.id).collect(),
+        }
+    }
+}
+
+/// Everything a simulated step changes, written back with `write`
+#[derive(Clone, Debug, Default)]
+pub struct StepOutput {
+    /// Bodies whose stored state changed, in write order
+    pub bodies: Vec<RigidBody>,
+    /// Enabled triggers with their refreshed entity lists
+    pub triggers: Vec<Trigger>,
+    /// Portal events for the bodies that entered a portal this step
+    pub portal_events: Vec<PortalEvent>,
+    /// Touching pairs of bodies, as `(lower_id, higher_id)`
+    pub contact_pairs: HashSet<(RigidBodyId, RigidBodyId)>,
+    /// `material_id` of every body, for new collision events
+    pub body_materials: HashMap<RigidBodyId, u32>,
+    /// Bodies carrying a sensor collider, with the bodies inside it
+    pub sensor_overlaps: Vec<(RigidBodyId, Vec<RigidBodyId>)>,
+    /// Enabled raycasts with their refreshed hit lists
+    pub raycasts: Vec<RayCast>,
+    /// Number of enabled bodies that were simulated
+    pub bodies_stepped: usize,
+    /// Bodies left out of the step
+    pub skipped_bodies: Vec<PhysicsError>,
+}
+
+impl StepOutput {
+    /// Bodies that left a trigger with `auto_despawn_on_exit` during this step
+    ///
+    /// Only the triggers evaluated by the step are read, the exit lists of
+    /// disabled triggers are left over from an older step.
+    pub fn exited_bodies(&self) -> HashSet<RigidBodyId> {
+        self.triggers
+            .iter()
+            .filter(|trigger| trigger.enabled && trigger.auto_despawn_on_exit)
+            .flat_map(|trigger| trigger.removed_entities.iter().copied())
+            .collect()
+    }
+
+    /// Write the results of a step to the tables
+    pub(crate) fn write(self, ctx: &ReducerContext, world: &PhysicsWorld) -> StepReport {
+        for body in self.bodies {
+            body.update(ctx);
+        }
+        for event in self.portal_events {
+            event.insert(ctx);
+        }
+        for trigger in self.triggers {
+            trigger.update(ctx);
+        }
+        CollisionEvent::sync_world(ctx, world.id, &self.contact_pairs, &self.body_materials);
+        write_sensor_overlaps(ctx, world.id, self.sensor_overlaps);
+        for raycast in self.raycasts {
+            raycast.update(ctx);
+        }
+
+        StepReport {
+            tick: world.tick,
+            bodies_stepped: self.bodies_stepped,
+            skipped_bodies: self.skipped_bodies,
+            expired_bodies: Vec::new(),
+            despawned_bodies: Vec::new(),
+        }
+    }
+}
+
+/// Refresh the overlap lists of bodies carrying a sensor collider
+///
+/// Lists of bodies that no longer carry a sensor (or were disabled) are removed.
+fn write_sensor_overlaps(
+    ctx: &ReducerContext,
+    world_id: PhysicsWorldId,
+    overlaps: Vec<(RigidBodyId, Vec<RigidBodyId>)>,
+) {
+    let mut previous: HashMap<u64, SensorOverlap> = SensorOverlap::all_in_world(ctx, world_id)
+        .map(|overlap| (overlap.body_id, overlap))
+        .collect();
+
+    for (body_id, current_inside) in overlaps {
+        match previous.remove(&body_id) {
+            Some(mut overlap) => {
+                overlap.update_entities(current_inside);
+                overlap.update(ctx);
+            }
+            None => {
+                let mut overlap = SensorOverlap::new(world_id, body_id);
+                overlap.update_entities(current_inside);
+                overlap.insert(ctx);
+            }
+        }
+    }
+
+    for overlap in previous.into_values() {
+        overlap.delete(ctx);
+    }
+}
+
+/// Step a world in memory from its loaded rows
+///
+/// This is the part of `step_world` between `WorldRows::load` and writing
+/// the output back, nothing is read from or written to the database.
+pub fn simulate(
+    world: &PhysicsWorld,
+    rows: &WorldRows,
+    kinematic_entities: impl Iterator<Item = KinematicBody>,
+    forces: impl Iterator<Item = ExternalForce>,
+) -> StepOutput {
+    #[cfg(feature = "dim2")]
+    {
+        world_2d::simulate_2d(world, rows, kinematic_entities, forces)
+    }
+    #[cfg(feature = "dim3")]
+    {
+        world_3d::simulate_3d(world, rows, kinematic_entities, forces)
+    }
+}
+
 /// Main physics simulation step
 ///
 /// This function:
@@ -56,8 +361,31 @@ pub fn step_world(
     ctx: &ReducerContext,
     world: &PhysicsWorld,
     kinematic_entities: impl Iterator<Item = KinematicBody>,
-) {
-    world_2d::step_world_2d(ctx, world, kinematic_entities);
+) -> Result<StepReport, PhysicsError> {
+    step_world_with_forces(ctx, world, kinematic_entities, std::iter::empty())
+}
+
+/// Step the simulation with extra forces applied to some bodies this tick only
+///
+/// Each entry is `(body_id, force, torque)`. The forces are applied to dynamic
+/// bodies before integration and are never stored, so gameplay forces (thrust,
+/// wind) don't need to be round-tripped through the tables every frame.
+#[cfg(feature = "dim2")]
+pub fn step_world_with_forces(
+    ctx: &ReducerContext,
+    world: &PhysicsWorld,
+    kinematic_entities: impl Iterator<Item = KinematicBody>,
+    forces: impl Iterator<Item = ExternalForce>,
+) -> Result<StepReport, PhysicsError> {
+    check_world(ctx, world)?;
+    let rows = WorldRows::load(ctx, world);
+    let output = simulate(world, &rows, kinematic_entities, forces);
+    let exited = output.exited_bodies();
+    let mut report = output.write(ctx, world);
+    report.tick = advance_tick(ctx, world);
+    report.expired_bodies = expire_bodies(ctx, world.id, report.tick);
+    report.despawned_bodies = despawn_exited_bodies(ctx, world.id, &exited);
+    Ok(report)
 }
 
 /// Main physics simulation step
@@ -75,6 +403,1422 @@ pub fn step_world(
     ctx: &ReducerContext,
     world: &PhysicsWorld,
     kinematic_entities: impl Iterator<Item = KinematicBody>,
-) {
-    world_3d::step_world_3d(ctx, world, kinematic_entities);
+) -> Result<StepReport, PhysicsError> {
+    step_world_with_forces(ctx, world, kinematic_entities, std::iter::empty())
+}
+
+/// Step the simulation with extra forces applied to some bodies this tick only
+///
+/// Each entry is `(body_id, force, torque)`. The forces are applied to dynamic
+/// bodies before integration and are never stored, so gameplay forces (thrust,
+/// wind) don't need to be round-tripped through the tables every frame.
+#[cfg(feature = "dim3")]
+pub fn step_world_with_forces(
+    ctx: &ReducerContext,
+    world: &PhysicsWorld,
+    kinematic_entities: impl Iterator<Item = KinematicBody>,
+    forces: impl Iterator<Item = ExternalForce>,
+) -> Result<StepReport, PhysicsError> {
+    check_world(ctx, world)?;
+    let rows = WorldRows::load(ctx, world);
+    let output = simulate(world, &rows, kinematic_entities, forces);
+    let exited = output.exited_bodies();
+    let mut report = output.write(ctx, world);
+    report.tick = advance_tick(ctx, world);
+    report.expired_bodies = expire_bodies(ctx, world.id, report.tick);
+    report.despawned_bodies = despawn_exited_bodies(ctx, world.id, &exited);
+    Ok(report)
+}
+
+/// List every contact in a world as it currently is, without stepping it
+///
+/// Unlike `CollisionEvent`, this is a full snapshot: one entry per touching
+/// pair of enabled bodies, sorted by body IDs. Nothing is written back.
+pub fn world_contacts(ctx: &ReducerContext, world: &PhysicsWorld) -> Vec<ContactPair> {
+    if let Err(err) = check_dimension(world) {
+        log::error!("world_contacts: {}", err);
+        return Vec::new();
+    }
+
+    #[cfg(feature = "dim2")]
+    let mut contacts = world_2d::world_contacts_2d(
+        world,
+        &RigidBody::collect_in_world(ctx, world.id),
+        &Collider::collect_in_world(ctx, world.id),
+        &RigidBodyProperties::collect_in_world(ctx, world.id),
+    );
+    #[cfg(feature = "dim3")]
+    let mut contacts = world_3d::world_contacts_3d(
+        world,
+        &RigidBody::collect_in_world(ctx, world.id),
+        &Collider::collect_in_world(ctx, world.id),
+        &RigidBodyProperties::collect_in_world(ctx, world.id),
+    );
+
+    contacts.sort_by_key(|c| (c.body_a_id, c.body_b_id));
+    contacts
+}
+
+/// Step several physics worlds in one call
+///
+/// Useful for servers hosting many lobbies. `kinematic_provider` is called
+/// once per world and returns the kinematic updates for that world. Worlds
+/// that no longer exist or fail to step are logged and skipped. Each world
+/// only loads its own entities, so worlds stay isolated from each other.
+pub fn step_worlds<I>(
+    ctx: &ReducerContext,
+    world_ids: impl IntoIterator<Item = PhysicsWorldId>,
+    mut kinematic_provider: impl FnMut(PhysicsWorldId) -> I,
+) where
+    I: Iterator<Item = KinematicBody>,
+{
+    for world_id in world_ids {
+        let Some(world) = PhysicsWorld::find(ctx, world_id) else {
+            log::warn!("step_worlds: world {} not found, skipping", world_id);
+            continue;
+        };
+
+        if let Err(err) = step_world(ctx, &world, kinematic_provider(world_id)) {
+            log::error!("step_worlds: {}, skipping step", err);
+        }
+    }
+}
+
+/// Increment the stored tick of a world after a step and return it
+///
+/// Re-reads the row so changes made to the world during the step are kept.
+fn advance_tick(ctx: &ReducerContext, world: &PhysicsWorld) -> u64 {
+    match PhysicsWorld::find(ctx, world.id) {
+        Some(mut stored) => {
+            stored.advance_tick();
+            stored.update(ctx).tick
+        }
+        None => world.tick,
+    }
+}
+
+/// Delete the bodies whose `expire_at_tick` was reached and return their IDs
+fn expire_bodies(ctx: &ReducerContext, world_id: PhysicsWorldId, tick: u64) -> Vec<RigidBodyId> {
+    despawn_bodies(ctx, world_id, |body| is_expired(body, tick))
+}
+
+/// Whether a body's `expire_at_tick` was reached at `tick`
+fn is_expired(body: &RigidBody, tick: u64) -> bool {
+    body.expire_at_tick.is_some_and(|at| at <= tick)
+}
+
+/// Delete the `exited` bodies, from `StepOutput::exited_bodies`, and return their IDs
+///
+/// Disabled bodies also leave triggers, they are kept.
+fn despawn_exited_bodies(
+    ctx: &ReducerContext,
+    world_id: PhysicsWorldId,
+    exited: &HashSet<RigidBodyId>,
+) -> Vec<RigidBodyId> {
+    if exited.is_empty() {
+        return Vec::new();
+    }
+    despawn_bodies(ctx, world_id, |body| body.enabled && exited.contains(&body.id))
+}
+
+/// Delete the bodies matching `despawn` and return their IDs
+///
+/// Their colliders and properties go with them, unless another body or
+/// trigger of the world still references them.
+fn despawn_bodies(
+    ctx: &ReducerContext,
+    world_id: PhysicsWorldId,
+    despawn: impl Fn(&RigidBody) -> bool,
+) -> Vec<RigidBodyId> {
+    let trigger_colliders = Trigger::all_in_world(ctx, world_id).map(|trigger| trigger.collider_id);
+    let plan = plan_despawn(RigidBody::all_in_world(ctx, world_id), trigger_colliders, despawn);
+
+    for body in &plan.bodies {
+        body.delete(ctx);
+    }
+    for &collider_id in &plan.colliders {
+        if let Some(collider) = Collider::find(ctx, collider_id) {
+            collider.delete(ctx);
+        }
+    }
+    for &properties_id in &plan.properties {
+        if let Some(properties) = RigidBodyProperties::find(ctx, properties_id) {
+            properties.delete(ctx);
+        }
+    }
+
+    plan.bodies.iter().map(|body| body.id).collect()
+}
+
+/// Rows deleted by `despawn_bodies`
+#[derive(Debug, Default)]
+struct DespawnPlan {
+    bodies: Vec<RigidBody>,
+    colliders: Vec<ColliderId>,
+    properties: Vec<u64>,
+}
+
+/// Pick the bodies matching `despawn`, plus the colliders and properties no
+/// remaining body or trigger references
+///
+/// `trigger_colliders` is only read if a body is despawned.
+fn plan_despawn(
+    bodies: impl IntoIterator<Item = RigidBody>,
+    trigger_colliders: impl IntoIterator<Item = ColliderId>,
+    despawn: impl Fn(&RigidBody) -> bool,
+) -> DespawnPlan {
+    let (despawned, remaining): (Vec<_>, Vec<_>) = bodies.into_iter().partition(|body| despawn(body));
+    if despawned.is_empty() {
+        return DespawnPlan::default();
+    }
+
+    let mut used_colliders: HashSet<ColliderId> = remaining.iter().map(|body| body.collider_id).collect();
+    used_colliders.extend(trigger_colliders);
+    let mut used_properties: HashSet<u64> = remaining.iter().map(|body| body.properties_id).collect();
+
+    let mut plan = DespawnPlan::default();
+    for body in despawned {
+        // Inserting into the used sets keeps rows shared by despawned bodies from being listed twice
+        if body.collider_id != 0 && used_colliders.insert(body.collider_id) {
+            plan.colliders.push(body.collider_id);
+        }
+        if body.properties_id != 0 && used_properties.insert(body.properties_id) {
+            plan.properties.push(body.properties_id);
+        }
+        plan.bodies.push(body);
+    }
+    plan
+}
+
+/// Everything that prevents a whole world from stepping
+fn check_world(ctx: &ReducerContext, world: &PhysicsWorld) -> Result<(), PhysicsError> {
+    check_world_row(PhysicsWorld::find(ctx, world.id).is_some(), world)
+}
+
+/// Same as `check_world`, `exists` tells if the world row is still in the table
+fn check_world_row(exists: bool, world: &PhysicsWorld) -> Result<(), PhysicsError> {
+    if !exists {
+        return Err(PhysicsError::WorldNotFound(world.id));
+    }
+    check_dimension(world)?;
+    check_gravity(world)
+}
+
+/// Reject worlds created for another dimension
+fn check_dimension(world: &PhysicsWorld) -> Result<(), PhysicsError> {
+    if world.matches_compiled_dimension() {
+        return Ok(());
+    }
+    Err(PhysicsError::DimensionMismatch {
+        world_id: world.id,
+        dimension: world.dimension,
+    })
+}
+
+/// Reject worlds whose gravity would fill the tables with NaNs
+fn check_gravity(world: &PhysicsWorld) -> Result<(), PhysicsError> {
+    if world.has_valid_gravity() {
+        return Ok(());
+    }
+    Err(PhysicsError::NonFiniteGravity(world.id))
+}
+
+/// Check if a step left a body at rest where it was loaded, within `epsilon`
+///
+/// The body must be nearly still: both velocities at most `epsilon` after
+/// the step. A slow body is always written, otherwise its motion would be
+/// dropped every step and it would never move. Positions and velocities are
+/// then compared by distance, rotations by angle (about `epsilon` radians).
+/// Bodies with a kinematic target, which the write-back clears, always count
+/// as changed.
+fn is_unchanged(updated: &RigidBody, loaded: &RigidBody, epsilon: f32) -> bool {
+    if loaded.has_kinematic_target {
+        return false;
+    }
+    if updated.linear_velocity().length() > epsilon || updated.angular_velocity().length() > epsilon {
+        return false;
+    }
+    // 1 - cos(angle / 2) is about angle² / 8 for small angles
+    let rotation_change = 1.0 - updated.rotation().dot(loaded.rotation()).abs();
+    updated.position().distance(loaded.position()) <= epsilon
+        && rotation_change <= epsilon * epsilon / 8.0
+        && updated.linear_velocity().distance(loaded.linear_velocity()) <= epsilon
+        && updated.angular_velocity().distance(loaded.angular_velocity()) <= epsilon
+}
+
+/// Check a body before adding it to the simulation
+///
+/// An ID of 0 means no collider or default properties and is not an error.
+/// `shape_errors` holds the colliders of the world that failed validation.
+fn check_body<C, P>(
+    body: &RigidBody,
+    colliders: &HashMap<ColliderId, C>,
+    shape_errors: &HashMap<ColliderId, PhysicsError>,
+    properties: &HashMap<u64, P>,
+) -> Result<(), PhysicsError> {
+    if !body.is_finite() {
+        return Err(PhysicsError::NonFiniteState(body.id));
+    }
+    if let Some(err) = shape_errors.get(&body.collider_id) {
+        return Err(err.clone());
+    }
+    if body.collider_id != 0 && !colliders.contains_key(&body.collider_id) {
+        return Err(PhysicsError::MissingCollider {
+            body_id: body.id,
+            collider_id: body.collider_id,
+        });
+    }
+    if body.properties_id != 0 && !properties.contains_key(&body.properties_id) {
+        return Err(PhysicsError::MissingProperties {
+            body_id: body.id,
+            properties_id: body.properties_id,
+        });
+    }
+    Ok(())
+}
+
+/// An in-memory world for tests, stepped like `step_world` but without a database
+#[cfg(test)]
+#[allow(dead_code)]
+pub(crate) mod test_utils {
+    use super::*;
+
+    pub struct TestWorld {
+        pub world: PhysicsWorld,
+        pub rows: WorldRows,
+        /// What the last step would have written
+        pub output: StepOutput,
+        next_id: u64,
+    }
+
+    impl TestWorld {
+        /// An empty world with id 1
+        pub fn new(world: PhysicsWorld) -> Self {
+            Self {
+                world: PhysicsWorld { id: 1, ..world },
+                rows: WorldRows::default(),
+                output: StepOutput::default(),
+                next_id: 1,
+            }
+        }
+
+        /// An empty world with the default settings
+        pub fn with_defaults() -> Self {
+            Self::new(PhysicsWorld::builder().build())
+        }
+
+        /// Ids are unique across every table, like a fresh database
+        fn next_id(&mut self) -> u64 {
+            let id = self.next_id;
+            self.next_id += 1;
+            id
+        }
+
+        pub fn add_collider(&mut self, collider: Collider) -> ColliderId {
+            let id = self.next_id();
+            self.rows.colliders.push(Collider { id, world_id: self.world.id, ..collider });
+            id
+        }
+
+        pub fn add_properties(&mut self, properties: RigidBodyProperties) -> u64 {
+            let id = self.next_id();
+            self.rows.properties.push(RigidBodyProperties { id, world_id: self.world.id, ..properties });
+            id
+        }
+
+        /// Add a body with its own collider and the default properties
+        pub fn add_body(&mut self, body: RigidBody, collider: Collider) -> RigidBodyId {
+            self.add_body_with(body, collider, RigidBodyProperties::default())
+        }
+
+        /// Add a body with its own collider and properties
+        pub fn add_body_with(
+            &mut self,
+            body: RigidBody,
+            collider: Collider,
+            properties: RigidBodyProperties,
+        ) -> RigidBodyId {
+            let collider_id = self.add_collider(collider);
+            let properties_id = self.add_properties(properties);
+            self.insert_body(RigidBody { collider_id, properties_id, ..body })
+        }
+
+        /// Add a body row as is, its collider and properties must already exist
+        pub fn insert_body(&mut self, body: RigidBody) -> RigidBodyId {
+            let id = self.next_id();
+            self.rows.bodies.push(RigidBody { id, world_id: self.world.id, ..body });
+            id
+        }
+
+        pub fn add_trigger(&mut self, trigger: Trigger, collider: Collider) -> u64 {
+            let collider_id = self.add_collider(collider);
+            let id = self.next_id();
+            self.rows.triggers.push(Trigger { id, world_id: self.world.id, collider_id, ..trigger });
+            id
+        }
+
+        pub fn add_raycast(&mut self, raycast: RayCast) -> u64 {
+            let id = self.next_id();
+            self.rows.raycasts.push(RayCast { id, world_id: self.world.id, ..raycast });
+            id
+        }
+
+        pub fn body(&self, id: RigidBodyId) -> &RigidBody {
+            self.rows.bodies.iter().find(|b| b.id == id).expect("body not found")
+        }
+
+        pub fn body_mut(&mut self, id: RigidBodyId) -> &mut RigidBody {
+            self.rows.bodies.iter_mut().find(|b| b.id == id).expect("body not found")
+        }
+
+        pub fn trigger(&self, id: u64) -> &Trigger {
+            self.rows.triggers.iter().find(|t| t.id == id).expect("trigger not found")
+        }
+
+        pub fn raycast(&self, id: u64) -> &RayCast {
+            self.rows.raycasts.iter().find(|r| r.id == id).expect("raycast not found")
+        }
+
+        /// The contacts `world_contacts` would list for the current rows, unsorted
+        pub fn contacts(&self) -> Vec<ContactPair> {
+            let rows = &self.rows;
+            #[cfg(feature = "dim2")]
+            {
+                world_2d::world_contacts_2d(&self.world, &rows.bodies, &rows.colliders, &rows.properties)
+            }
+            #[cfg(feature = "dim3")]
+            {
+                world_3d::world_contacts_3d(&self.world, &rows.bodies, &rows.colliders, &rows.properties)
+            }
+        }
+
+        /// Ids of the bodies the last step wrote back
+        pub fn written_bodies(&self) -> Vec<RigidBodyId> {
+            self.output.bodies.iter().map(|b| b.id).collect()
+        }
+
+        pub fn step(&mut self) {
+            self.step_with(Vec::new(), Vec::new());
+        }
+
+        pub fn step_n(&mut self, steps: usize) {
+            for _ in 0..steps {
+                self.step();
+            }
+        }
+
+        /// Step with kinematic updates and external forces, then apply the output to the rows
+        pub fn step_with(&mut self, kinematic: Vec<KinematicBody>, forces: Vec<ExternalForce>) {
+            let output = simulate(&self.world, &self.rows, kinematic.into_iter(), forces.into_iter());
+
+            for updated in &output.bodies {
+                *self.body_mut(updated.id) = *updated;
+            }
+            for updated in &output.triggers {
+                if let Some(trigger) = self.rows.triggers.iter_mut().find(|t| t.id == updated.id) {
+                    *trigger = updated.clone();
+                }
+            }
+            for updated in &output.raycasts {
+                if let Some(raycast) = self.rows.raycasts.iter_mut().find(|r| r.id == updated.id) {
+                    *raycast = updated.clone();
+                }
+            }
+            self.world.advance_tick();
+            self.output = output;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::TestWorld;
+    use super::*;
+    use crate::math::Vec3;
+    use crate::tables::{BodyTypeFlags, CollisionTypeFlags, RigidBodyType};
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn ball_falls_and_rests_on_floor() {
+        let mut world = TestWorld::with_defaults();
+        let floor = RigidBody {
+            position_y: -0.5,
+            body_type: RigidBodyType::Static,
+            ..Default::default()
+        };
+        world.add_body(floor, Collider::cuboid(1, Vec3::new(20.0, 0.5, 20.0)));
+        let ball = world.add_body(RigidBody { position_y: 3.0, ..Default::default() }, Collider::ball(1, 0.5));
+
+        world.step_n(240);
+
+        let ball = world.body(ball);
+        assert!((ball.position_y - 0.5).abs() < 0.05, "ball at {}", ball.position_y);
+        assert!(ball.linear_velocity().length() < 0.1);
+    }
+
+    #[test]
+    fn world_of_the_other_dimension_is_rejected() {
+        let other = if COMPILED_DIMENSION == 3 { 2 } else { 3 };
+        let world = PhysicsWorld::builder().id(7).dim(other).build();
+
+        assert_eq!(
+            check_dimension(&world),
+            Err(PhysicsError::DimensionMismatch { world_id: 7, dimension: other })
+        );
+        assert_eq!(check_dimension(&PhysicsWorld::builder().build()), Ok(()));
+    }
+
+    #[test]
+    fn kinematic_only_trigger_ignores_dynamic_projectile() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let players_only = Trigger { detect_body_types: BodyTypeFlags::KINEMATIC, ..Default::default() };
+        let zone = world.add_trigger(players_only, Collider::ball(1, 2.0));
+        let any = world.add_trigger(Trigger::default(), Collider::ball(1, 2.0));
+        let projectile = world.add_body(RigidBody::default(), Collider::ball(1, 0.1));
+        let player = world.add_body(
+            RigidBody { position_x: 0.5, body_type: RigidBodyType::Kinematic, ..Default::default() },
+            Collider::ball(1, 0.5),
+        );
+
+        world.step();
+
+        assert_eq!(world.trigger(zone).entities_inside, vec![player]);
+        let mut inside = world.trigger(any).entities_inside.clone();
+        inside.sort();
+        assert_eq!(inside, vec![projectile, player]);
+    }
+
+    #[test]
+    fn worlds_step_in_isolation() {
+        // Same place, different worlds: the balls would collide if they shared one
+        let mut slow = TestWorld::new(PhysicsWorld::builder().gravity_y(-1.0).build());
+        let mut fast = TestWorld::new(PhysicsWorld::builder().gravity_y(-20.0).build());
+        let slow_ball = slow.add_body(RigidBody::default(), Collider::ball(1, 0.5));
+        let fast_ball = fast.add_body(RigidBody::default(), Collider::ball(1, 0.5));
+
+        for _ in 0..30 {
+            for world in [&mut slow, &mut fast] {
+                world.step();
+                assert!(world.output.contact_pairs.is_empty());
+            }
+        }
+
+        // Free fall for half a second, each under its own gravity only
+        let (slow_y, fast_y) = (slow.body(slow_ball).position_y, fast.body(fast_ball).position_y);
+        assert!((slow_y + 0.5 * 1.0 * 0.25).abs() < 0.02, "slow ball at {}", slow_y);
+        assert!((fast_y + 0.5 * 20.0 * 0.25).abs() < 0.2, "fast ball at {}", fast_y);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn character_is_pushed_sideways_but_never_tips() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let bundle = crate::tables::CharacterBundle::new(1, Vec3::ZERO, 1.8, 0.4, 0, RigidBodyType::Dynamic);
+        let character = world.add_body(bundle.body, bundle.collider);
+        // Same capsule without the locks, to show the push does tip a free body
+        let free = RigidBody { position_x: 10.0, ..Default::default() };
+        let free = world.add_body(free, Collider::character(1, 1.8, 0.4));
+
+        // A shove at shoulder height: sideways force plus the torque it exerts
+        let push = |id| (id, Vec3::new(5.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -3.0));
+        for _ in 0..30 {
+            world.step_with(Vec::new(), vec![push(character), push(free)]);
+            let rotation = world.body(character).rotation();
+            assert!(rotation.x.abs() < 1e-5 && rotation.z.abs() < 1e-5, "character tipped: {:?}", rotation);
+        }
+
+        assert!(world.body(character).position_x > 0.5);
+        assert!(world.body(free).rotation().z.abs() > 0.1);
+    }
+
+    #[test]
+    fn frozen_body_stops_mid_air_and_falls_again_once_thawed() {
+        let mut world = TestWorld::with_defaults();
+        let ball = world.add_body(RigidBody { position_y: 100.0, ..Default::default() }, Collider::ball(1, 0.5));
+        world.step_n(10);
+        let falling = world.body(ball).linear_velocity_y;
+        assert!(falling < 0.0);
+
+        world.body_mut(ball).freeze();
+        let frozen_at = world.body(ball).position_y;
+        world.step_n(10);
+        assert!(!world.written_bodies().contains(&ball));
+        assert_eq!(world.body(ball).position_y, frozen_at);
+        assert_eq!(world.body(ball).linear_velocity_y, falling);
+
+        world.body_mut(ball).unfreeze();
+        world.step_n(10);
+        assert!(world.body(ball).position_y < frozen_at);
+        assert!(world.body(ball).linear_velocity_y < falling);
+    }
+
+    #[cfg(feature = "dim2")]
+    #[test]
+    fn stray_z_is_snapped_to_the_plane_on_load() {
+        let world = PhysicsWorld::builder().gravity_y(0.0).zero_z_on_load(true).build();
+        let mut world = TestWorld::new(world);
+        let body = RigidBody { position_z: 5.0, linear_velocity_z: 2.0, ..Default::default() };
+        let body = world.add_body(body, Collider::ball(1, 0.5));
+
+        world.step();
+
+        assert!(world.written_bodies().contains(&body));
+        assert_eq!(world.body(body).position_z, 0.0);
+        assert_eq!(world.body(body).linear_velocity_z, 0.0);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn only_contacts_above_the_restitution_threshold_bounce() {
+        let world = PhysicsWorld::builder().gravity_y(0.0).restitution_velocity_threshold(2.0).build();
+        let mut world = TestWorld::new(world);
+        let bouncy = RigidBodyProperties { restitution: 1.0, ..Default::default() };
+        let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+        world.add_body_with(floor, Collider::cuboid(1, Vec3::new(20.0, 0.5, 20.0)), bouncy);
+        let fast = RigidBody { position_x: -5.0, position_y: 0.6, linear_velocity_y: -5.0, ..Default::default() };
+        let fast = world.add_body_with(fast, Collider::ball(1, 0.5), bouncy);
+        let slow = RigidBody { position_x: 5.0, position_y: 0.6, linear_velocity_y: -1.0, ..Default::default() };
+        let slow = world.add_body_with(slow, Collider::ball(1, 0.5), bouncy);
+
+        world.step_n(30);
+
+        assert!(world.body(fast).linear_velocity_y > 4.0, "fast ball: {}", world.body(fast).linear_velocity_y);
+        let slow = world.body(slow);
+        assert!(slow.linear_velocity_y.abs() < 0.1, "slow ball: {}", slow.linear_velocity_y);
+        assert!((slow.position_y - 0.5).abs() < 0.05, "slow ball at {}", slow.position_y);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn enlarged_collider_spins_up_slower() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let body = world.add_body(RigidBody::default(), Collider::ball(1, 0.5));
+        let twist = || vec![(body, Vec3::ZERO, Vec3::new(0.0, 1.0, 0.0))];
+
+        world.step_with(Vec::new(), twist());
+        let small_spin = world.body(body).angular_velocity_y;
+
+        // Mass properties come from the collider every step, nothing to invalidate
+        let larger = world.add_collider(Collider::ball(1, 1.0));
+        let row = world.body_mut(body);
+        row.collider_id = larger;
+        row.set_angular_velocity(Vec3::ZERO);
+        world.step_with(Vec::new(), twist());
+        let large_spin = world.body(body).angular_velocity_y;
+
+        assert!(small_spin > 0.0);
+        // Inertia grows with r⁵, 32 times for twice the radius
+        assert!((small_spin / large_spin - 32.0).abs() < 0.5, "{} vs {}", small_spin, large_spin);
+    }
+
+    #[test]
+    fn sensor_collider_reports_bodies_it_passes() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let magnet = RigidBody { linear_velocity_x: 10.0, ..Default::default() };
+        let magnet = world.add_body(magnet, Collider { is_sensor: true, ..Collider::ball(1, 2.0) });
+        let crate_at = |x: f32, y: f32| RigidBody {
+            position_x: x,
+            position_y: y,
+            body_type: RigidBodyType::Static,
+            ..Default::default()
+        };
+        let near = world.add_body(crate_at(5.0, 0.0), Collider::ball(1, 0.5));
+        let far = world.add_body(crate_at(15.0, 0.0), Collider::ball(1, 0.5));
+        let out_of_reach = world.add_body(crate_at(10.0, 10.0), Collider::ball(1, 0.5));
+
+        // Step at which each body first shows up in the magnet's overlaps
+        let mut first_seen = HashMap::new();
+        for step in 0..120 {
+            world.step();
+            let (owner, inside) = &world.output.sensor_overlaps[0];
+            assert_eq!(*owner, magnet);
+            for &id in inside {
+                first_seen.entry(id).or_insert(step);
+            }
+        }
+
+        assert!(first_seen[&near] < first_seen[&far]);
+        assert!(!first_seen.contains_key(&out_of_reach));
+        assert!(!first_seen.contains_key(&magnet));
+        // The sensor doesn't push anything
+        assert_eq!(world.body(magnet).linear_velocity_x, 10.0);
+    }
+
+    #[test]
+    fn touching_bodies_produce_one_contact_pair() {
+        let mut world = TestWorld::with_defaults();
+        let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+        let floor = world.add_body(floor, Collider::cuboid(1, Vec3::new(20.0, 0.5, 20.0)));
+        // Slightly sunk into the floor
+        let ball = world.add_body(RigidBody { position_y: 0.49, ..Default::default() }, Collider::ball(1, 0.5));
+        world.add_body(RigidBody { position_y: 5.0, ..Default::default() }, Collider::ball(1, 0.5));
+
+        let contacts = world.contacts();
+
+        assert_eq!(contacts.len(), 1);
+        let contact = contacts[0];
+        assert_eq!((contact.body_a_id.min(contact.body_b_id), contact.body_a_id.max(contact.body_b_id)), (floor, ball));
+        assert!(contact.distance < 0.0);
+    }
+
+    #[test]
+    fn contacts_skip_pairs_the_step_does_not_collide() {
+        let contacts_with = |kinematic_vs_kinematic: bool| {
+            let world = PhysicsWorld::builder().gravity_y(0.0).kinematic_vs_kinematic(kinematic_vs_kinematic).build();
+            let mut world = TestWorld::new(world);
+            // Overlapping walls, and two overlapping players away from them
+            let wall = RigidBody { body_type: RigidBodyType::Static, ..Default::default() };
+            world.add_body(wall, Collider::cuboid(1, Vec3::new(1.0, 1.0, 1.0)));
+            world.add_body(RigidBody { position_x: 1.5, ..wall }, Collider::cuboid(1, Vec3::new(1.0, 1.0, 1.0)));
+            let player = RigidBody { position_x: 10.0, body_type: RigidBodyType::Kinematic, ..Default::default() };
+            let a = world.add_body(player, Collider::ball(1, 0.5));
+            let b = world.add_body(RigidBody { position_x: 10.5, ..player }, Collider::ball(1, 0.5));
+            let pairs: Vec<_> = world
+                .contacts()
+                .iter()
+                .map(|c| (c.body_a_id.min(c.body_b_id), c.body_a_id.max(c.body_b_id)))
+                .collect();
+            (pairs, (a, b))
+        };
+
+        assert_eq!(contacts_with(false).0, Vec::new());
+        let (pairs, players) = contacts_with(true);
+        assert_eq!(pairs, vec![players]);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn kinematic_bodies_moved_into_each_other_are_separated() {
+        let overlap_after = |kinematic_vs_kinematic: bool| {
+            let world = PhysicsWorld::builder().gravity_y(0.0).kinematic_vs_kinematic(kinematic_vs_kinematic).build();
+            let mut world = TestWorld::new(world);
+            let kinematic = RigidBody { body_type: RigidBodyType::Kinematic, ..Default::default() };
+            let a = world.add_body(RigidBody { position_x: -2.0, ..kinematic }, Collider::ball(1, 0.5));
+            let b = world.add_body(RigidBody { position_x: 2.0, ..kinematic }, Collider::ball(1, 0.5));
+
+            // Drive both players until their spheres overlap by 0.4
+            world.step_with(
+                vec![(a, (Vec3::new(-0.3, 0.0, 0.0), Quat::IDENTITY)), (b, (Vec3::new(0.3, 0.0, 0.0), Quat::IDENTITY))],
+                Vec::new(),
+            );
+            world.step_n(2);
+
+            1.0 - (world.body(b).position_x - world.body(a).position_x)
+        };
+
+        assert!(overlap_after(true) < 0.02, "overlap {}", overlap_after(true));
+        assert!((overlap_after(false) - 0.4).abs() < 1e-4, "overlap {}", overlap_after(false));
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn separated_interpolated_kinematic_bodies_are_written_once_with_their_velocity() {
+        let world = PhysicsWorld::builder()
+            .gravity_y(0.0)
+            .kinematic_vs_kinematic(true)
+            .kinematic_interpolation(true)
+            .build();
+        let mut world = TestWorld::new(world);
+        let kinematic = RigidBody { body_type: RigidBodyType::Kinematic, ..Default::default() };
+        let a = world.add_body(RigidBody { position_x: -1.0, ..kinematic }, Collider::ball(1, 0.5));
+        let b = world.add_body(RigidBody { position_x: 1.0, ..kinematic }, Collider::ball(1, 0.5));
+
+        // 0.3 closer each step, they overlap by 0.2 once the second step is written
+        for step in 1..=3 {
+            let offset = 1.0 - 0.3 * step as f32;
+            let targets = vec![
+                (a, (Vec3::new(-offset, 0.0, 0.0), Quat::IDENTITY)),
+                (b, (Vec3::new(offset, 0.0, 0.0), Quat::IDENTITY)),
+            ];
+            world.step_with(targets, Vec::new());
+            let mut written = world.written_bodies();
+            written.sort_unstable();
+            assert_eq!(written, vec![a, b], "step {step}");
+        }
+
+        // Pushed apart on top of the move, and still moving towards each other
+        let gap = world.body(b).position_x - world.body(a).position_x;
+        assert!(gap > 0.3, "gap {gap}");
+        assert!(world.body(a).linear_velocity_x > 0.0);
+        assert!(world.body(b).linear_velocity_x < 0.0);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn response_disabled_body_is_hit_by_rays_but_not_pushed() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let dummy = world.add_body(RigidBody { response_enabled: false, ..Default::default() }, Collider::ball(1, 0.5));
+        let faller = world.add_body(
+            RigidBody { position_y: 3.0, linear_velocity_y: -5.0, ..Default::default() },
+            Collider::ball(1, 0.5),
+        );
+        let ray = world.add_raycast(RayCast {
+            origin_x: -5.0,
+            direction_x: 1.0,
+            direction_y: 0.0,
+            ..Default::default()
+        });
+
+        world.step();
+        let hits: Vec<u64> = world.raycast(ray).hits.iter().map(|hit| hit.rigid_body_id).collect();
+        assert_eq!(hits, vec![dummy]);
+
+        world.step_n(60);
+
+        let dummy = world.body(dummy);
+        assert_eq!(dummy.position(), Vec3::ZERO);
+        assert_eq!(dummy.linear_velocity(), Vec3::ZERO);
+        // The falling body went straight through
+        assert!(world.body(faller).position_y < -1.0, "faller at {}", world.body(faller).position_y);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn zero_mass_dynamic_body_stays_put_without_nans() {
+        let mut world = TestWorld::with_defaults();
+        let massless = RigidBodyProperties { mass: 0.0, ..Default::default() };
+        let body =
+            world.add_body_with(RigidBody { position_y: 2.0, ..Default::default() }, Collider::ball(1, 0.5), massless);
+        // Lands on the massless body
+        let landed = world.add_body(RigidBody { position_y: 4.0, ..Default::default() }, Collider::ball(1, 0.5));
+
+        world.step_n(120);
+
+        let body = world.body(body);
+        assert_eq!(body.position(), Vec3::new(0.0, 2.0, 0.0));
+        assert_eq!(body.linear_velocity(), Vec3::ZERO);
+        let landed = world.body(landed);
+        assert!(landed.position().is_finite() && landed.linear_velocity().is_finite());
+        assert!(landed.position_y > 2.5, "landed at {}", landed.position_y);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn external_force_cancels_gravity_for_its_body_only() {
+        let mut world = TestWorld::with_defaults();
+        // A unit cube of density 1 weighs 1 kg
+        let cube = || Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5));
+        let held = world.add_body(RigidBody { position_y: 10.0, ..Default::default() }, cube());
+        let free = world.add_body(RigidBody { position_x: 5.0, position_y: 10.0, ..Default::default() }, cube());
+
+        for _ in 0..60 {
+            world.step_with(Vec::new(), vec![(held, Vec3::new(0.0, 9.81, 0.0), Vec3::ZERO)]);
+        }
+
+        assert!((world.body(held).position_y - 10.0).abs() < 1e-3, "held at {}", world.body(held).position_y);
+        assert!(world.body(free).position_y < 6.0);
+
+        // The force isn't stored, without it the cube falls
+        world.step_n(10);
+        assert!(world.body(held).linear_velocity_y < -0.5);
+    }
+
+    #[test]
+    fn raycasts_pass_through_sensor_bodies() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let floor = RigidBody { body_type: RigidBodyType::Static, ..Default::default() };
+        let floor = world.add_body(floor, Collider::cuboid(1, Vec3::new(5.0, 0.5, 5.0)));
+        // Between the ray origin and the floor
+        let sensor = RigidBody { position_y: 2.0, ..Default::default() };
+        world.add_body(sensor, Collider { is_sensor: true, ..Collider::ball(1, 1.0) });
+        let ray = world.add_raycast(RayCast { origin_y: 5.0, ..Default::default() });
+
+        world.step();
+
+        let hits: Vec<_> = world.raycast(ray).hits.iter().map(|hit| hit.rigid_body_id).collect();
+        assert_eq!(hits, vec![floor]);
+    }
+
+    #[test]
+    fn raycast_hits_and_contacts_carry_the_body_user_data() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let floor = RigidBody { body_type: RigidBodyType::Static, user_data: 7, ..Default::default() };
+        world.add_body(floor, Collider::cuboid(1, Vec3::new(5.0, 0.5, 5.0)));
+        let player = world.add_body(
+            RigidBody { position_y: 0.99, user_data: 42, ..Default::default() },
+            Collider::ball(1, 0.5),
+        );
+        let ray = world.add_raycast(RayCast { origin_y: 5.0, ..Default::default() });
+
+        world.step();
+
+        let hit = &world.raycast(ray).hits[0];
+        assert_eq!((hit.rigid_body_id, hit.user_data), (player, 42));
+
+        let contacts = world.contacts();
+        assert_eq!(contacts.len(), 1);
+        let mut user_data = [contacts[0].body_a_user_data, contacts[0].body_b_user_data];
+        user_data.sort();
+        assert_eq!(user_data, [7, 42]);
+    }
+
+    #[test]
+    fn ray_with_a_longer_direction_hits_the_same_bodies() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let fixed = RigidBody { body_type: RigidBodyType::Static, ..Default::default() };
+        world.add_body(fixed, Collider::ball(1, 0.5));
+        // 3.5 below the ray origin, out of reach of a 3 long ray
+        world.add_body(RigidBody { position_x: 5.0, position_y: -1.0, ..fixed }, Collider::ball(1, 0.5));
+
+        let mut pairs = Vec::new();
+        for x in [0.0, 5.0] {
+            // Set through the fields, like the builder would, so nothing normalizes them up front
+            let ray = |direction_y| RayCast {
+                origin_x: x,
+                origin_y: 3.0,
+                direction_y,
+                max_distance: 3.0,
+                ..Default::default()
+            };
+            pairs.push((world.add_raycast(ray(-1.0)), world.add_raycast(ray(-2.0))));
+        }
+
+        world.step();
+
+        let hits = |id| -> Vec<(u64, f32)> {
+            world.raycast(id).hits.iter().map(|hit| (hit.rigid_body_id, hit.distance)).collect()
+        };
+        for &(unit, double) in &pairs {
+            assert_eq!(hits(unit), hits(double));
+        }
+        assert_eq!(hits(pairs[0].0).len(), 1);
+        assert!((hits(pairs[0].0)[0].1 - 2.5).abs() < 1e-4);
+        assert!(hits(pairs[1].0).is_empty());
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn entering_a_portal_reports_its_destination() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let trigger = world.add_trigger(Trigger::default(), Collider::ball(1, 1.0));
+        let destination = (Vec3::new(50.0, 2.0, -10.0), Quat::from_rotation_y(1.0));
+        let portal = PortalTrigger { id: 100, ..PortalTrigger::new(1, trigger, destination.0, destination.1) };
+        world.rows.portals.push(portal);
+        let body = world.add_body(
+            RigidBody { position_x: 3.0, linear_velocity_x: -10.0, ..Default::default() },
+            Collider::ball(1, 0.25),
+        );
+
+        let mut events = Vec::new();
+        for _ in 0..30 {
+            world.step();
+            events.extend(world.output.portal_events.iter().copied());
+        }
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!((event.portal_id, event.body_id), (100, body));
+        assert_eq!(event.destination_position(), destination.0);
+        assert_eq!(event.destination_rotation(), destination.1);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn friction_comes_from_properties_whatever_the_collider_order() {
+        let slide = |reverse_colliders: bool| {
+            let mut world = TestWorld::with_defaults();
+            let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+            world.add_body(floor, Collider::cuboid(1, Vec3::new(50.0, 0.5, 50.0)));
+            let mut sliders = Vec::new();
+            for (z, friction) in [(-2.0, 0.2), (2.0, 0.8)] {
+                let body = RigidBody { position_y: 0.5, position_z: z, linear_velocity_x: 5.0, ..Default::default() };
+                let properties = RigidBodyProperties { friction, ..Default::default() };
+                sliders.push(world.add_body_with(body, Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5)), properties));
+            }
+            if reverse_colliders {
+                world.rows.colliders.reverse();
+            }
+
+            world.step_n(30);
+            sliders.iter().map(|&id| world.body(id).position_x).collect::<Vec<_>>()
+        };
+
+        let distances = slide(false);
+        assert_eq!(distances, slide(true));
+        // The box with the stickier properties stops first
+        assert!(distances[0] > distances[1] + 0.1, "{:?}", distances);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn collision_event_includes_both_material_ids() {
+        let mut world = TestWorld::with_defaults();
+        let metal = RigidBodyProperties { material_id: 3, ..Default::default() };
+        let wood = RigidBodyProperties { material_id: 7, ..Default::default() };
+        let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+        let floor = world.add_body_with(floor, Collider::cuboid(1, Vec3::new(20.0, 0.5, 20.0)), metal);
+        let crate_ = world.add_body_with(
+            RigidBody { position_y: 0.5, ..Default::default() },
+            Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5)),
+            wood,
+        );
+
+        world.step();
+
+        assert_eq!(world.output.contact_pairs, HashSet::from([(floor, crate_)]));
+        let event = CollisionEvent::started(world.world.id, (floor, crate_), &world.output.body_materials);
+        assert_eq!((event.material_a_id, event.material_b_id), (3, 7));
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn local_forward_thrust_of_a_turned_body_pushes_along_world_x() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let mut body = RigidBody::default();
+        body.set_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2));
+        let id = world.add_body(body, Collider::ball(1, 0.5));
+
+        let (_, force, torque) = world.body(id).local_force(Vec3::new(0.0, 0.0, 10.0));
+        assert!((force - Vec3::new(10.0, 0.0, 0.0)).length() < 1e-5, "force {:?}", force);
+        assert_eq!(torque, Vec3::ZERO);
+
+        for _ in 0..10 {
+            let thrust = world.body(id).local_force(Vec3::new(0.0, 0.0, 10.0));
+            world.step_with(Vec::new(), vec![thrust]);
+        }
+
+        let velocity = world.body(id).linear_velocity();
+        assert!(velocity.x > 0.1, "velocity {:?}", velocity);
+        assert!(velocity.y.abs() < 1e-4 && velocity.z.abs() < 1e-4, "velocity {:?}", velocity);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn kinematic_target_moving_into_a_box_shoves_it() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let cube = || Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5));
+        let pusher = world.add_body(
+            RigidBody { position_x: -2.0, body_type: RigidBodyType::Kinematic, ..Default::default() },
+            cube(),
+        );
+        let crate_ = world.add_body(RigidBody::default(), cube());
+
+        for step in 1..=30 {
+            let target = Vec3::new(-2.0 + step as f32 * 0.1, 0.0, 0.0);
+            world.body_mut(pusher).set_kinematic_target(target, Quat::IDENTITY);
+            world.step();
+        }
+
+        let pusher = world.body(pusher);
+        assert!((pusher.position_x - 1.0).abs() < 1e-3, "pusher at {}", pusher.position_x);
+        assert!(!pusher.has_kinematic_target);
+        let crate_ = world.body(crate_);
+        assert!(crate_.position_x > 1.5, "crate at {}", crate_.position_x);
+        assert!(crate_.linear_velocity_x > 0.0);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn grounded_damping_slows_a_sliding_body_but_not_an_airborne_one() {
+        let mut world = TestWorld::with_defaults();
+        let slippery = RigidBodyProperties { friction: 0.0, ..Default::default() };
+        let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+        world.add_body_with(floor, Collider::cuboid(1, Vec3::new(50.0, 0.5, 50.0)), slippery);
+
+        let damped = RigidBodyProperties { grounded_linear_damping: 5.0, ..slippery };
+        let cube = || Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5));
+        let sliding = world.add_body_with(
+            RigidBody { position_y: 0.5, linear_velocity_x: 5.0, ..Default::default() },
+            cube(),
+            damped,
+        );
+        let airborne = world.add_body_with(
+            RigidBody { position_y: 40.0, linear_velocity_x: 5.0, ..Default::default() },
+            cube(),
+            damped,
+        );
+
+        world.step_n(30);
+
+        let sliding = world.body(sliding).linear_velocity_x;
+        let airborne = world.body(airborne).linear_velocity_x;
+        assert!(sliding < 1.0, "sliding at {}", sliding);
+        assert!((airborne - 5.0).abs() < 1e-3, "airborne at {}", airborne);
+    }
+
+    #[test]
+    fn unusable_worlds_are_rejected_with_their_error() {
+        let world = PhysicsWorld { id: 4, ..PhysicsWorld::builder().build() };
+        assert_eq!(check_world_row(true, &world), Ok(()));
+        assert_eq!(check_world_row(false, &world), Err(PhysicsError::WorldNotFound(4)));
+
+        let other_dimension = 5 - COMPILED_DIMENSION;
+        let mismatched = PhysicsWorld { dimension: other_dimension, ..world };
+        assert_eq!(
+            check_world_row(true, &mismatched),
+            Err(PhysicsError::DimensionMismatch { world_id: 4, dimension: other_dimension })
+        );
+
+        let mut floating = world;
+        floating.gravity_y = f32::NAN;
+        assert_eq!(check_world_row(true, &floating), Err(PhysicsError::NonFiniteGravity(4)));
+    }
+
+    #[test]
+    fn broken_bodies_are_skipped_with_their_error() {
+        let mut world = TestWorld::with_defaults();
+        let healthy = world.add_body(RigidBody::default(), Collider::ball(1, 0.5));
+        let ball = world.add_collider(Collider::ball(1, 0.5));
+        let no_collider = world.insert_body(RigidBody { collider_id: 999, ..Default::default() });
+        let no_properties =
+            world.insert_body(RigidBody { collider_id: ball, properties_id: 998, ..Default::default() });
+        let nan = world.insert_body(RigidBody { collider_id: ball, position_x: f32::NAN, ..Default::default() });
+        let bad_shape = world.add_body(RigidBody::default(), Collider::ball(1, -1.0));
+        let bad_collider = world.body(bad_shape).collider_id;
+
+        world.step();
+
+        let mut skipped = world.output.skipped_bodies.clone();
+        skipped.sort_by_key(|err| err.to_string());
+        let mut expected = vec![
+            PhysicsError::MissingCollider { body_id: no_collider, collider_id: 999 },
+            PhysicsError::MissingProperties { body_id: no_properties, properties_id: 998 },
+            PhysicsError::NonFiniteState(nan),
+            world.rows.colliders.iter().find(|c| c.id == bad_collider).unwrap().validate().unwrap_err(),
+        ];
+        expected.sort_by_key(|err| err.to_string());
+        assert_eq!(skipped, expected);
+        assert!(matches!(expected.last(), Some(PhysicsError::InvalidShape { .. })));
+        assert_eq!(world.output.bodies_stepped, 1);
+        assert!(world.body(healthy).position_y < 0.0);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn interpolated_kinematic_body_reaches_its_target_and_pushes_a_box() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).kinematic_interpolation(true).build());
+        let cube = || Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5));
+        let pusher = world.add_body(
+            RigidBody { position_x: -2.0, body_type: RigidBodyType::Kinematic, ..Default::default() },
+            cube(),
+        );
+        let crate_ = world.add_body(RigidBody::default(), cube());
+
+        for step in 1..=30 {
+            let target = Vec3::new(-2.0 + step as f32 * 0.1, 0.0, 0.0);
+            world.step_with(vec![(pusher, (target, Quat::IDENTITY))], Vec::new());
+            // Written back at the end of the step, the stored pose is the target
+            let reached = world.body(pusher).position_x;
+            assert!((reached - target.x).abs() < 1e-4, "step {step}: pusher at {reached}, target {}", target.x);
+        }
+
+        let crate_ = world.body(crate_);
+        assert!(crate_.position_x > 1.5, "crate at {}", crate_.position_x);
+        assert!(crate_.linear_velocity_x > 0.0);
+    }
+
+    #[test]
+    fn disabled_trigger_processing_leaves_trigger_rows_unchanged() {
+        let run = |process_triggers: bool| {
+            let world_row = PhysicsWorld::builder().gravity_y(0.0).process_triggers(process_triggers).build();
+            let mut world = TestWorld::new(world_row);
+            let zone = world.add_trigger(Trigger::default(), Collider::ball(1, 2.0));
+            world.add_body(RigidBody::default(), Collider::ball(1, 0.5));
+            let before = world.trigger(zone).clone();
+            world.step();
+            (before, world.trigger(zone).clone(), world.output.triggers.len())
+        };
+
+        let (before, after, updated) = run(true);
+        assert_ne!(before, after);
+        assert_eq!(updated, 1);
+
+        let (before, after, updated) = run(false);
+        assert_eq!(before, after);
+        assert_eq!(updated, 0);
+    }
+
+    #[test]
+    fn kinematic_static_contacts_need_to_be_enabled() {
+        let touching = |active_collision_types: u8| {
+            let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+            let wall = world.add_body(
+                RigidBody { body_type: RigidBodyType::Static, ..Default::default() },
+                Collider { active_collision_types, ..Collider::ball(1, 1.0) },
+            );
+            let player = world.add_body(
+                RigidBody { position_x: 1.5, body_type: RigidBodyType::Kinematic, ..Default::default() },
+                Collider::ball(1, 1.0),
+            );
+            world.step();
+            world.output.contact_pairs.contains(&(wall, player))
+        };
+
+        // Rapier's default skips kinematic-static pairs, the player would walk through the wall
+        assert!(!touching(0));
+        assert!(touching(CollisionTypeFlags::DEFAULT | CollisionTypeFlags::KINEMATIC_STATIC));
+    }
+
+    #[test]
+    fn bodies_expire_once_their_tick_is_reached_and_take_their_own_rows() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let debris =
+            world.add_body(RigidBody { expire_at_tick: Some(3), ..Default::default() }, Collider::ball(1, 0.2));
+        let keeper = world.add_body(RigidBody { position_x: 5.0, ..Default::default() }, Collider::ball(1, 0.2));
+        // Uses the keeper's collider and the debris' properties
+        let shard = world.insert_body(RigidBody {
+            position_x: -5.0,
+            collider_id: world.body(keeper).collider_id,
+            properties_id: world.body(debris).properties_id,
+            expire_at_tick: Some(3),
+            ..Default::default()
+        });
+        let plan = |world: &TestWorld| {
+            let trigger_colliders = world.rows.triggers.iter().map(|trigger| trigger.collider_id);
+            plan_despawn(world.rows.bodies.clone(), trigger_colliders, |body| is_expired(body, world.world.tick))
+        };
+
+        world.step_n(2);
+        assert!(plan(&world).bodies.is_empty());
+
+        world.step();
+        let plan = plan(&world);
+        let despawned: Vec<RigidBodyId> = plan.bodies.iter().map(|body| body.id).collect();
+        assert_eq!(despawned, vec![debris, shard]);
+        assert_eq!(plan.colliders, vec![world.body(debris).collider_id]);
+        assert_eq!(plan.properties, vec![world.body(debris).properties_id]);
+    }
+
+    #[test]
+    fn global_damping_adds_to_each_body_damping() {
+        let spin_down = |global_damping: f32, own_damping: f32| {
+            let mut world = TestWorld::new(
+                PhysicsWorld::builder()
+                    .gravity_y(0.0)
+                    .global_linear_damping(global_damping)
+                    .global_angular_damping(global_damping)
+                    .build(),
+            );
+            let properties =
+                RigidBodyProperties { linear_damping: own_damping, angular_damping: own_damping, ..Default::default() };
+            let ball = world.add_body_with(
+                RigidBody { linear_velocity_x: 5.0, angular_velocity_z: 5.0, ..Default::default() },
+                Collider::ball(1, 0.5),
+                properties,
+            );
+            world.step_n(60);
+            let ball = world.body(ball);
+            (ball.linear_velocity_x, ball.angular_velocity_z)
+        };
+
+        let (linear, angular) = spin_down(0.0, 0.0);
+        assert!((linear - 5.0).abs() < 1e-4 && (angular - 5.0).abs() < 1e-4, "undamped at {linear}, {angular}");
+
+        let (linear, angular) = spin_down(1.0, 0.0);
+        assert!(linear < 4.0 && angular < 4.0, "damped at {linear}, {angular}");
+
+        // Half from the world and half from the body damps like all of it on the body
+        let (split_linear, split_angular) = spin_down(0.5, 0.5);
+        assert!((split_linear - linear).abs() < 1e-4, "{split_linear} vs {linear}");
+        assert!((split_angular - angular).abs() < 1e-4, "{split_angular} vs {angular}");
+        let (own_linear, _) = spin_down(0.0, 1.0);
+        assert!((own_linear - linear).abs() < 1e-4, "{own_linear} vs {linear}");
+    }
+
+    #[test]
+    fn swapped_collider_changes_contacts_but_keeps_the_velocity() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let wall = world.add_body(
+            RigidBody { body_type: RigidBodyType::Static, ..Default::default() },
+            Collider::ball(1, 0.5),
+        );
+        let body = world.add_body(
+            RigidBody { position_x: 1.0, linear_velocity_x: 1.0, ..Default::default() },
+            Collider::ball(1, 0.25),
+        );
+        let large = world.add_collider(Collider::ball(1, 0.75));
+
+        world.step();
+        assert!(!world.output.contact_pairs.contains(&(wall, body)));
+
+        // What `RigidBody::set_collider` writes, the rest of the row is untouched
+        world.body_mut(body).collider_id = large;
+        world.step();
+
+        assert!(world.output.contact_pairs.contains(&(wall, body)));
+        let body = world.body(body);
+        assert_eq!(body.collider_id, large);
+        assert!(body.linear_velocity_x >= 1.0 - 1e-4, "velocity {}", body.linear_velocity_x);
+    }
+
+    #[test]
+    fn every_error_formats_a_message_naming_its_rows() {
+        let other_dimension = 5 - COMPILED_DIMENSION;
+        let cases = [
+            (PhysicsError::WorldNotFound(3), "world 3 not found".to_string()),
+            (
+                PhysicsError::DimensionMismatch { world_id: 3, dimension: other_dimension },
+                format!("world 3 is {other_dimension}D but spacetime_rapier was built for {COMPILED_DIMENSION}D"),
+            ),
+            (PhysicsError::NonFiniteGravity(3), "world 3 has non-finite gravity".to_string()),
+            (
+                PhysicsError::MissingCollider { body_id: 7, collider_id: 9 },
+                "body 7 references missing collider 9".to_string(),
+            ),
+            (
+                PhysicsError::MissingProperties { body_id: 7, properties_id: 11 },
+                "body 7 references missing properties 11".to_string(),
+            ),
+            (PhysicsError::NonFiniteState(7), "body 7 has a non-finite position or velocity".to_string()),
+            (
+                PhysicsError::InvalidShape { collider_id: 9, reason: "radius must be positive".to_string() },
+                "collider 9: radius must be positive".to_string(),
+            ),
+            (PhysicsError::ConcaveShape(9), "collider 9 is concave and can't be cast".to_string()),
+        ];
+
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+            // Usable wherever a boxed error is expected
+            let boxed: Box<dyn std::error::Error> = Box::new(error);
+            assert_eq!(boxed.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn tilted_body_with_upright_stiffness_rights_itself() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let mut tilted = RigidBody { upright_stiffness: 5.0, ..Default::default() };
+        tilted.set_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4));
+        // The spring has no damping of its own
+        let braked = RigidBodyProperties { angular_damping: 6.0, ..Default::default() };
+        let body = world.add_body_with(tilted, Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5)), braked);
+        let tilt = |world: &TestWorld| world.body(body).rotation().up().y.clamp(-1.0, 1.0).acos();
+
+        let mut previous = tilt(&world);
+        for _ in 0..5 {
+            world.step();
+            let current = tilt(&world);
+            assert!(current < previous, "tilt went from {previous} to {current}");
+            previous = current;
+        }
+        assert!(previous > 0.5, "righted too fast, tilt {previous}");
+
+        world.step_n(175);
+        assert!(tilt(&world) < 0.05, "still tilted by {}", tilt(&world));
+        assert!(world.body(body).angular_velocity().length() < 0.05);
+    }
+
+    #[test]
+    fn normals_of_a_ball_on_a_floor_point_up() {
+        for ball_first in [false, true] {
+            let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+            let add_floor = |world: &mut TestWorld| {
+                let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+                world.add_body(floor, Collider::cuboid(1, Vec3::new(5.0, 0.5, 5.0)))
+            };
+            let floor = if ball_first { None } else { Some(add_floor(&mut world)) };
+            let ball = world.add_body(RigidBody { position_y: 0.49, ..Default::default() }, Collider::ball(1, 0.5));
+            let floor = floor.unwrap_or_else(|| add_floor(&mut world));
+
+            let contacts = world.contacts();
+            assert_eq!(contacts.len(), 1);
+            let up = contacts[0].normal_for(ball);
+            assert!(up.x.abs() < 1e-4 && (up.y - 1.0).abs() < 1e-4, "ball first: {ball_first}, {up:?}");
+            let down = contacts[0].normal_for(floor);
+            assert!((down.y + 1.0).abs() < 1e-4, "ball first: {ball_first}, {down:?}");
+
+            // A ray cast down next to the ball hits the floor with the same convention
+            let ray = world.add_raycast(RayCast { origin_x: 3.0, origin_y: 5.0, ..Default::default() });
+            world.step();
+            let hit = &world.raycast(ray).hits[0];
+            assert_eq!(hit.rigid_body_id, floor);
+            assert!((hit.normal_y - 1.0).abs() < 1e-4, "ray normal y {}", hit.normal_y);
+        }
+    }
+
+    #[test]
+    fn resting_bodies_are_not_written_back_but_slow_gliders_are() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().write_back_epsilon(1e-2).build());
+        let slippery = RigidBodyProperties { friction: 0.0, ..Default::default() };
+        let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+        world.add_body_with(floor, Collider::cuboid(1, Vec3::new(50.0, 0.5, 50.0)), slippery);
+        let cube = || Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5));
+        let resting = world.add_body(RigidBody { position_y: 0.5, ..Default::default() }, cube());
+        world.step_n(120);
+        assert!(world.body(resting).linear_velocity().length() <= 1e-2);
+
+        // Moves less than the epsilon per step but is far from still
+        let glider = world.add_body_with(
+            RigidBody { position_x: 5.0, position_y: 0.5, linear_velocity_x: 0.1, ..Default::default() },
+            cube(),
+            slippery,
+        );
+        let resting_at = world.body(resting).position();
+        for _ in 0..60 {
+            world.step();
+            assert_eq!(world.written_bodies(), vec![glider]);
+        }
+
+        assert_eq!(world.body(resting).position(), resting_at);
+        let glided = world.body(glider).position_x - 5.0;
+        assert!((glided - 0.1).abs() < 1e-2, "glided {glided}");
+    }
+
+    #[test]
+    fn only_triggers_evaluated_this_step_despawn_the_bodies_that_left_them() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let zone = Trigger { auto_despawn_on_exit: true, ..Default::default() };
+        let zone = world.add_trigger(zone, Collider::ball(1, 2.0));
+        let body = world.add_body(RigidBody::default(), Collider::ball(1, 0.2));
+        let despawned = |world: &TestWorld| {
+            let exited = world.output.exited_bodies();
+            let trigger_colliders = world.rows.triggers.iter().map(|trigger| trigger.collider_id);
+            let despawn = |body: &RigidBody| body.enabled && exited.contains(&body.id);
+            let plan = plan_despawn(world.rows.bodies.clone(), trigger_colliders, despawn);
+            plan.bodies.iter().map(|body| body.id).collect::<Vec<_>>()
+        };
+        world.step();
+        assert!(despawned(&world).is_empty());
+
+        world.body_mut(body).position_x = 10.0;
+        world.step();
+        assert_eq!(despawned(&world), vec![body]);
+
+        // Disabled, the trigger keeps the exit list of the last step it was evaluated in
+        world.rows.triggers.iter_mut().find(|t| t.id == zone).unwrap().enabled = false;
+        world.body_mut(body).position_x = 0.0;
+        world.step();
+        assert_eq!(world.trigger(zone).removed_entities, vec![body]);
+        assert!(despawned(&world).is_empty());
+
+        world.rows.triggers.iter_mut().find(|t| t.id == zone).unwrap().enabled = true;
+        world.world.process_triggers = false;
+        world.step();
+        assert!(despawned(&world).is_empty());
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn rolling_resistance_slows_a_rolling_ball_until_it_stops() {
+        let mut world = TestWorld::with_defaults();
+        let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+        world.add_body(floor, Collider::cuboid(1, Vec3::new(50.0, 0.5, 50.0)));
+        // Rolling without slipping along X
+        let ball = |z: f32| RigidBody {
+            position_y: 0.5,
+            position_z: z,
+            linear_velocity_x: 3.0,
+            angular_velocity_z: -6.0,
+            ..Default::default()
+        };
+        let free = world.add_body(ball(-2.0), Collider::ball(1, 0.5));
+        let resisted = RigidBodyProperties { rolling_resistance: 2.0, ..Default::default() };
+        let resisted = world.add_body_with(ball(2.0), Collider::ball(1, 0.5), resisted);
+        let speed = |world: &TestWorld, id| world.body(id).linear_velocity().length();
+
+        let mut previous = speed(&world, resisted);
+        for _ in 0..60 {
+            world.step();
+            let current = speed(&world, resisted);
+            assert!(current <= previous + 1e-3, "sped up from {} to {}", previous, current);
+            previous = current;
+        }
+        assert!(speed(&world, free) > 2.5, "free ball at {}", speed(&world, free));
+        assert!(previous < 0.5 * speed(&world, free), "resisted ball at {}", previous);
+
+        world.step_n(540);
+        assert!(speed(&world, resisted) < 0.01, "still rolling at {}", speed(&world, resisted));
+        assert!(world.body(resisted).angular_velocity().length() < 0.02);
+    }
 }
@@ -2,42 +2,56 @@
 
 #![cfg(feature = "dim2")]
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroUsize;
 
-use spacetimedb::ReducerContext;
 use rapier2d::prelude::*;
 use nalgebra::{Vector2, Point2, UnitComplex};
 
 use crate::tables::{
-    PhysicsWorld, RigidBody, RigidBodyType, Collider, ColliderType,
-    RigidBodyProperties, Trigger, RayCast, RayCastHit,
+    PhysicsWorld, RigidBody, RigidBodyType, Collider,
+    RigidBodyProperties, Trigger, RayCast, RayCastHit, PortalTrigger, PortalEvent,
+};
+use crate::math::{float, Vec2, Vec3};
+use super::{
+    check_body, is_unchanged, ContactPair, ExternalForce, KinematicBody, PhysicsError, StepOutput, WorldRows,
+    GROUND_NORMAL_MIN_UP,
 };
-use crate::math::{Vec2, Vec3};
-use super::KinematicBody;
 
-/// Step the 2D physics world
+/// Step the 2D physics world in memory
 ///
 /// This function:
-/// 1. Loads all physics entities from SpacetimeDB tables
-/// 2. Builds a Rapier2D physics world
-/// 3. Applies kinematic body updates
-/// 4. Steps the simulation
-/// 5. Writes results back to SpacetimeDB tables
-/// 6. Updates trigger enter/exit events
-/// 7. Updates raycast hit lists
-pub fn step_world_2d(
-    ctx: &ReducerContext,
+/// 1. Builds a Rapier2D physics world from the loaded rows
+/// 2. Applies kinematic body updates and external forces
+/// 3. Steps the simulation
+/// 4. Collects the bodies to write back
+/// 5. Updates trigger enter/exit lists
+/// 6. Updates raycast hit lists
+///
+/// Nothing is written, see `StepOutput::write`.
+pub(crate) fn simulate_2d(
     world: &PhysicsWorld,
+    rows: &WorldRows,
     kinematic_entities: impl Iterator<Item = KinematicBody>,
-) {
+    forces: impl Iterator<Item = ExternalForce>,
+) -> StepOutput {
     // Collect kinematic updates into a map
     let kinematic_updates: HashMap<u64, (Vec2, f32)> = kinematic_entities
         .map(|(id, (pos, rot))| (id, (pos, rot)))
         .collect();
 
+    // Sum external forces per body, they only live for this step since the set is rebuilt
+    let mut external_forces: HashMap<u64, (Vec2, f32)> = HashMap::new();
+    for (id, force, torque) in forces {
+        let total = external_forces.entry(id).or_insert((Vec2::ZERO, 0.0));
+        total.0 += force;
+        total.1 += torque;
+    }
+
     // Initialize Rapier structures
     let gravity = world.gravity_vector();
+    let world_up = (-gravity).try_normalize(1.0e-6).unwrap_or_else(Vector2::y);
     let mut integration_parameters = IntegrationParameters::default();
     integration_parameters.dt = world.timestep();
     integration_parameters.num_solver_iterations = NonZeroUsize::new(world.num_solver_iterations as usize)
@@ -53,69 +67,86 @@ pub fn step_world_2d(
     let mut multibody_joint_set = MultibodyJointSet::new();
     let mut ccd_solver = CCDSolver::new();
     let mut query_pipeline = QueryPipeline::new();
-    let physics_hooks = ();
+    let physics_hooks = RestitutionThreshold {
+        threshold: world.restitution_threshold(),
+    };
     let event_handler = ();
 
-    let mut rigid_body_set = RigidBodySet::new();
-    let mut collider_set = ColliderSet::new();
+    // The rows are known up front so the sets and maps below are allocated once
+    // Keep every body on the Z = 0 plane, the rows are only copied if some are off it
+    let off_plane = |body: &RigidBody| body.position_z != 0.0 || body.linear_velocity_z != 0.0;
+    let bodies: Cow<[RigidBody]> = if world.zero_z_on_load && rows.bodies.iter().any(off_plane) {
+        Cow::Owned(
+            rows.bodies
+                .iter()
+                .map(|body| RigidBody { position_z: 0.0, linear_velocity_z: 0.0, ..*body })
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(&rows.bodies)
+    };
+    let colliders = &rows.colliders;
+
+    let mut rigid_body_set = RigidBodySet::with_capacity(bodies.len());
+    let mut collider_set = ColliderSet::with_capacity(bodies.len());
 
     // Maps from our IDs to Rapier handles
-    let mut id_to_rb_handle: HashMap<u64, RigidBodyHandle> = HashMap::new();
-    let mut rb_handle_to_id: HashMap<RigidBodyHandle, u64> = HashMap::new();
-    let mut id_to_collider: HashMap<u64, ColliderHandle> = HashMap::new();
-
-    // Load colliders first (we need them to create rigid bodies)
-    let colliders: Vec<_> = Collider::all_in_world(ctx, world.id).collect();
-    let properties: HashMap<u64, RigidBodyProperties> = RigidBodyProperties::all_in_world(ctx, world.id)
-        .map(|p| (p.id, p))
-        .collect();
+    let mut id_to_rb_handle: HashMap<u64, RigidBodyHandle> = HashMap::with_capacity(bodies.len());
+    let mut rb_handle_to_id: HashMap<RigidBodyHandle, u64> = HashMap::with_capacity(bodies.len());
+    let mut id_to_collider: HashMap<u64, ColliderHandle> = HashMap::with_capacity(bodies.len());
+
+    // Colliders and properties are needed to create rigid bodies
+    let mut properties: HashMap<u64, RigidBodyProperties> = HashMap::with_capacity(rows.properties.len());
+    properties.extend(rows.properties.iter().map(|p| (p.id, *p)));
 
-    // Build collider shapes (2D versions)
-    let collider_shapes: HashMap<u64, SharedShape> = colliders
+    // Build collider shapes, invalid ones are kept out and their bodies skipped
+    let shape_errors: HashMap<u64, PhysicsError> = colliders
         .iter()
-        .map(|c| {
-            let shape = match c.collider_type {
-                ColliderType::Ball => SharedShape::ball(c.radius),
-                ColliderType::Cuboid => SharedShape::cuboid(
-                    c.half_extent_x,
-                    c.half_extent_y,
-                ),
-                ColliderType::Capsule => SharedShape::capsule_y(c.half_height, c.radius),
-                // 3D-only shapes fallback to ball in 2D
-                ColliderType::Cylinder | ColliderType::Cone => SharedShape::ball(c.radius),
-                ColliderType::Triangle => SharedShape::triangle(
-                    Point2::new(c.vertex_a_x, c.vertex_a_y),
-                    Point2::new(c.vertex_b_x, c.vertex_b_y),
-                    Point2::new(c.vertex_c_x, c.vertex_c_y),
-                ),
-                ColliderType::Heightfield => SharedShape::ball(1.0), // Placeholder
-            };
-            (c.id, shape)
-        })
+        .filter_map(|c| c.validate().err().map(|err| (c.id, err)))
         .collect();
+    let mut collider_shapes: HashMap<u64, SharedShape> = HashMap::with_capacity(colliders.len());
+    collider_shapes.extend(
+        colliders
+            .iter()
+            .filter(|c| !shape_errors.contains_key(&c.id))
+            .map(|c| (c.id, c.shared_shape())),
+    );
+    let mut sensor_collider_ids: HashSet<u64> = HashSet::with_capacity(colliders.len());
+    sensor_collider_ids.extend(colliders.iter().filter(|c| c.is_sensor).map(|c| c.id));
+    let mut collision_types: HashMap<u64, ActiveCollisionTypes> = HashMap::with_capacity(colliders.len());
+    collision_types.extend(colliders.iter().map(|c| (c.id, c.collision_types())));
+    let mut sensor_bodies: Vec<(u64, ColliderHandle)> = Vec::new();
 
-    // Load and create rigid bodies
-    let bodies: Vec<_> = RigidBody::all_in_world(ctx, world.id).collect();
+    // Create rigid bodies
 
-    for body in &bodies {
+    let mut skipped_bodies = Vec::new();
+    let mut interpolated_bodies: HashSet<u64> = HashSet::new();
+    for body in bodies.iter() {
         if !body.enabled {
             continue;
         }
 
-        // Get position (use kinematic update if available)
-        let (pos_2d, rot_angle) = kinematic_updates
-            .get(&body.id)
-            .cloned()
+        // Leave broken bodies out of the step instead of aborting the reducer
+        if let Err(err) = check_body(body, &collider_shapes, &shape_errors, &properties) {
+            log::warn!("step_world: {}, skipping body", err);
+            skipped_bodies.push(err);
+            continue;
+        }
+
+        // Get position (use kinematic update if available, unless it is interpolated over the step)
+        let kinematic_update = kinematic_updates.get(&body.id).cloned();
+        let interpolated = world.kinematic_interpolation
+            && body.body_type == RigidBodyType::Kinematic
+            && kinematic_update.is_some();
+        let (pos_2d, rot_angle) = kinematic_update
+            .filter(|_| !interpolated)
             .unwrap_or_else(|| (body.position_2d(), body.rotation_angle()));
 
         // Create Rapier rigid body
-        let rb_type = match body.body_type {
-            RigidBodyType::Static => rapier2d::prelude::RigidBodyType::Fixed,
-            RigidBodyType::Dynamic => rapier2d::prelude::RigidBodyType::Dynamic,
-            RigidBodyType::Kinematic => rapier2d::prelude::RigidBodyType::KinematicPositionBased,
-        };
+        let rb_type = simulated_body_type(body, &properties);
 
         let mut rb_builder = RigidBodyBuilder::new(rb_type)
+            .user_data(body.user_data as u128)
             .translation(Vector2::new(pos_2d.x, pos_2d.y))
             .rotation(rot_angle)
             .linvel(Vector2::new(
@@ -124,15 +155,44 @@ pub fn step_world_2d(
             ))
             .angvel(body.angular_velocity_z); // 2D uses only Z angular velocity
 
-        // Apply properties if available
-        if let Some(props) = properties.get(&body.properties_id) {
-            rb_builder = rb_builder
-                .linear_damping(props.linear_damping)
-                .angular_damping(props.angular_damping)
-                .ccd_enabled(props.ccd_enabled);
+        if body.lock_rotation_z {
+            rb_builder = rb_builder.lock_rotations();
+        }
+
+        // Apply properties if available, the world damping applies on top of them
+        let props = properties.get(&body.properties_id);
+        rb_builder = rb_builder
+            .linear_damping(props.map_or(0.0, |p| p.linear_damping) + world.global_linear_damping)
+            .angular_damping(props.map_or(0.0, |p| p.angular_damping) + world.global_angular_damping);
+        if let Some(props) = props {
+            rb_builder = rb_builder.ccd_enabled(props.ccd_enabled);
         }
 
         let rb_handle = rigid_body_set.insert(rb_builder.build());
+        if let Some(&(force, torque)) = external_forces.get(&body.id) {
+            let rb = &mut rigid_body_set[rb_handle];
+            rb.add_force(Vector2::new(force.x, force.y), true);
+            rb.add_torque(torque, true);
+        }
+        if body.upright_stiffness > 0.0 && rb_type.is_dynamic() {
+            let rb = &mut rigid_body_set[rb_handle];
+            let body_up = rb.rotation() * Vector2::y();
+            rb.add_torque(body_up.perp(&world_up) * body.upright_stiffness, true);
+        }
+        if let Some((target_pos, target_rot)) = body.kinematic_target().filter(|_| rb_type.is_kinematic()) {
+            // Rapier derives the velocity from the move, so the body pushes what it sweeps through
+            rigid_body_set[rb_handle].set_next_kinematic_position(Isometry::new(
+                Vector2::new(target_pos.x, target_pos.y),
+                target_rot.to_angle_z(),
+            ));
+        }
+        if let Some((target_pos, target_angle)) = kinematic_update.filter(|_| interpolated && rb_type.is_kinematic()) {
+            rigid_body_set[rb_handle].set_next_kinematic_position(Isometry::new(
+                Vector2::new(target_pos.x, target_pos.y),
+                target_angle,
+            ));
+            interpolated_bodies.insert(body.id);
+        }
         id_to_rb_handle.insert(body.id, rb_handle);
         rb_handle_to_id.insert(rb_handle, body.id);
 
@@ -149,27 +209,58 @@ pub fn step_world_2d(
                     .density(props.density);
             }
 
+            // Sensor colliders move with their body and report overlaps instead of colliding
+            let is_sensor = sensor_collider_ids.contains(&body.collider_id);
+            if is_sensor {
+                collider_builder = collider_builder
+                    .sensor(true)
+                    .active_collision_types(ActiveCollisionTypes::all());
+            } else {
+                let types = collision_types
+                    .get(&body.collider_id)
+                    .copied()
+                    .unwrap_or_default();
+                let types = solid_collision_types(world, types, rb_type);
+                collider_builder = collider_builder.active_collision_types(types);
+            }
+
+            // Contacts are still computed (events, queries) but never solved
+            if !body.response_enabled {
+                collider_builder = collider_builder.solver_groups(InteractionGroups::none());
+            }
+
+            if physics_hooks.is_active() {
+                collider_builder = collider_builder.active_hooks(ActiveHooks::MODIFY_SOLVER_CONTACTS);
+            }
+
             let collider_handle = collider_set.insert_with_parent(
                 collider_builder.build(),
                 rb_handle,
                 &mut rigid_body_set,
             );
             id_to_collider.insert(body.id, collider_handle);
+            if is_sensor {
+                sensor_bodies.push((body.id, collider_handle));
+            }
         }
     }
 
     // Load and create triggers (sensors)
-    let triggers: Vec<_> = Trigger::all_in_world(ctx, world.id).collect();
-    let mut trigger_collider_handles: HashMap<ColliderHandle, u64> = HashMap::new();
+    let triggers: &[Trigger] = if world.process_triggers {
+        &rows.triggers
+    } else {
+        &[]
+    };
+    let mut trigger_collider_handles: HashMap<ColliderHandle, u64> = HashMap::with_capacity(triggers.len());
 
-    for trigger in &triggers {
+    for trigger in triggers {
         if !trigger.enabled {
             continue;
         }
 
         if let Some(shape) = collider_shapes.get(&trigger.collider_id) {
-            // Extract 2D rotation from quaternion
-            let angle = 2.0 * trigger.rotation_z.atan2(trigger.rotation_w);
+            // The native angle if set, otherwise extracted from the quaternion
+            let angle = trigger.rotation_angle();
 
             let collider = ColliderBuilder::new(shape.clone())
                 .position(Isometry::from_parts(
@@ -180,6 +271,7 @@ pub fn step_world_2d(
                     UnitComplex::new(angle),
                 ))
                 .sensor(true)
+                .active_collision_types(trigger.collision_types())
                 .build();
 
             let handle = collider_set.insert(collider);
@@ -204,14 +296,27 @@ pub fn step_world_2d(
         &event_handler,
     );
 
-    // Write results back to SpacetimeDB
-    for body in bodies {
+    // Bodies standing on something, for `grounded_linear_damping` and `rolling_resistance`
+    let up = (-world.gravity_vector()).try_normalize(1.0e-6);
+    let grounded = match up {
+        Some(up) if properties.values().any(|p| p.grounded_linear_damping > 0.0 || p.rolling_resistance > 0.0) => {
+            grounded_bodies(&narrow_phase, &collider_set, &rb_handle_to_id, &up)
+        }
+        _ => HashSet::new(),
+    };
+
+    // Collect the results to write back to SpacetimeDB
+    // Compared against the rows as loaded, so a Z snap counts as a change and is written back
+    let mut updated_bodies = Vec::new();
+    for (body, loaded) in bodies.iter().zip(&rows.bodies) {
         if !body.enabled {
             continue;
         }
 
-        // Skip kinematic bodies - their positions are set externally
-        if body.body_type == RigidBodyType::Kinematic {
+        // Skip kinematic bodies without a target (their positions are set externally)
+        // and frozen bodies (they keep their stored state until thawed)
+        let moved_by_step = body.has_kinematic_target || interpolated_bodies.contains(&body.id);
+        if (body.body_type == RigidBodyType::Kinematic && !moved_by_step) || body.frozen {
             continue;
         }
 
@@ -219,8 +324,16 @@ pub fn step_world_2d(
             if let Some(rb) = rigid_body_set.get(handle) {
                 let pos = rb.translation();
                 let rot = rb.rotation();
-                let linvel = rb.linvel();
-                let angvel = rb.angvel();
+                let mut linvel = *rb.linvel();
+                let mut angvel = rb.angvel();
+                if let Some(up) = up.filter(|_| grounded.contains(&body.id)) {
+                    let props = properties.get(&body.properties_id);
+                    let rolling = props.map_or(0.0, |p| p.rolling_resistance);
+                    let damping = props.map_or(0.0, |p| p.grounded_linear_damping) + rolling;
+                    linvel = damp_along_ground(linvel, &up, damping, world.timestep());
+                    // Rolling resistance slows the spin along with the rolling speed
+                    angvel /= 1.0 + world.timestep() * rolling;
+                }
 
                 let mut updated = body.clone();
                 updated.position_x = pos.x;
@@ -232,8 +345,8 @@ pub fn step_world_2d(
                 let half_angle = angle / 2.0;
                 updated.rotation_x = 0.0;
                 updated.rotation_y = 0.0;
-                updated.rotation_z = half_angle.sin();
-                updated.rotation_w = half_angle.cos();
+                updated.rotation_z = float::sin(half_angle);
+                updated.rotation_w = float::cos(half_angle);
 
                 updated.linear_velocity_x = linvel.x;
                 updated.linear_velocity_y = linvel.y;
@@ -243,13 +356,42 @@ pub fn step_world_2d(
                 updated.angular_velocity_x = 0.0;
                 updated.angular_velocity_y = 0.0;
                 updated.angular_velocity_z = angvel;
+                updated.has_kinematic_target = false;
 
-                updated.update(ctx);
+                if updated.sanitize(body) {
+                    log::warn!("step_world: body {} diverged to a non-finite state, reset it", body.id);
+                }
+                // Resting bodies barely move, skip their no-op row updates
+                if world.write_back_epsilon > 0.0 && is_unchanged(&updated, loaded, world.write_back_epsilon) {
+                    continue;
+                }
+                updated_bodies.push(updated);
             }
         }
     }
 
+    if world.kinematic_vs_kinematic {
+        resolve_kinematic_contacts(
+            &mut updated_bodies,
+            &bodies,
+            &narrow_phase,
+            &rigid_body_set,
+            &collider_set,
+            &id_to_rb_handle,
+            &id_to_collider,
+        );
+    }
+
     // Update trigger events
+    let bodies_by_id: HashMap<u64, &RigidBody> = bodies.iter().map(|b| (b.id, b)).collect();
+    let portals: HashMap<u64, &PortalTrigger> = if world.process_triggers {
+        rows.portals.iter().map(|p| (p.trigger_id, p)).collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut updated_triggers = Vec::with_capacity(triggers.len());
+    let mut portal_events = Vec::new();
     for trigger in triggers {
         if !trigger.enabled {
             continue;
@@ -265,7 +407,11 @@ pub fn step_world_2d(
         if let Some(trigger_handle) = trigger_collider {
             // Check for intersections with all rigid body colliders
             for (&body_id, &collider_handle) in &id_to_collider {
-                if narrow_phase.intersection_pair(trigger_handle, collider_handle).is_some() {
+                let detected = bodies_by_id
+                    .get(&body_id)
+                    .is_some_and(|body| trigger.detects(body));
+
+                if detected && narrow_phase.intersection_pair(trigger_handle, collider_handle).is_some() {
                     current_inside.push(body_id);
                 }
             }
@@ -273,11 +419,31 @@ pub fn step_world_2d(
 
         let mut updated = trigger.clone();
         updated.update_entities(current_inside);
-        updated.update(ctx);
+
+        if let Some(portal) = portals.get(&trigger.id) {
+            portal_events.extend(
+                updated
+                    .added_entities
+                    .iter()
+                    .map(|&body_id| PortalEvent::new(portal, body_id)),
+            );
+        }
+
+        updated_triggers.push(updated);
     }
 
+    // Update collision events
+    let body_materials: HashMap<u64, u32> = bodies
+        .iter()
+        .map(|b| (b.id, properties.get(&b.properties_id).map_or(0, |p| p.material_id)))
+        .collect();
+    let contact_pairs = touching_pairs(&narrow_phase, &id_to_collider);
+
+    // Update overlaps of sensors attached to bodies
+    let sensor_overlaps = sensor_overlaps(&narrow_phase, &id_to_collider, &sensor_bodies);
+
     // Update raycasts
-    update_raycasts_2d(ctx, world, &query_pipeline, &rigid_body_set, &collider_set, &rb_handle_to_id, &id_to_collider);
+    let raycasts = cast_rays_2d(&rows.raycasts, &query_pipeline, &rigid_body_set, &collider_set, &rb_handle_to_id, &id_to_collider);
 
     if world.debug {
         log::debug!(
@@ -288,26 +454,364 @@ pub fn step_world_2d(
             trigger_collider_handles.len()
         );
     }
+
+    StepOutput {
+        bodies: updated_bodies,
+        triggers: updated_triggers,
+        portal_events,
+        contact_pairs,
+        body_materials,
+        sensor_overlaps,
+        raycasts,
+        bodies_stepped: id_to_rb_handle.len(),
+        skipped_bodies,
+    }
 }
 
-/// Update all raycasts for this world (2D version)
-fn update_raycasts_2d(
-    ctx: &ReducerContext,
+/// Collect the current contacts of the world without simulating it
+///
+/// Every enabled body is placed at its stored pose and only the collision
+/// pipeline is run, so the same pairs as a step are checked.
+pub(crate) fn world_contacts_2d(
+    world: &PhysicsWorld,
+    bodies: &[RigidBody],
+    colliders: &[Collider],
+    properties: &[RigidBodyProperties],
+) -> Vec<ContactPair> {
+    let colliders: HashMap<u64, &Collider> = colliders.iter().map(|c| (c.id, c)).collect();
+    let properties: HashMap<u64, RigidBodyProperties> = properties.iter().map(|p| (p.id, *p)).collect();
+
+    let mut rigid_body_set = RigidBodySet::new();
+    let mut collider_set = ColliderSet::new();
+    let mut collider_to_body: HashMap<ColliderHandle, (u64, u64)> = HashMap::new();
+
+    for body in bodies {
+        let Some(collider) = colliders.get(&body.collider_id) else {
+            continue;
+        };
+        if !body.enabled || collider.is_sensor {
+            continue;
+        }
+
+        let pos = body.position();
+        let rb_type = simulated_body_type(body, &properties);
+        let rb_handle = rigid_body_set.insert(
+            RigidBodyBuilder::new(rb_type).position(Isometry::new(
+                Vector2::new(pos.x, pos.y),
+                body.rotation_angle(),
+            )),
+        );
+        // Same body types and collision types as the step, so only pairs it would touch are reported
+        let handle = collider_set.insert_with_parent(
+            ColliderBuilder::new(collider.shared_shape())
+                .active_collision_types(solid_collision_types(world, collider.collision_types(), rb_type))
+                .build(),
+            rb_handle,
+            &mut rigid_body_set,
+        );
+        collider_to_body.insert(handle, (body.id, body.user_data));
+    }
+
+    let mut broad_phase = DefaultBroadPhase::new();
+    let mut narrow_phase = NarrowPhase::new();
+    CollisionPipeline::new().step(
+        IntegrationParameters::default().prediction_distance(),
+        &mut broad_phase,
+        &mut narrow_phase,
+        &mut rigid_body_set,
+        &mut collider_set,
+        None,
+        &(),
+        &(),
+    );
+
+    narrow_phase
+        .contact_pairs()
+        .filter(|pair| pair.has_any_active_contact)
+        .filter_map(|pair| {
+            let (body_a_id, body_a_user_data) = *collider_to_body.get(&pair.collider1)?;
+            let (body_b_id, body_b_user_data) = *collider_to_body.get(&pair.collider2)?;
+            let (manifold, contact) = pair.find_deepest_contact()?;
+            let collider1 = collider_set.get(pair.collider1)?;
+
+            Some(ContactPair {
+                body_a_id,
+                body_b_id,
+                body_a_user_data,
+                body_b_user_data,
+                point: Vec2::from(collider1.position() * contact.local_p1),
+                normal: Vec2::from(collider1.position().rotation * manifold.local_n1),
+                distance: contact.dist,
+            })
+        })
+        .collect()
+}
+
+/// The Rapier type a body is simulated as
+///
+/// Frozen bodies and dynamic bodies with massless properties are fixed.
+fn simulated_body_type(
+    body: &RigidBody,
+    properties: &HashMap<u64, RigidBodyProperties>,
+) -> rapier2d::prelude::RigidBodyType {
+    match body.body_type {
+        _ if body.frozen => rapier2d::prelude::RigidBodyType::Fixed,
+        // Reported once by `RigidBodyProperties::insert`, not on every step
+        RigidBodyType::Dynamic if properties.get(&body.properties_id).is_some_and(|p| p.is_massless()) => {
+            rapier2d::prelude::RigidBodyType::Fixed
+        }
+        RigidBodyType::Static => rapier2d::prelude::RigidBodyType::Fixed,
+        RigidBodyType::Dynamic => rapier2d::prelude::RigidBodyType::Dynamic,
+        RigidBodyType::Kinematic => rapier2d::prelude::RigidBodyType::KinematicPositionBased,
+    }
+}
+
+/// The collision types of a solid collider, with the world's kinematic pairs
+fn solid_collision_types(
     world: &PhysicsWorld,
+    types: ActiveCollisionTypes,
+    rb_type: rapier2d::prelude::RigidBodyType,
+) -> ActiveCollisionTypes {
+    if world.kinematic_vs_kinematic && rb_type.is_kinematic() {
+        types | ActiveCollisionTypes::KINEMATIC_KINEMATIC
+    } else {
+        types
+    }
+}
+
+/// Get the bodies resting on another collider
+///
+/// A body is grounded when one of its contacts has a normal within about 45°
+/// of `up`, whatever it stands on (static floor, dynamic crate, ...).
+fn grounded_bodies(
+    narrow_phase: &NarrowPhase,
+    collider_set: &ColliderSet,
+    rb_handle_to_id: &HashMap<RigidBodyHandle, u64>,
+    up: &Vector<Real>,
+) -> HashSet<u64> {
+    let body_id = |handle: ColliderHandle| {
+        collider_set
+            .get(handle)
+            .and_then(|c| c.parent())
+            .and_then(|h| rb_handle_to_id.get(&h))
+            .copied()
+    };
+
+    let mut grounded = HashSet::new();
+    for pair in narrow_phase.contact_pairs() {
+        if !pair.has_any_active_contact {
+            continue;
+        }
+        let (Some((manifold, _)), Some(collider1)) = (pair.find_deepest_contact(), collider_set.get(pair.collider1)) else {
+            continue;
+        };
+
+        // The normal points from collider 1 towards collider 2
+        let alignment = (collider1.position().rotation * manifold.local_n1).dot(up);
+        if alignment <= -GROUND_NORMAL_MIN_UP {
+            grounded.extend(body_id(pair.collider1));
+        } else if alignment >= GROUND_NORMAL_MIN_UP {
+            grounded.extend(body_id(pair.collider2));
+        }
+    }
+    grounded
+}
+
+/// Damp the part of a velocity along the ground, the same way Rapier applies linear damping
+fn damp_along_ground(velocity: Vector<Real>, up: &Vector<Real>, damping: f32, dt: f32) -> Vector<Real> {
+    if damping <= 0.0 {
+        return velocity;
+    }
+    let vertical = up * velocity.dot(up);
+    vertical + (velocity - vertical) / (1.0 + dt * damping)
+}
+
+/// Push overlapping kinematic bodies apart, splitting the correction evenly
+///
+/// Only runs with `kinematic_vs_kinematic`, which lets the narrow phase report
+/// these pairs. The correction is added to the body's row in `updated_bodies`
+/// when the step already wrote one, otherwise a row at the resolved pose is added.
+fn resolve_kinematic_contacts(
+    updated_bodies: &mut Vec<RigidBody>,
+    bodies: &[RigidBody],
+    narrow_phase: &NarrowPhase,
+    rigid_body_set: &RigidBodySet,
+    collider_set: &ColliderSet,
+    id_to_rb_handle: &HashMap<u64, RigidBodyHandle>,
+    id_to_collider: &HashMap<u64, ColliderHandle>,
+) {
+    let collider_to_id: HashMap<ColliderHandle, u64> = id_to_collider
+        .iter()
+        .map(|(&id, &handle)| (handle, id))
+        .collect();
+    let is_kinematic = |handle: ColliderHandle| {
+        collider_set
+            .get(handle)
+            .and_then(|c| c.parent())
+            .and_then(|h| rigid_body_set.get(h))
+            .is_some_and(|rb| rb.is_kinematic())
+    };
+
+    let mut corrections: HashMap<u64, Vector<Real>> = HashMap::new();
+    for pair in narrow_phase.contact_pairs() {
+        if !pair.has_any_active_contact || !is_kinematic(pair.collider1) || !is_kinematic(pair.collider2) {
+            continue;
+        }
+
+        let (Some(&id1), Some(&id2)) = (collider_to_id.get(&pair.collider1), collider_to_id.get(&pair.collider2)) else {
+            continue;
+        };
+        let Some((manifold, contact)) = pair.find_deepest_contact() else {
+            continue;
+        };
+        let Some(collider1) = collider_set.get(pair.collider1) else {
+            continue;
+        };
+        if contact.dist >= 0.0 {
+            continue;
+        }
+
+        // The normal points from body 1 towards body 2
+        let normal = collider1.position().rotation * manifold.local_n1;
+        let half = normal * (-contact.dist * 0.5);
+        *corrections.entry(id1).or_insert_with(Vector::zeros) -= half;
+        *corrections.entry(id2).or_insert_with(Vector::zeros) += half;
+    }
+
+    let written: HashMap<u64, usize> = updated_bodies
+        .iter()
+        .enumerate()
+        .map(|(i, body)| (body.id, i))
+        .collect();
+    for body in bodies {
+        let Some(correction) = corrections.get(&body.id) else {
+            continue;
+        };
+        if let Some(&i) = written.get(&body.id) {
+            let row = &mut updated_bodies[i];
+            row.position_x += correction.x;
+            row.position_y += correction.y;
+            continue;
+        }
+
+        if let Some(rb) = id_to_rb_handle.get(&body.id).and_then(|&h| rigid_body_set.get(h)) {
+            let pos = rb.translation() + correction;
+            let half_angle = rb.rotation().angle() / 2.0;
+
+            let mut updated = body.clone();
+            updated.position_x = pos.x;
+            updated.position_y = pos.y;
+            updated.rotation_x = 0.0;
+            updated.rotation_y = 0.0;
+            updated.rotation_z = float::sin(half_angle);
+            updated.rotation_w = float::cos(half_angle);
+            updated.has_kinematic_target = false;
+            updated_bodies.push(updated);
+        }
+    }
+}
+
+/// Physics hooks that remove bounce from slow contacts
+///
+/// Rapier has no restitution velocity threshold of its own, so this zeroes the
+/// restitution of solver contacts whose approach speed is below the world's
+/// `restitution_threshold`.
+struct RestitutionThreshold {
+    threshold: f32,
+}
+
+impl RestitutionThreshold {
+    fn is_active(&self) -> bool {
+        self.threshold > 0.0
+    }
+}
+
+impl PhysicsHooks for RestitutionThreshold {
+    fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+        let bodies = context.bodies;
+        let velocity_at = |handle: Option<RigidBodyHandle>, point: &Point<Real>| {
+            handle
+                .and_then(|h| bodies.get(h))
+                .map(|rb| rb.velocity_at_point(point))
+                .unwrap_or_else(Vector::zeros)
+        };
+
+        let (rb1, rb2) = (context.rigid_body1, context.rigid_body2);
+        let normal = *context.normal;
+
+        for contact in context.solver_contacts.iter_mut() {
+            let relative = velocity_at(rb1, &contact.point) - velocity_at(rb2, &contact.point);
+            if relative.dot(&normal).abs() < self.threshold {
+                contact.restitution = 0.0;
+            }
+        }
+    }
+}
+
+/// Get the pairs of bodies touching this tick, as `(lower_id, higher_id)`
+fn touching_pairs(
+    narrow_phase: &NarrowPhase,
+    id_to_collider: &HashMap<u64, ColliderHandle>,
+) -> HashSet<(u64, u64)> {
+    let collider_to_body: HashMap<ColliderHandle, u64> = id_to_collider
+        .iter()
+        .map(|(&body_id, &collider_handle)| (collider_handle, body_id))
+        .collect();
+
+    narrow_phase
+        .contact_pairs()
+        .filter(|pair| pair.has_any_active_contact)
+        .filter_map(|pair| {
+            let a = *collider_to_body.get(&pair.collider1)?;
+            let b = *collider_to_body.get(&pair.collider2)?;
+            Some((a.min(b), a.max(b)))
+        })
+        .collect()
+}
+
+/// Get the bodies inside the sensor collider of each body carrying one
+fn sensor_overlaps(
+    narrow_phase: &NarrowPhase,
+    id_to_collider: &HashMap<u64, ColliderHandle>,
+    sensor_bodies: &[(u64, ColliderHandle)],
+) -> Vec<(u64, Vec<u64>)> {
+    let collider_to_body: HashMap<ColliderHandle, u64> = id_to_collider
+        .iter()
+        .map(|(&body_id, &collider_handle)| (collider_handle, body_id))
+        .collect();
+
+    sensor_bodies
+        .iter()
+        .map(|&(body_id, sensor_handle)| {
+            let current_inside: Vec<u64> = narrow_phase
+                .intersection_pairs_with(sensor_handle)
+                .filter(|&(_, _, intersecting)| intersecting)
+                .filter_map(|(h1, h2, _)| {
+                    let other = if h1 == sensor_handle { h2 } else { h1 };
+                    collider_to_body.get(&other).copied()
+                })
+                .collect();
+            (body_id, current_inside)
+        })
+        .collect()
+}
+
+/// Update all raycasts for this world (2D version)
+fn cast_rays_2d(
+    raycasts: &[RayCast],
     query_pipeline: &QueryPipeline,
     rigid_body_set: &RigidBodySet,
     collider_set: &ColliderSet,
     _rb_handle_to_id: &HashMap<RigidBodyHandle, u64>,
     id_to_collider: &HashMap<u64, ColliderHandle>,
-) {
+) -> Vec<RayCast> {
     // Build a map from collider handle to body id
     let collider_to_body: HashMap<ColliderHandle, u64> = id_to_collider
         .iter()
         .map(|(&body_id, &collider_handle)| (collider_handle, body_id))
         .collect();
 
-    let raycasts: Vec<_> = RayCast::all_in_world(ctx, world.id).collect();
-
+    let mut updated_raycasts = Vec::with_capacity(raycasts.len());
     for raycast in raycasts {
         if !raycast.enabled {
             continue;
@@ -324,7 +828,8 @@ fn update_raycasts_2d(
         };
 
         let ray = Ray::new(origin, direction);
-        let filter = QueryFilter::default();
+        // Sensor bodies only report overlaps, rays go through them
+        let filter = QueryFilter::default().exclude_sensors();
 
         let mut hits: Vec<RayCastHit> = Vec::new();
 
@@ -339,6 +844,12 @@ fn update_raycasts_2d(
             |handle, intersection| {
                 if let Some(&body_id) = collider_to_body.get(&handle) {
                     let point = ray.point_at(intersection.time_of_impact);
+                    // The body's user_data and velocities are mirrored on the Rapier rigid body
+                    let rb = collider_set[handle].parent().and_then(|rb| rigid_body_set.get(rb));
+                    let user_data = rb.map_or(0, |rb| rb.user_data as u64);
+                    let (linvel, angvel) = rb.map_or((Vec3::ZERO, Vec3::ZERO), |rb| {
+                        (Vec3::new(rb.linvel().x, rb.linvel().y, 0.0), Vec3::new(0.0, 0.0, rb.angvel()))
+                    });
                     hits.push(RayCastHit::new(
                         body_id,
                         intersection.time_of_impact,
@@ -348,7 +859,7 @@ fn update_raycasts_2d(
                             intersection.normal.y,
                             0.0, // Z=0 for 2D
                         ),
-                    ));
+                    ).with_user_data(user_data).with_body_velocity(linvel, angvel));
                 }
                 true // Continue searching for more hits
             },
@@ -359,6 +870,7 @@ fn update_raycasts_2d(
 
         let mut updated = raycast.clone();
         updated.update_hits(hits);
-        updated.update(ctx);
+        updated_raycasts.push(updated);
     }
+    updated_raycasts
 }
@@ -0,0 +1,199 @@
+//! Level building helpers
+//!
+//! Shortcuts for common static geometry on top of the table APIs.
+
+use spacetimedb::ReducerContext;
+
+use crate::tables::{Collider, ColliderId, PhysicsWorld, RigidBody, RigidBodyId, RigidBodyProperties, RigidBodyType};
+
+#[cfg(feature = "dim2")]
+use crate::math::Vec2;
+
+#[cfg(feature = "dim3")]
+use crate::math::Vec3;
+
+/// Insert six static walls enclosing a box, returns the wall body IDs
+///
+/// The inside of the room spans `center ± half_extents`, walls are
+/// `wall_thickness` thick and built outwards so they overlap at the corners.
+/// Opposite walls share a collider and every wall shares one properties row.
+#[cfg(feature = "dim3")]
+pub fn create_box_room(
+    ctx: &ReducerContext,
+    world: &PhysicsWorld,
+    center: Vec3,
+    half_extents: Vec3,
+    wall_thickness: f32,
+) -> Vec<RigidBodyId> {
+    let properties = RigidBodyProperties {
+        world_id: world.id,
+        ..Default::default()
+    }
+    .insert(ctx);
+
+    create_box_room_with(
+        world,
+        properties.id,
+        center,
+        half_extents,
+        wall_thickness,
+        |collider| collider.insert(ctx).id,
+        |body| body.insert(ctx).id,
+    )
+}
+
+/// Same as `create_box_room`, inserting the rows with `insert_collider` and `insert_body` (3D)
+///
+/// Every wall uses the existing `properties_id`.
+#[cfg(feature = "dim3")]
+pub fn create_box_room_with(
+    world: &PhysicsWorld,
+    properties_id: u64,
+    center: Vec3,
+    half_extents: Vec3,
+    wall_thickness: f32,
+    mut insert_collider: impl FnMut(Collider) -> ColliderId,
+    mut insert_body: impl FnMut(RigidBody) -> RigidBodyId,
+) -> Vec<RigidBodyId> {
+    let half_thickness = wall_thickness / 2.0;
+    let outer = half_extents + Vec3::new(wall_thickness, wall_thickness, wall_thickness);
+    let walls = [
+        (Vec3::X, Vec3::new(half_thickness, outer.y, outer.z), half_extents.x),
+        (Vec3::Y, Vec3::new(outer.x, half_thickness, outer.z), half_extents.y),
+        (Vec3::Z, Vec3::new(outer.x, outer.y, half_thickness), half_extents.z),
+    ];
+
+    let mut ids = Vec::with_capacity(6);
+    for (axis, wall_half_extents, half_extent) in walls {
+        let collider_id = insert_collider(Collider::cuboid(world.id, wall_half_extents));
+        for side in [-1.0, 1.0] {
+            let mut body = RigidBody {
+                world_id: world.id,
+                body_type: RigidBodyType::Static,
+                collider_id,
+                properties_id,
+                ..Default::default()
+            };
+            body.set_position(center + axis * (side * (half_extent + half_thickness)));
+            ids.push(insert_body(body));
+        }
+    }
+    ids
+}
+
+/// Insert four static walls enclosing a rectangle, returns the wall body IDs
+///
+/// The inside of the room spans `center ± half_extents`, walls are
+/// `wall_thickness` thick and built outwards so they overlap at the corners.
+/// Opposite walls share a collider and every wall shares one properties row.
+#[cfg(feature = "dim2")]
+pub fn create_box_room(
+    ctx: &ReducerContext,
+    world: &PhysicsWorld,
+    center: Vec2,
+    half_extents: Vec2,
+    wall_thickness: f32,
+) -> Vec<RigidBodyId> {
+    let properties = RigidBodyProperties {
+        world_id: world.id,
+        ..Default::default()
+    }
+    .insert(ctx);
+
+    create_box_room_with(
+        world,
+        properties.id,
+        center,
+        half_extents,
+        wall_thickness,
+        |collider| collider.insert(ctx).id,
+        |body| body.insert(ctx).id,
+    )
+}
+
+/// Same as `create_box_room`, inserting the rows with `insert_collider` and `insert_body` (2D)
+///
+/// Every wall uses the existing `properties_id`.
+#[cfg(feature = "dim2")]
+pub fn create_box_room_with(
+    world: &PhysicsWorld,
+    properties_id: u64,
+    center: Vec2,
+    half_extents: Vec2,
+    wall_thickness: f32,
+    mut insert_collider: impl FnMut(Collider) -> ColliderId,
+    mut insert_body: impl FnMut(RigidBody) -> RigidBodyId,
+) -> Vec<RigidBodyId> {
+    let half_thickness = wall_thickness / 2.0;
+    let outer = half_extents + Vec2::new(wall_thickness, wall_thickness);
+    let walls = [
+        (Vec2::X, Vec2::new(half_thickness, outer.y), half_extents.x),
+        (Vec2::Y, Vec2::new(outer.x, half_thickness), half_extents.y),
+    ];
+
+    let mut ids = Vec::with_capacity(4);
+    for (axis, wall_half_extents, half_extent) in walls {
+        let collider_id = insert_collider(Collider::cuboid_2d(world.id, wall_half_extents));
+        for side in [-1.0, 1.0] {
+            let mut body = RigidBody {
+                world_id: world.id,
+                body_type: RigidBodyType::Static,
+                collider_id,
+                properties_id,
+                ..Default::default()
+            };
+            body.set_position_2d(center + axis * (side * (half_extent + half_thickness)));
+            ids.push(insert_body(body));
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::engine::test_utils::TestWorld;
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn ball_thrown_inside_a_box_room_stays_inside() {
+        let world = RefCell::new(TestWorld::with_defaults());
+        let properties_id = world.borrow_mut().add_properties(RigidBodyProperties::default());
+        let physics_world = world.borrow().world;
+        let walls = create_box_room_with(
+            &physics_world,
+            properties_id,
+            Vec3::ZERO,
+            Vec3::new(2.0, 2.0, 2.0),
+            0.5,
+            |collider| world.borrow_mut().add_collider(collider),
+            |body| world.borrow_mut().insert_body(body),
+        );
+        let mut world = world.into_inner();
+        assert_eq!(walls.len(), 6);
+        // Opposite walls share a collider
+        assert_eq!(world.rows.colliders.len(), 3);
+
+        let ball = world.add_body(
+            RigidBody {
+                position_y: 1.0,
+                linear_velocity_x: 8.0,
+                linear_velocity_y: 3.0,
+                linear_velocity_z: -6.0,
+                ..Default::default()
+            },
+            Collider::ball(1, 0.25),
+        );
+        for _ in 0..300 {
+            world.step();
+            let position = world.body(ball).position();
+            assert!(
+                position.x.abs() < 2.0 && position.y.abs() < 2.0 && position.z.abs() < 2.0,
+                "ball escaped to {:?}",
+                position
+            );
+        }
+    }
+}
@@ -6,22 +6,27 @@
 //!
 //! - `dim2` - Enable 2D physics with Rapier2D
 //! - `dim3` - Enable 3D physics with Rapier3D (default)
+//! - `strict_float` - Same float results on every platform, see [`math::float`]
 //!
 //! ## Usage
 //!
-//! ```rust
-//! use spacetime_rapier::{PhysicsWorld, RigidBody, Collider, step_world};
+//! ```rust,no_run
+//! use spacetime_rapier::{PhysicsWorld, step_world};
+//! use spacetimedb::ReducerContext;
 //!
-//! // Create a physics world
-//! let world = PhysicsWorld::builder()
-//!     .ticks_per_second(60.0)
-//!     .gravity_y(-9.81)
-//!     .build()
-//!     .insert(ctx);
+//! fn f(ctx: &ReducerContext) -> Result<(), String> {
+//!     // Create a physics world (gravity is set per axis: `gravity_x/y/z`)
+//!     let world = PhysicsWorld::builder()
+//!         .ticks_per_second(60.0)
+//!         .gravity_y(-9.81)
+//!         .build()
+//!         .insert(ctx);
 //!
-//! // In your scheduled reducer
-//! let world = PhysicsWorld::find(ctx, world_id).unwrap();
-//! step_world(ctx, &world, kinematic_updates);
+//!     // In your scheduled reducer, a broken world returns an error instead of panicking
+//!     let world = PhysicsWorld::find(ctx, world.id).ok_or("world not found")?;
+//!     step_world(ctx, &world, std::iter::empty()).map_err(|e| e.to_string())?;
+//!     Ok(())
+//! }
 //! ```
 
 // Ensure at least one dimension feature is enabled
@@ -40,14 +45,29 @@ pub mod tables;
 // Physics engine wrapper
 pub mod engine;
 
-// Query utilities (raycasts, shapecasts)
+// Query utilities (raycasts, shapecasts, spatial index)
 pub mod queries;
 
+// Fixed-rate tick scheduling
+pub mod schedule;
+
+// Level building helpers (box rooms)
+pub mod level;
+
+// Spawning helpers (projectiles)
+pub mod spawn;
+
 // Re-export commonly used types
 pub use math::*;
 pub use tables::*;
-pub use engine::step_world;
+pub use engine::{
+    step_world, step_world_with_forces, step_worlds, world_contacts, ContactPair, ExternalForce,
+    PhysicsError, StepReport,
+};
 pub use queries::*;
+pub use schedule::*;
+pub use level::*;
+pub use spawn::*;
 
 // Re-export Rapier types that users might need
 #[cfg(feature = "dim2")]
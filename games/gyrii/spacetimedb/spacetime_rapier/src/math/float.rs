@@ -0,0 +1,123 @@
+//! Transcendental functions used by the math types
+//!
+//! `+`, `-`, `*`, `/` and `sqrt` are correctly rounded by IEEE 754 and give
+//! the same result on every platform. `sin`, `cos`, `acos`... come from the
+//! platform's libm and may differ in the last bit between replicas, which
+//! is enough for two servers to drift apart.
+//!
+//! With the `strict_float` feature they go through the pure Rust `libm`
+//! crate instead, and Rapier is built with `enhanced-determinism`.
+//!
+//! This only covers `spacetime_rapier`. The XPBD `spacetime_physics` crate
+//! still calls the platform's functions, its replicas can drift apart.
+
+#[cfg(not(feature = "strict_float"))]
+mod backend {
+    #[inline]
+    pub fn sin(x: f32) -> f32 {
+        x.sin()
+    }
+
+    #[inline]
+    pub fn cos(x: f32) -> f32 {
+        x.cos()
+    }
+
+    #[inline]
+    pub fn sin_cos(x: f32) -> (f32, f32) {
+        x.sin_cos()
+    }
+
+    #[inline]
+    pub fn asin(x: f32) -> f32 {
+        x.asin()
+    }
+
+    #[inline]
+    pub fn acos(x: f32) -> f32 {
+        x.acos()
+    }
+
+    #[inline]
+    pub fn atan2(y: f32, x: f32) -> f32 {
+        y.atan2(x)
+    }
+}
+
+#[cfg(feature = "strict_float")]
+mod backend {
+    #[inline]
+    pub fn sin(x: f32) -> f32 {
+        libm::sinf(x)
+    }
+
+    #[inline]
+    pub fn cos(x: f32) -> f32 {
+        libm::cosf(x)
+    }
+
+    #[inline]
+    pub fn sin_cos(x: f32) -> (f32, f32) {
+        libm::sincosf(x)
+    }
+
+    #[inline]
+    pub fn asin(x: f32) -> f32 {
+        libm::asinf(x)
+    }
+
+    #[inline]
+    pub fn acos(x: f32) -> f32 {
+        libm::acosf(x)
+    }
+
+    #[inline]
+    pub fn atan2(y: f32, x: f32) -> f32 {
+        libm::atan2f(y, x)
+    }
+}
+
+pub use backend::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Quat, Vec3};
+
+    // Expected bits agree with the correctly rounded results, the platform's libm and
+    // the `libm` crate. A mismatch means this build drifted from other replicas.
+    const SIN: [(f32, u32); 3] = [(0.5, 0x3ef57744), (-2.5, 0xbf193578), (100.0, 0xbf01a12e)];
+    const COS: [(f32, u32); 3] = [(0.5, 0x3f60a940), (2.0, 0xbed51133), (10.0, 0xbf56cd64)];
+    const ATAN2: [(f32, f32, u32); 3] = [(1.0, 2.0, 0x3eed6338), (-2.0, -5.0, 0xc030b5a3), (0.5, -1.0, 0x402b6374)];
+    const ACOS: [(f32, u32); 3] = [(0.3, 0x3fa20faf), (-0.6, 0x400db70d), (0.99, 0x3e10efb5)];
+
+    #[test]
+    fn transcendental_functions_match_the_reference_bits() {
+        for (x, bits) in SIN {
+            assert_eq!(sin(x).to_bits(), bits, "sin({})", x);
+            assert_eq!(sin_cos(x).0.to_bits(), bits, "sin_cos({})", x);
+        }
+        for (x, bits) in COS {
+            assert_eq!(cos(x).to_bits(), bits, "cos({})", x);
+            assert_eq!(sin_cos(x).1.to_bits(), bits, "sin_cos({})", x);
+        }
+        for (y, x, bits) in ATAN2 {
+            assert_eq!(atan2(y, x).to_bits(), bits, "atan2({}, {})", y, x);
+        }
+        for (x, bits) in ACOS {
+            assert_eq!(acos(x).to_bits(), bits, "acos({})", x);
+        }
+    }
+
+    #[test]
+    fn quaternion_math_matches_the_reference_bits() {
+        let bits = |q: Quat| [q.x.to_bits(), q.y.to_bits(), q.z.to_bits(), q.w.to_bits()];
+
+        let q = Quat::from_axis_angle(Vec3::new(1.0, 2.0, 3.0), 1.2);
+        assert_eq!(bits(q), [0x3e1a8761, 0x3e9a8761, 0x3ee7cb10, 0x3f534932]);
+
+        let a = Quat::from_axis_angle(Vec3::Y, 0.3);
+        let b = Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 2.0);
+        assert_eq!(bits(a.slerp(b, 0.3)), [0x3e4f59f5, 0x3ea133c4, 0x00000000, 0x3f6d62e9]);
+    }
+}
@@ -1,10 +1,12 @@
 //! Math types for physics calculations
 //!
 //! Provides Vec2, Vec3, and Quat types with conversions to/from Rapier types.
+//! Trigonometry goes through `float`, see the `strict_float` feature.
 
 mod vec2;
 mod vec3;
 mod quat;
+pub mod float;
 
 pub use vec2::Vec2;
 pub use vec3::Vec3;
@@ -2,7 +2,7 @@
 
 use spacetimedb::SpacetimeType;
 use std::ops::{Mul, MulAssign, Neg};
-use super::Vec3;
+use super::{float, Vec3};
 
 /// A quaternion representing a 3D rotation
 #[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
@@ -33,9 +33,9 @@ impl Quat {
     #[inline]
     pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
         let half_angle = angle * 0.5;
-        let s = half_angle.sin();
+        let s = float::sin(half_angle);
         let axis = axis.normalize();
-        Self::new(axis.x * s, axis.y * s, axis.z * s, half_angle.cos())
+        Self::new(axis.x * s, axis.y * s, axis.z * s, float::cos(half_angle))
     }
 
     /// Create from rotation around X axis (in radians)
@@ -62,6 +62,56 @@ impl Quat {
         Self::from_rotation_x(x) * Self::from_rotation_y(y) * Self::from_rotation_z(z)
     }
 
+    /// Create a rotation whose forward (-Z) points along `direction`, keeping `up` as close to +Y as possible
+    ///
+    /// Returns identity for a zero direction. If `up` is parallel to the
+    /// direction another axis is used as up.
+    pub fn look_to(direction: Vec3, up: Vec3) -> Self {
+        let back = -direction.normalize_or_zero();
+        if back == Vec3::ZERO {
+            return Self::IDENTITY;
+        }
+
+        let mut right = up.cross(back).normalize_or_zero();
+        if right == Vec3::ZERO {
+            let fallback = if back.x.abs() < 0.9 { Vec3::X } else { Vec3::Z };
+            right = fallback.cross(back).normalize();
+        }
+        let up = back.cross(right);
+
+        Self::from_axes(right, up, back)
+    }
+
+    /// Create a rotation that makes something at `eye` face `target`
+    #[inline]
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        Self::look_to(target - eye, up)
+    }
+
+    /// Create from an orthonormal basis (the rotated X, Y and Z axes)
+    fn from_axes(x_axis: Vec3, y_axis: Vec3, z_axis: Vec3) -> Self {
+        let (m00, m10, m20) = (x_axis.x, x_axis.y, x_axis.z);
+        let (m01, m11, m21) = (y_axis.x, y_axis.y, y_axis.z);
+        let (m02, m12, m22) = (z_axis.x, z_axis.y, z_axis.z);
+
+        let trace = m00 + m11 + m22;
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new((m21 - m12) / s, (m02 - m20) / s, (m10 - m01) / s, 0.25 * s)
+        } else if m00 > m11 && m00 > m22 {
+            let s = (1.0 + m00 - m11 - m22).sqrt() * 2.0;
+            Self::new(0.25 * s, (m01 + m10) / s, (m02 + m20) / s, (m21 - m12) / s)
+        } else if m11 > m22 {
+            let s = (1.0 + m11 - m00 - m22).sqrt() * 2.0;
+            Self::new((m01 + m10) / s, 0.25 * s, (m12 + m21) / s, (m02 - m20) / s)
+        } else {
+            let s = (1.0 + m22 - m00 - m11).sqrt() * 2.0;
+            Self::new((m02 + m20) / s, (m12 + m21) / s, 0.25 * s, (m10 - m01) / s)
+        };
+
+        q.normalize()
+    }
+
     /// Squared length
     #[inline]
     pub fn length_squared(self) -> f32 {
@@ -85,6 +135,26 @@ impl Quat {
         }
     }
 
+    /// True if no component is NaN or infinite
+    #[inline]
+    pub fn is_finite(self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite() && self.w.is_finite()
+    }
+
+    /// True if any component is NaN
+    #[inline]
+    pub fn is_nan(self) -> bool {
+        self.x.is_nan() || self.y.is_nan() || self.z.is_nan() || self.w.is_nan()
+    }
+
+    /// Return `fallback` if any component is NaN or infinite
+    ///
+    /// Unlike vectors, a partly valid rotation means nothing, so the whole quaternion is replaced.
+    #[inline]
+    pub fn sanitize(self, fallback: Self) -> Self {
+        if self.is_finite() { self } else { fallback }
+    }
+
     /// Conjugate (inverse for unit quaternions)
     #[inline]
     pub fn conjugate(self) -> Self {
@@ -133,10 +203,10 @@ impl Quat {
             ).normalize();
         }
 
-        let theta = dot.acos();
-        let sin_theta = theta.sin();
-        let wa = ((1.0 - t) * theta).sin() / sin_theta;
-        let wb = (t * theta).sin() / sin_theta;
+        let theta = float::acos(dot);
+        let sin_theta = float::sin(theta);
+        let wa = float::sin((1.0 - t) * theta) / sin_theta;
+        let wb = float::sin(t * theta) / sin_theta;
 
         Self::new(
             self.x * wa + other.x * wb,
@@ -146,6 +216,24 @@ impl Quat {
         )
     }
 
+    /// Angle between two rotations (in radians, in `[0, PI]`)
+    #[inline]
+    pub fn angle_between(self, other: Self) -> f32 {
+        2.0 * float::acos(self.dot(other).abs().min(1.0))
+    }
+
+    /// Rotate towards `target` by at most `max_radians`
+    ///
+    /// Returns `target` when it is already within `max_radians`.
+    #[inline]
+    pub fn rotate_towards(self, target: Self, max_radians: f32) -> Self {
+        let angle = self.angle_between(target);
+        if angle <= max_radians.max(0.0) || angle <= 1e-6 {
+            return target;
+        }
+        self.slerp(target, max_radians.max(0.0) / angle)
+    }
+
     /// Get the axis of rotation (assumes normalized)
     #[inline]
     pub fn axis(self) -> Vec3 {
@@ -160,25 +248,25 @@ impl Quat {
     /// Get the angle of rotation (in radians)
     #[inline]
     pub fn angle(self) -> f32 {
-        2.0 * self.w.acos()
+        2.0 * float::acos(self.w)
     }
 
     /// Convert to Euler angles (XYZ order, in radians)
     pub fn to_euler(self) -> (f32, f32, f32) {
         let sinr_cosp = 2.0 * (self.w * self.x + self.y * self.z);
         let cosr_cosp = 1.0 - 2.0 * (self.x * self.x + self.y * self.y);
-        let x = sinr_cosp.atan2(cosr_cosp);
+        let x = float::atan2(sinr_cosp, cosr_cosp);
 
         let sinp = 2.0 * (self.w * self.y - self.z * self.x);
         let y = if sinp.abs() >= 1.0 {
             std::f32::consts::FRAC_PI_2.copysign(sinp)
         } else {
-            sinp.asin()
+            float::asin(sinp)
         };
 
         let siny_cosp = 2.0 * (self.w * self.z + self.x * self.y);
         let cosy_cosp = 1.0 - 2.0 * (self.y * self.y + self.z * self.z);
-        let z = siny_cosp.atan2(cosy_cosp);
+        let z = float::atan2(siny_cosp, cosy_cosp);
 
         (x, y, z)
     }
@@ -186,14 +274,14 @@ impl Quat {
     /// Get the Z rotation angle (for 2D games)
     #[inline]
     pub fn to_angle_z(self) -> f32 {
-        2.0 * self.z.atan2(self.w)
+        2.0 * float::atan2(self.z, self.w)
     }
 
     /// Create from Z rotation angle (for 2D games)
     #[inline]
     pub fn from_angle_z(angle: f32) -> Self {
         let half = angle * 0.5;
-        Self::new(0.0, 0.0, half.sin(), half.cos())
+        Self::new(0.0, 0.0, float::sin(half), float::cos(half))
     }
 
     /// Extract forward direction (negative Z)
@@ -284,3 +372,59 @@ impl Neg for Quat {
         Self::new(-self.x, -self.y, -self.z, -self.w)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate_towards_converges_without_overshooting() {
+        let target = Quat::from_axis_angle(Vec3::Y, 2.0);
+        let step = 0.1;
+
+        let mut current = Quat::IDENTITY;
+        let mut remaining = current.angle_between(target);
+        let mut steps = 0;
+        while current != target {
+            let next = current.rotate_towards(target, step);
+            assert!(current.angle_between(next) <= step + 1e-3);
+            let next_remaining = next.angle_between(target);
+            assert!(next_remaining <= remaining, "moved away from the target");
+
+            current = next;
+            remaining = next_remaining;
+            steps += 1;
+            assert!(steps <= 25, "did not reach the target");
+        }
+    }
+
+    #[test]
+    fn rotate_towards_returns_target_within_max_angle() {
+        let target = Quat::from_axis_angle(Vec3::Y, 0.05);
+        assert_eq!(Quat::IDENTITY.rotate_towards(target, 0.1), target);
+    }
+
+    fn assert_vec_close(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).length() < 1e-5, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn look_at_points_forward_at_the_target() {
+        let eye = Vec3::new(1.0, 2.0, 3.0);
+        let targets = [Vec3::new(4.0, 2.0, 3.0), Vec3::new(1.0, 2.0, 10.0), Vec3::new(-2.0, 5.0, 0.0)];
+        for target in targets {
+            let rotation = Quat::look_at(eye, target, Vec3::Y);
+            let forward = rotation.rotate_vec3(Vec3::new(0.0, 0.0, -1.0));
+            assert_vec_close(forward, (target - eye).normalize());
+            // Right stays horizontal, the view doesn't roll
+            assert!(rotation.rotate_vec3(Vec3::X).y.abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn look_at_handles_up_parallel_to_the_direction() {
+        let rotation = Quat::look_to(Vec3::Y, Vec3::Y);
+        assert_vec_close(rotation.rotate_vec3(Vec3::new(0.0, 0.0, -1.0)), Vec3::Y);
+        assert_eq!(Quat::look_to(Vec3::ZERO, Vec3::Y), Quat::IDENTITY);
+    }
+}
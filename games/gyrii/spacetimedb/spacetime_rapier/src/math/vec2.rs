@@ -2,6 +2,7 @@
 
 use spacetimedb::SpacetimeType;
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use super::float;
 
 /// A 2D vector
 #[derive(SpacetimeType, Clone, Copy, Debug, Default, PartialEq)]
@@ -99,7 +100,7 @@ impl Vec2 {
     /// Rotate by an angle (in radians)
     #[inline]
     pub fn rotate(self, angle: f32) -> Self {
-        let (sin, cos) = angle.sin_cos();
+        let (sin, cos) = float::sin_cos(angle);
         Self::new(
             self.x * cos - self.y * sin,
             self.x * sin + self.y * cos,
@@ -115,7 +116,7 @@ impl Vec2 {
     /// Angle from positive X axis (in radians)
     #[inline]
     pub fn angle(self) -> f32 {
-        self.y.atan2(self.x)
+        float::atan2(self.y, self.x)
     }
 
     /// Component-wise min
@@ -142,6 +143,48 @@ impl Vec2 {
         Self::new(self.x.abs(), self.y.abs())
     }
 
+    /// True if no component is NaN or infinite
+    #[inline]
+    pub fn is_finite(self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+
+    /// True if any component is NaN
+    #[inline]
+    pub fn is_nan(self) -> bool {
+        self.x.is_nan() || self.y.is_nan()
+    }
+
+    /// Replace NaN or infinite components with the matching component of `fallback`
+    #[inline]
+    pub fn sanitize(self, fallback: Self) -> Self {
+        let pick = |v: f32, f: f32| if v.is_finite() { v } else { f };
+        Self::new(pick(self.x, fallback.x), pick(self.y, fallback.y))
+    }
+
+    /// Move towards a target by at most `max_delta`, landing exactly on it when close enough
+    #[inline]
+    pub fn move_towards(self, target: Self, max_delta: f32) -> Self {
+        let delta = target - self;
+        let dist = delta.length();
+        if dist <= max_delta || dist < 1e-6 {
+            target
+        } else {
+            self + delta * (max_delta / dist)
+        }
+    }
+
+    /// Clamp the length to `max`, shorter vectors are left unchanged
+    #[inline]
+    pub fn clamp_length(self, max: f32) -> Self {
+        let len_sq = self.length_squared();
+        if len_sq > max * max && len_sq > 1e-12 {
+            self * (max / len_sq.sqrt())
+        } else {
+            self
+        }
+    }
+
     /// Extend to Vec3 with z=0
     pub fn extend(self, z: f32) -> super::Vec3 {
         super::Vec3::new(self.x, self.y, z)
@@ -257,3 +300,31 @@ impl Neg for Vec2 {
         Self::new(-self.x, -self.y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Vec2;
+
+    #[test]
+    fn clamp_length_shortens_only_long_vectors() {
+        assert_eq!(Vec2::new(3.0, 4.0).clamp_length(2.5), Vec2::new(1.5, 2.0));
+        assert_eq!(Vec2::new(1.0, 1.0).clamp_length(2.0), Vec2::new(1.0, 1.0));
+        assert_eq!(Vec2::ZERO.clamp_length(0.0), Vec2::ZERO);
+    }
+
+    #[test]
+    fn move_towards_lands_exactly_on_target() {
+        let target = Vec2::new(3.0, 4.0);
+
+        let first = Vec2::ZERO.move_towards(target, 2.0);
+        assert!((first.length() - 2.0).abs() < 1e-6);
+
+        // The last step is shorter than max_delta and snaps onto the target
+        let mut position = first;
+        for _ in 0..2 {
+            position = position.move_towards(target, 2.0);
+        }
+        assert_eq!(position, target);
+        assert_eq!(target.move_towards(target, 2.0), target);
+    }
+}
@@ -135,6 +135,25 @@ impl Vec3 {
         Self::new(self.x.abs(), self.y.abs(), self.z.abs())
     }
 
+    /// True if no component is NaN or infinite
+    #[inline]
+    pub fn is_finite(self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+
+    /// True if any component is NaN
+    #[inline]
+    pub fn is_nan(self) -> bool {
+        self.x.is_nan() || self.y.is_nan() || self.z.is_nan()
+    }
+
+    /// Replace NaN or infinite components with the matching component of `fallback`
+    #[inline]
+    pub fn sanitize(self, fallback: Self) -> Self {
+        let pick = |v: f32, f: f32| if v.is_finite() { v } else { f };
+        Self::new(pick(self.x, fallback.x), pick(self.y, fallback.y), pick(self.z, fallback.z))
+    }
+
     /// Get XY components as Vec2
     #[inline]
     pub fn xy(self) -> super::Vec2 {
@@ -147,6 +166,29 @@ impl Vec3 {
         super::Vec2::new(self.x, self.z)
     }
 
+    /// Move towards a target by at most `max_delta`, landing exactly on it when close enough
+    #[inline]
+    pub fn move_towards(self, target: Self, max_delta: f32) -> Self {
+        let delta = target - self;
+        let dist = delta.length();
+        if dist <= max_delta || dist < 1e-6 {
+            target
+        } else {
+            self + delta * (max_delta / dist)
+        }
+    }
+
+    /// Clamp the length to `max`, shorter vectors are left unchanged
+    #[inline]
+    pub fn clamp_length(self, max: f32) -> Self {
+        let len_sq = self.length_squared();
+        if len_sq > max * max && len_sq > 1e-12 {
+            self * (max / len_sq.sqrt())
+        } else {
+            self
+        }
+    }
+
     /// Reflect this vector around a normal
     #[inline]
     pub fn reflect(self, normal: Self) -> Self {
@@ -279,3 +321,31 @@ impl Neg for Vec3 {
         Self::new(-self.x, -self.y, -self.z)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Vec3;
+
+    #[test]
+    fn clamp_length_shortens_only_long_vectors() {
+        assert_eq!(Vec3::new(3.0, 4.0, 0.0).clamp_length(2.5), Vec3::new(1.5, 2.0, 0.0));
+        assert_eq!(Vec3::new(0.0, 1.0, 1.0).clamp_length(2.0), Vec3::new(0.0, 1.0, 1.0));
+        assert_eq!(Vec3::ZERO.clamp_length(0.0), Vec3::ZERO);
+    }
+
+    #[test]
+    fn move_towards_lands_exactly_on_target() {
+        let target = Vec3::new(3.0, 4.0, 0.0);
+
+        let first = Vec3::ZERO.move_towards(target, 2.0);
+        assert!((first.length() - 2.0).abs() < 1e-6);
+
+        // The last step is shorter than max_delta and snaps onto the target
+        let mut position = first;
+        for _ in 0..2 {
+            position = position.move_towards(target, 2.0);
+        }
+        assert_eq!(position, target);
+        assert_eq!(target.move_towards(target, 2.0), target);
+    }
+}
@@ -1,15 +1,43 @@
 //! Query utilities for physics world
 //!
-//! Provides raycast and shapecast functionality.
+//! Provides raycast and shapecast functionality, trigger lookups, region
+//! queries, plus a spatial index for gameplay queries that don't need the
+//! physics pipeline.
+
+mod spatial_index;
+
+pub use spatial_index::SpatialIndex;
+
+use std::collections::HashMap;
 
 use spacetimedb::ReducerContext;
-use crate::tables::{PhysicsWorld, RayCastHit};
+use crate::engine::PhysicsError;
+use crate::tables::{Collider, PhysicsWorld, RayCastHit, RigidBody, Trigger};
 
 #[cfg(feature = "dim2")]
 use crate::math::Vec2;
 
 #[cfg(feature = "dim3")]
-use crate::math::Vec3;
+use crate::math::{Quat, Vec3};
+
+#[cfg(feature = "dim2")]
+use rapier2d::parry::query::{cast_shapes, ShapeCastOptions};
+#[cfg(feature = "dim2")]
+use rapier2d::prelude::{Aabb, BoundingVolume, Isometry, Vector};
+
+#[cfg(feature = "dim3")]
+use rapier3d::parry::query::{cast_shapes, ShapeCastOptions};
+#[cfg(feature = "dim3")]
+use rapier3d::prelude::{Aabb, BoundingVolume, Isometry, Vector};
+
+/// First body touched by a `shape_cast`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapeCastHit {
+    /// ID of the body that was hit
+    pub body_id: u64,
+    /// Distance the shape travelled before touching it
+    pub distance: f32,
+}
 
 /// Perform an instant raycast (not persistent) - 3D version
 ///
@@ -42,3 +70,323 @@ pub fn raycast_instant(
     // TODO: Implement instant raycast
     Vec::new()
 }
+
+/// Get the IDs of the enabled triggers a body is currently inside
+///
+/// Based on the trigger state written by the last `step_world`. IDs are
+/// sorted so the result doesn't depend on table iteration order.
+pub fn triggers_containing(ctx: &ReducerContext, world: &PhysicsWorld, body_id: u64) -> Vec<u64> {
+    triggers_containing_in(Trigger::all_in_world(ctx, world.id), body_id)
+}
+
+/// Same as `triggers_containing`, over already loaded triggers
+pub fn triggers_containing_in(triggers: impl IntoIterator<Item = Trigger>, body_id: u64) -> Vec<u64> {
+    let mut trigger_ids: Vec<u64> = triggers
+        .into_iter()
+        .filter(|trigger| trigger.enabled && trigger.contains(body_id))
+        .map(|trigger| trigger.id)
+        .collect();
+    trigger_ids.sort_unstable();
+    trigger_ids
+}
+
+/// Get the IDs of the enabled bodies whose collider AABB overlaps a region (3D)
+///
+/// Meant for interest management: a reducer can call this with the area
+/// around a player and publish only the returned bodies to that client.
+/// IDs are sorted so the result doesn't depend on table iteration order.
+#[cfg(feature = "dim3")]
+pub fn bodies_in_region(ctx: &ReducerContext, world: &PhysicsWorld, min: Vec3, max: Vec3) -> Vec<u64> {
+    bodies_in_region_in(RigidBody::all_in_world(ctx, world.id), Collider::all_in_world(ctx, world.id), min, max)
+}
+
+/// Same as `bodies_in_region`, over already loaded bodies and colliders (3D)
+#[cfg(feature = "dim3")]
+pub fn bodies_in_region_in(
+    bodies: impl IntoIterator<Item = RigidBody>,
+    colliders: impl IntoIterator<Item = Collider>,
+    min: Vec3,
+    max: Vec3,
+) -> Vec<u64> {
+    let region = Aabb::new(
+        nalgebra::Point3::new(min.x, min.y, min.z),
+        nalgebra::Point3::new(max.x, max.y, max.z),
+    );
+    bodies_overlapping(bodies, colliders, &region, |body| {
+        let pos = body.position();
+        Isometry::from_parts(
+            nalgebra::Translation3::new(pos.x, pos.y, pos.z),
+            nalgebra::UnitQuaternion::from(body.rotation()),
+        )
+    })
+}
+
+/// Get the IDs of the enabled bodies whose collider AABB overlaps a region (2D)
+///
+/// Meant for interest management: a reducer can call this with the area
+/// around a player and publish only the returned bodies to that client.
+/// IDs are sorted so the result doesn't depend on table iteration order.
+#[cfg(feature = "dim2")]
+pub fn bodies_in_region(ctx: &ReducerContext, world: &PhysicsWorld, min: Vec2, max: Vec2) -> Vec<u64> {
+    bodies_in_region_in(RigidBody::all_in_world(ctx, world.id), Collider::all_in_world(ctx, world.id), min, max)
+}
+
+/// Same as `bodies_in_region`, over already loaded bodies and colliders (2D)
+#[cfg(feature = "dim2")]
+pub fn bodies_in_region_in(
+    bodies: impl IntoIterator<Item = RigidBody>,
+    colliders: impl IntoIterator<Item = Collider>,
+    min: Vec2,
+    max: Vec2,
+) -> Vec<u64> {
+    let region = Aabb::new(
+        nalgebra::Point2::new(min.x, min.y),
+        nalgebra::Point2::new(max.x, max.y),
+    );
+    bodies_overlapping(bodies, colliders, &region, |body| {
+        let pos = body.position_2d();
+        Isometry::new(nalgebra::Vector2::new(pos.x, pos.y), body.rotation_angle())
+    })
+}
+
+fn bodies_overlapping(
+    bodies: impl IntoIterator<Item = RigidBody>,
+    colliders: impl IntoIterator<Item = Collider>,
+    region: &Aabb,
+    isometry: impl Fn(&RigidBody) -> Isometry<f32>,
+) -> Vec<u64> {
+    let colliders: HashMap<u64, Collider> = colliders
+        .into_iter()
+        .map(|collider| (collider.id, collider))
+        .collect();
+
+    let mut body_ids: Vec<u64> = bodies
+        .into_iter()
+        .filter(|body| body.enabled)
+        .filter(|body| {
+            colliders.get(&body.collider_id).is_some_and(|collider| {
+                collider
+                    .shared_shape()
+                    .compute_aabb(&isometry(body))
+                    .intersects(region)
+            })
+        })
+        .map(|body| body.id)
+        .collect();
+    body_ids.sort_unstable();
+    body_ids
+}
+
+/// Sweep a shape from a pose along `direction` and get the first enabled body it touches (3D)
+///
+/// Shape casts rely on GJK, which only gives correct results for a convex
+/// moving shape, so a concave `shape` is rejected with `PhysicsError::ConcaveShape`.
+/// A zero `direction` never hits anything.
+#[cfg(feature = "dim3")]
+pub fn shape_cast(
+    ctx: &ReducerContext,
+    world: &PhysicsWorld,
+    shape: &Collider,
+    position: Vec3,
+    rotation: Quat,
+    direction: Vec3,
+    max_distance: f32,
+) -> Result<Option<ShapeCastHit>, PhysicsError> {
+    let bodies = RigidBody::all_in_world(ctx, world.id);
+    shape_cast_in(bodies, Collider::all_in_world(ctx, world.id), shape, position, rotation, direction, max_distance)
+}
+
+/// Same as `shape_cast`, over already loaded bodies and colliders (3D)
+#[cfg(feature = "dim3")]
+pub fn shape_cast_in(
+    bodies: impl IntoIterator<Item = RigidBody>,
+    colliders: impl IntoIterator<Item = Collider>,
+    shape: &Collider,
+    position: Vec3,
+    rotation: Quat,
+    direction: Vec3,
+    max_distance: f32,
+) -> Result<Option<ShapeCastHit>, PhysicsError> {
+    let pose = Isometry::from_parts(
+        nalgebra::Translation3::new(position.x, position.y, position.z),
+        nalgebra::UnitQuaternion::from(rotation),
+    );
+    let motion = nalgebra::Vector3::new(direction.x, direction.y, direction.z)
+        .try_normalize(f32::EPSILON)
+        .map(|direction| direction * max_distance);
+    first_hit(bodies, colliders, shape, pose, motion, |body| {
+        let pos = body.position();
+        Isometry::from_parts(
+            nalgebra::Translation3::new(pos.x, pos.y, pos.z),
+            nalgebra::UnitQuaternion::from(body.rotation()),
+        )
+    })
+}
+
+/// Sweep a shape from a pose along `direction` and get the first enabled body it touches (2D)
+///
+/// Shape casts rely on GJK, which only gives correct results for a convex
+/// moving shape, so a concave `shape` is rejected with `PhysicsError::ConcaveShape`.
+/// A zero `direction` never hits anything.
+#[cfg(feature = "dim2")]
+pub fn shape_cast(
+    ctx: &ReducerContext,
+    world: &PhysicsWorld,
+    shape: &Collider,
+    position: Vec2,
+    angle: f32,
+    direction: Vec2,
+    max_distance: f32,
+) -> Result<Option<ShapeCastHit>, PhysicsError> {
+    let bodies = RigidBody::all_in_world(ctx, world.id);
+    shape_cast_in(bodies, Collider::all_in_world(ctx, world.id), shape, position, angle, direction, max_distance)
+}
+
+/// Same as `shape_cast`, over already loaded bodies and colliders (2D)
+#[cfg(feature = "dim2")]
+pub fn shape_cast_in(
+    bodies: impl IntoIterator<Item = RigidBody>,
+    colliders: impl IntoIterator<Item = Collider>,
+    shape: &Collider,
+    position: Vec2,
+    angle: f32,
+    direction: Vec2,
+    max_distance: f32,
+) -> Result<Option<ShapeCastHit>, PhysicsError> {
+    let pose = Isometry::new(nalgebra::Vector2::new(position.x, position.y), angle);
+    let motion = nalgebra::Vector2::new(direction.x, direction.y)
+        .try_normalize(f32::EPSILON)
+        .map(|direction| direction * max_distance);
+    first_hit(bodies, colliders, shape, pose, motion, |body| {
+        let pos = body.position_2d();
+        Isometry::new(nalgebra::Vector2::new(pos.x, pos.y), body.rotation_angle())
+    })
+}
+
+/// Closest body hit by `shape` moving by `motion` from `pose`, ties go to the lowest ID
+fn first_hit(
+    bodies: impl IntoIterator<Item = RigidBody>,
+    colliders: impl IntoIterator<Item = Collider>,
+    shape: &Collider,
+    pose: Isometry<f32>,
+    motion: Option<Vector<f32>>,
+    isometry: impl Fn(&RigidBody) -> Isometry<f32>,
+) -> Result<Option<ShapeCastHit>, PhysicsError> {
+    if !shape.is_convex() {
+        return Err(PhysicsError::ConcaveShape(shape.id));
+    }
+    shape.validate()?;
+    let Some(motion) = motion else {
+        return Ok(None);
+    };
+
+    let colliders: HashMap<u64, Collider> = colliders
+        .into_iter()
+        .map(|collider| (collider.id, collider))
+        .collect();
+    let cast = shape.shared_shape();
+    let length = motion.norm();
+
+    let mut best: Option<ShapeCastHit> = None;
+    for body in bodies.into_iter().filter(|body| body.enabled) {
+        // Bodies with a broken collider are skipped by the step too
+        let Some(collider) = colliders.get(&body.collider_id).filter(|collider| collider.validate().is_ok()) else {
+            continue;
+        };
+        let target = collider.shared_shape();
+        // The motion is scaled to the max distance, so the time of impact is a fraction of it.
+        // Unsupported shape pairs can't be hit, like rays that miss.
+        let Ok(Some(hit)) = cast_shapes(
+            &pose,
+            &motion,
+            &*cast,
+            &isometry(&body),
+            &Vector::zeros(),
+            &*target,
+            ShapeCastOptions::with_max_time_of_impact(1.0),
+        ) else {
+            continue;
+        };
+        let distance = hit.time_of_impact * length;
+        let closer = match best {
+            Some(best) => distance < best.distance || (distance == best.distance && body.id < best.body_id),
+            None => true,
+        };
+        if closer {
+            best = Some(ShapeCastHit { body_id: body.id, distance });
+        }
+    }
+    Ok(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::test_utils::TestWorld;
+    use crate::math::Vec3;
+    use crate::tables::{Collider, ColliderType, RigidBody};
+
+    #[test]
+    fn body_inside_two_overlapping_triggers_is_in_both() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let zone = world.add_trigger(Trigger::default(), Collider::ball(1, 2.0));
+        let hazard = world.add_trigger(Trigger { position_x: 1.5, ..Default::default() }, Collider::ball(1, 1.0));
+        world.add_trigger(Trigger { position_x: 10.0, ..Default::default() }, Collider::ball(1, 1.0));
+        let player = world.add_body(RigidBody { position_x: 1.0, ..Default::default() }, Collider::ball(1, 0.25));
+
+        world.step();
+
+        let mut expected = vec![zone, hazard];
+        expected.sort();
+        assert_eq!(triggers_containing_in(world.rows.triggers.clone(), player), expected);
+        assert!(triggers_containing_in(world.rows.triggers.clone(), player + 1000).is_empty());
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn bodies_in_region_returns_only_the_bodies_overlapping_it() {
+        let mut world = TestWorld::with_defaults();
+        let inside = world.add_body(RigidBody { position_x: 2.0, ..Default::default() }, Collider::ball(1, 0.5));
+        // Its center is outside but its collider pokes into the region
+        let straddling = world.add_body(RigidBody { position_x: 5.3, ..Default::default() }, Collider::ball(1, 0.5));
+        world.add_body(RigidBody { position_x: 20.0, ..Default::default() }, Collider::ball(1, 0.5));
+        world.add_body(RigidBody { position_y: 8.0, ..Default::default() }, Collider::ball(1, 0.5));
+        world.add_body(RigidBody { enabled: false, ..Default::default() }, Collider::ball(1, 0.5));
+
+        let found = bodies_in_region_in(
+            world.rows.bodies.clone(),
+            world.rows.colliders.clone(),
+            Vec3::new(-5.0, -5.0, -5.0),
+            Vec3::new(5.0, 5.0, 5.0),
+        );
+
+        assert_eq!(found, vec![inside, straddling]);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn casting_a_concave_shape_is_rejected_and_a_convex_one_hits() {
+        let mut world = TestWorld::with_defaults();
+        let near = world.add_body(RigidBody { position_x: 5.0, ..Default::default() }, Collider::ball(1, 0.5));
+        world.add_body(RigidBody { position_x: 9.0, ..Default::default() }, Collider::ball(1, 0.5));
+        let cast = |shape: &Collider| {
+            shape_cast_in(
+                world.rows.bodies.clone(),
+                world.rows.colliders.clone(),
+                shape,
+                Vec3::ZERO,
+                Quat::IDENTITY,
+                Vec3::new(1.0, 0.0, 0.0),
+                20.0,
+            )
+        };
+
+        let hit = cast(&Collider::ball(1, 0.5)).unwrap().expect("the ball should hit");
+        assert_eq!(hit.body_id, near);
+        assert!((hit.distance - 4.0).abs() < 1e-3, "hit at {}", hit.distance);
+
+        let terrain = Collider { id: 42, collider_type: ColliderType::Heightfield, ..Collider::ball(1, 0.5) };
+        assert!(!terrain.is_convex());
+        assert_eq!(cast(&terrain), Err(PhysicsError::ConcaveShape(42)));
+    }
+}
@@ -0,0 +1,318 @@
+//! Spatial hash over rigid body positions
+//!
+//! A lightweight acceleration structure for gameplay queries (explosions,
+//! target lookup, ...) that doesn't require stepping the physics world.
+
+use std::collections::HashMap;
+
+use spacetimedb::ReducerContext;
+use crate::math::Vec3;
+use crate::tables::{PhysicsWorld, RigidBody};
+
+type Cell = (i64, i64, i64);
+
+/// Cell coordinates are clamped to this range so differences between them never overflow
+const MAX_CELL: i64 = 1 << 40;
+
+/// Uniform grid of body positions, built from a snapshot of the tables
+///
+/// Only body origins are indexed, collider extents are ignored.
+#[derive(Clone, Debug)]
+pub struct SpatialIndex {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<usize>>,
+    entries: Vec<(u64, Vec3)>,
+    min_cell: Cell,
+    max_cell: Cell,
+}
+
+impl SpatialIndex {
+    /// Cell size used by `build`
+    pub const DEFAULT_CELL_SIZE: f32 = 4.0;
+
+    /// Index every enabled body of a world
+    pub fn build(ctx: &ReducerContext, world: &PhysicsWorld) -> Self {
+        Self::build_with_cell_size(ctx, world, Self::DEFAULT_CELL_SIZE)
+    }
+
+    /// Index every enabled body of a world with a custom cell size
+    ///
+    /// The cell size should be close to the typical query radius.
+    pub fn build_with_cell_size(ctx: &ReducerContext, world: &PhysicsWorld, cell_size: f32) -> Self {
+        let points = RigidBody::all_in_world(ctx, world.id)
+            .filter(|body| body.enabled)
+            .map(|body| (body.id, body.position()));
+        Self::from_points(points, cell_size)
+    }
+
+    /// Index arbitrary `(id, position)` pairs
+    pub fn from_points(points: impl IntoIterator<Item = (u64, Vec3)>, cell_size: f32) -> Self {
+        let mut index = Self {
+            cell_size: cell_size.max(1e-3),
+            cells: HashMap::new(),
+            entries: Vec::new(),
+            min_cell: (i64::MAX, i64::MAX, i64::MAX),
+            max_cell: (i64::MIN, i64::MIN, i64::MIN),
+        };
+
+        for (id, position) in points {
+            let cell = index.cell_of(position);
+            index.min_cell = (
+                index.min_cell.0.min(cell.0),
+                index.min_cell.1.min(cell.1),
+                index.min_cell.2.min(cell.2),
+            );
+            index.max_cell = (
+                index.max_cell.0.max(cell.0),
+                index.max_cell.1.max(cell.1),
+                index.max_cell.2.max(cell.2),
+            );
+            index.cells.entry(cell).or_default().push(index.entries.len());
+            index.entries.push((id, position));
+        }
+
+        index
+    }
+
+    /// Number of indexed bodies
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no body was indexed
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the IDs of all bodies within `radius` of `center`, closest first
+    pub fn query_radius(&self, center: Vec3, radius: f32) -> Vec<u64> {
+        if self.is_empty() || radius < 0.0 {
+            return Vec::new();
+        }
+
+        let (min, max) = self.clamp_to_bounds(
+            self.cell_of(center - Vec3::new(radius, radius, radius)),
+            self.cell_of(center + Vec3::new(radius, radius, radius)),
+        );
+        let radius_sq = radius * radius;
+        let mut hits: Vec<(u64, f32)> = Vec::new();
+        let mut push_hit = |&(id, position): &(u64, Vec3)| {
+            let dist_sq = position.distance_squared(center);
+            if dist_sq <= radius_sq {
+                hits.push((id, dist_sq));
+            }
+        };
+
+        // Visiting more cells than there are bodies costs more than checking every body
+        if cell_count(min, max) > self.entries.len() as u64 {
+            self.entries.iter().for_each(push_hit);
+        } else {
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    for z in min.2..=max.2 {
+                        self.entries_in((x, y, z)).for_each(&mut push_hit);
+                    }
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        hits.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Get the ID of the body closest to `point`
+    ///
+    /// Searches rings of cells around `point`, clamped to the occupied cells,
+    /// and falls back to checking every body once the rings have visited as
+    /// many cells as there are bodies.
+    pub fn nearest(&self, point: Vec3) -> Option<u64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let center = self.cell_of(point);
+        // Rings closer than the occupied cells are empty, the farthest one reaches every cell
+        let gap = |c: i64, min: i64, max: i64| (min - c).max(c - max).max(0);
+        let first_ring = gap(center.0, self.min_cell.0, self.max_cell.0)
+            .max(gap(center.1, self.min_cell.1, self.max_cell.1))
+            .max(gap(center.2, self.min_cell.2, self.max_cell.2));
+        let reach = |c: i64, min: i64, max: i64| (c - min).abs().max((c - max).abs());
+        let last_ring = reach(center.0, self.min_cell.0, self.max_cell.0)
+            .max(reach(center.1, self.min_cell.1, self.max_cell.1))
+            .max(reach(center.2, self.min_cell.2, self.max_cell.2));
+
+        let mut budget = self.entries.len();
+        let mut best: Option<(u64, f32)> = None;
+
+        for ring in first_ring..=last_ring {
+            // Anything in this ring or beyond is at least (ring - 1) cells away
+            if let Some((_, best_dist_sq)) = best {
+                let min_dist = (ring - 1).max(0) as f32 * self.cell_size;
+                if min_dist * min_dist > best_dist_sq {
+                    break;
+                }
+            }
+
+            let cells = self.ring_cells(center, ring, budget);
+            if cells.len() >= budget {
+                // Sparse or far away content, a linear scan is cheaper than more rings
+                best = None;
+                self.entries.iter().for_each(|entry| keep_closest(&mut best, entry, point));
+                break;
+            }
+            budget -= cells.len();
+
+            for cell in cells {
+                self.entries_in(cell).for_each(|entry| keep_closest(&mut best, entry, point));
+            }
+        }
+
+        best.map(|(id, _)| id)
+    }
+
+    fn cell_of(&self, position: Vec3) -> Cell {
+        // Float to int casts saturate, clamping keeps the cell arithmetic below from overflowing
+        let cell = |v: f32| ((v / self.cell_size).floor() as i64).clamp(-MAX_CELL, MAX_CELL);
+        (cell(position.x), cell(position.y), cell(position.z))
+    }
+
+    /// Clamp the cell range `[min, max]` to the occupied cells
+    fn clamp_to_bounds(&self, min: Cell, max: Cell) -> (Cell, Cell) {
+        (
+            (
+                min.0.max(self.min_cell.0),
+                min.1.max(self.min_cell.1),
+                min.2.max(self.min_cell.2),
+            ),
+            (
+                max.0.min(self.max_cell.0),
+                max.1.min(self.max_cell.1),
+                max.2.min(self.max_cell.2),
+            ),
+        )
+    }
+
+    /// Occupied-range cells exactly `ring` cells away from `center` (the surface of a cube)
+    ///
+    /// Stops early once `limit` cells were collected.
+    fn ring_cells(&self, center: Cell, ring: i64, limit: usize) -> Vec<Cell> {
+        let (min, max) = self.clamp_to_bounds(
+            (center.0 - ring, center.1 - ring, center.2 - ring),
+            (center.0 + ring, center.1 + ring, center.2 + ring),
+        );
+
+        let mut cells = Vec::new();
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                if cells.len() >= limit {
+                    return cells;
+                }
+                if (x - center.0).abs() == ring || (y - center.1).abs() == ring {
+                    cells.extend((min.2..=max.2).map(|z| (x, y, z)));
+                } else {
+                    // Inside the cube's X/Y extent only the two Z faces are on the surface
+                    for z in [center.2 - ring, center.2 + ring] {
+                        if (min.2..=max.2).contains(&z) {
+                            cells.push((x, y, z));
+                        }
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    fn entries_in(&self, cell: Cell) -> impl Iterator<Item = &(u64, Vec3)> + '_ {
+        self.cells
+            .get(&cell)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.entries[i])
+    }
+}
+
+/// Number of cells in the range `[min, max]`, 0 if it's empty
+fn cell_count(min: Cell, max: Cell) -> u64 {
+    let span = |min: i64, max: i64| (max - min + 1).max(0) as u64;
+    span(min.0, max.0)
+        .saturating_mul(span(min.1, max.1))
+        .saturating_mul(span(min.2, max.2))
+}
+
+/// Replace `best` with `entry` if it's closer to `point`, ties go to the lowest ID
+fn keep_closest(best: &mut Option<(u64, f32)>, &(id, position): &(u64, Vec3), point: Vec3) {
+    let dist_sq = position.distance_squared(point);
+    let closer = match *best {
+        Some((best_id, best_dist_sq)) => dist_sq < best_dist_sq || (dist_sq == best_dist_sq && id < best_id),
+        None => true,
+    };
+    if closer {
+        *best = Some((id, dist_sq));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic scatter of points in a 100m cube
+    fn scattered_points(count: u64) -> Vec<(u64, Vec3)> {
+        let mut seed = 12345u64;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 40) as f32 / (1u64 << 24) as f32 * 100.0 - 50.0
+        };
+        (1..=count).map(|id| (id, Vec3::new(next(), next(), next()))).collect()
+    }
+
+    fn brute_force_nearest(points: &[(u64, Vec3)], point: Vec3) -> Option<u64> {
+        let mut best = None;
+        points.iter().for_each(|entry| keep_closest(&mut best, entry, point));
+        best.map(|(id, _)| id)
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let points = scattered_points(200);
+        let index = SpatialIndex::from_points(points.clone(), 4.0);
+
+        let queries = scattered_points(50).into_iter().map(|(_, p)| p).chain([
+            Vec3::ZERO,
+            Vec3::new(1e6, 0.0, 0.0),
+            Vec3::new(-1e6, 1e6, -1e6),
+            Vec3::new(1e30, -1e30, 1e30),
+            Vec3::new(f32::MAX, f32::MIN, 0.0),
+        ]);
+        for query in queries {
+            assert_eq!(index.nearest(query), brute_force_nearest(&points, query), "query {query:?}");
+        }
+    }
+
+    #[test]
+    fn nearest_handles_far_apart_clusters() {
+        let points = vec![(1, Vec3::ZERO), (2, Vec3::new(1e7, 1e7, 1e7)), (3, Vec3::new(-1e30, 0.0, 0.0))];
+        let index = SpatialIndex::from_points(points.clone(), 1.0);
+
+        for query in [Vec3::new(1.0, 1.0, 1.0), Vec3::new(9e6, 9e6, 9e6), Vec3::new(-1e29, 0.0, 0.0)] {
+            assert_eq!(index.nearest(query), brute_force_nearest(&points, query), "query {query:?}");
+        }
+    }
+
+    #[test]
+    fn query_radius_matches_brute_force() {
+        let points = scattered_points(200);
+        let index = SpatialIndex::from_points(points.clone(), 4.0);
+
+        for (radius, center) in [(0.0, Vec3::ZERO), (5.0, Vec3::new(10.0, -5.0, 3.0)), (30.0, Vec3::ZERO), (1e9, Vec3::ZERO)] {
+            let mut expected: Vec<(u64, f32)> = points
+                .iter()
+                .map(|&(id, position)| (id, position.distance_squared(center)))
+                .filter(|&(_, dist_sq)| dist_sq <= radius * radius)
+                .collect();
+            expected.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            let expected: Vec<u64> = expected.into_iter().map(|(id, _)| id).collect();
+
+            assert_eq!(index.query_radius(center, radius), expected, "radius {radius}");
+        }
+    }
+}
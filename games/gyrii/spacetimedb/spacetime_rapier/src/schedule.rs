@@ -0,0 +1,93 @@
+//! Tick scheduling helpers
+//!
+//! Keeps physics ticks on a fixed grid derived from `ticks_per_second`, so a
+//! stalled server can tell how many steps it missed instead of drifting.
+
+use spacetimedb::Timestamp;
+
+use crate::tables::PhysicsWorld;
+
+/// Upper bound on `TickSchedule::catch_up_steps`
+///
+/// Stepping too many times in one reducer makes the next tick late too, so
+/// after a long stall the remaining missed ticks are dropped.
+pub const MAX_CATCH_UP_STEPS: u32 = 5;
+
+/// When to run the next tick, and how many ticks were missed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TickSchedule {
+    /// Absolute time of the next tick, on the grid started by `last_tick_time`
+    pub next_tick: Timestamp,
+    /// Extra steps to run now to catch up (capped at `MAX_CATCH_UP_STEPS`)
+    pub catch_up_steps: u32,
+}
+
+/// Compute the next tick of a world from the time the current tick was scheduled for
+///
+/// `last_tick_time` is the scheduled (not actual) time of the tick being run,
+/// and `now` is usually `ctx.timestamp`. Ticks that should have run between
+/// the two are reported in `catch_up_steps`.
+pub fn schedule_next_tick(world: &PhysicsWorld, last_tick_time: Timestamp, now: Timestamp) -> TickSchedule {
+    let interval = tick_interval_micros(world);
+    let last = last_tick_time.to_micros_since_unix_epoch();
+    let elapsed = (now.to_micros_since_unix_epoch() - last).max(0);
+
+    // Whole intervals since the last tick, the current one not included
+    let missed = elapsed / interval;
+
+    TickSchedule {
+        next_tick: Timestamp::from_micros_since_unix_epoch(last + (missed + 1) * interval),
+        catch_up_steps: missed.min(MAX_CATCH_UP_STEPS as i64) as u32,
+    }
+}
+
+/// Duration of one tick in microseconds (at least 1)
+pub fn tick_interval_micros(world: &PhysicsWorld) -> i64 {
+    let ticks_per_second = if world.ticks_per_second.is_finite() && world.ticks_per_second > 0.0 {
+        world.ticks_per_second as f64
+    } else {
+        60.0
+    };
+    ((1_000_000.0 / ticks_per_second).round() as i64).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(micros: i64) -> Timestamp {
+        Timestamp::from_micros_since_unix_epoch(micros)
+    }
+
+    #[test]
+    fn on_time_tick_schedules_the_next_one_without_catch_up() {
+        let world = PhysicsWorld::builder().ticks_per_second(50.0).build();
+        let start = 1_000_000;
+
+        let schedule = schedule_next_tick(&world, at(start), at(start + 3_000));
+
+        assert_eq!(schedule, TickSchedule { next_tick: at(start + 20_000), catch_up_steps: 0 });
+    }
+
+    #[test]
+    fn stall_reports_missed_ticks_and_stays_on_the_grid() {
+        let world = PhysicsWorld::builder().ticks_per_second(50.0).build();
+        let start = 1_000_000;
+
+        // Ran 2.5 ticks late: the ticks at +20ms and +40ms were missed
+        let schedule = schedule_next_tick(&world, at(start), at(start + 50_000));
+        assert_eq!(schedule, TickSchedule { next_tick: at(start + 60_000), catch_up_steps: 2 });
+
+        // A long stall is capped, the next tick is still on the grid
+        let schedule = schedule_next_tick(&world, at(start), at(start + 1_000_000));
+        assert_eq!(schedule.catch_up_steps, MAX_CATCH_UP_STEPS);
+        assert_eq!(schedule.next_tick, at(start + 1_020_000));
+    }
+
+    #[test]
+    fn clock_going_backwards_is_not_a_stall() {
+        let world = PhysicsWorld::builder().ticks_per_second(50.0).build();
+        let schedule = schedule_next_tick(&world, at(1_000_000), at(900_000));
+        assert_eq!(schedule, TickSchedule { next_tick: at(1_020_000), catch_up_steps: 0 });
+    }
+}
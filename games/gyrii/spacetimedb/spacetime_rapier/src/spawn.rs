@@ -0,0 +1,96 @@
+//! Spawning helpers
+//!
+//! Shortcuts for common dynamic bodies on top of the table APIs.
+
+use spacetimedb::ReducerContext;
+
+use crate::math::Vec3;
+use crate::tables::{Collider, PhysicsWorldId, RigidBody, RigidBodyId, RigidBodyProperties, RigidBodyType};
+
+/// Insert a dynamic ball moving at `velocity`, returns its body ID
+///
+/// Creates the collider, properties and body of a bullet, rocket or grenade
+/// in one call. Keep `ccd` on for anything fast enough to cross a wall in a
+/// single step, or it tunnels through.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_projectile(
+    ctx: &ReducerContext,
+    world_id: PhysicsWorldId,
+    origin: Vec3,
+    velocity: Vec3,
+    radius: f32,
+    mass: f32,
+    restitution: f32,
+    ccd: bool,
+) -> RigidBodyId {
+    let (collider, properties, body) = projectile_rows(world_id, origin, velocity, radius, mass, restitution, ccd);
+    let collider = collider.insert(ctx);
+    let properties = properties.insert(ctx);
+    RigidBody { collider_id: collider.id, properties_id: properties.id, ..body }.insert(ctx).id
+}
+
+/// The collider, properties and body rows inserted by `spawn_projectile`
+///
+/// The body's collider and properties IDs are left to the caller. Rapier
+/// derives the mass from the density, so the density is the one that gives
+/// the ball `mass`.
+#[allow(clippy::too_many_arguments)]
+pub fn projectile_rows(
+    world_id: PhysicsWorldId,
+    origin: Vec3,
+    velocity: Vec3,
+    radius: f32,
+    mass: f32,
+    restitution: f32,
+    ccd: bool,
+) -> (Collider, RigidBodyProperties, RigidBody) {
+    let collider = Collider::ball(world_id, radius);
+    let unit_mass = collider.shared_shape().mass_properties(1.0).mass();
+    let density = if unit_mass > 0.0 { mass / unit_mass } else { 1.0 };
+    let properties = RigidBodyProperties {
+        world_id,
+        mass,
+        density,
+        restitution,
+        ccd_enabled: ccd,
+        ..Default::default()
+    };
+
+    let mut body = RigidBody {
+        world_id,
+        body_type: RigidBodyType::Dynamic,
+        ..Default::default()
+    };
+    body.set_position(origin);
+    body.set_linear_velocity(velocity);
+    (collider, properties, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::test_utils::TestWorld;
+    use crate::tables::PhysicsWorld;
+
+    #[test]
+    fn projectile_keeps_its_velocity_ccd_flag_and_mass() {
+        let origin = Vec3::new(0.0, 2.0, 0.0);
+        let velocity = Vec3::new(30.0, 5.0, 0.0);
+        let (collider, properties, body) = projectile_rows(1, origin, velocity, 0.2, 0.5, 0.3, true);
+
+        assert_eq!(body.position(), origin);
+        assert_eq!(body.linear_velocity(), velocity);
+        assert_eq!(body.body_type, RigidBodyType::Dynamic);
+        assert!(properties.ccd_enabled);
+        assert_eq!(properties.restitution, 0.3);
+        let mass = collider.shared_shape().mass_properties(properties.density).mass();
+        assert!((mass - 0.5).abs() < 1e-5, "mass {mass}");
+
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let projectile = world.add_body_with(body, collider, properties);
+        world.step();
+        let projectile = world.body(projectile);
+        assert!((projectile.linear_velocity() - velocity).length() < 1e-4);
+        assert!(projectile.position_x > origin.x);
+    }
+}
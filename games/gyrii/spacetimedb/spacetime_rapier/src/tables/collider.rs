@@ -1,11 +1,17 @@
 //! Collider table - collision shapes for rigid bodies
 
+use std::f32::consts::PI;
+
 use bon::Builder;
 use spacetimedb::{table, ReducerContext, SpacetimeType, Table};
-use crate::math::Vec3;
+use crate::math::{Vec2, Vec3};
+use crate::PhysicsError;
 
 #[cfg(feature = "dim2")]
-use crate::math::Vec2;
+use rapier2d::prelude::{ActiveCollisionTypes, SharedShape};
+
+#[cfg(feature = "dim3")]
+use rapier3d::prelude::{ActiveCollisionTypes, SharedShape};
 
 pub type ColliderId = u64;
 
@@ -27,6 +33,36 @@ pub enum ColliderType {
     Triangle,
     /// Heightfield - not yet implemented
     Heightfield,
+    /// Box with rounded edges (half-extents, border radius)
+    RoundedCuboid,
+    /// Cylinder with rounded edges (half-height, radius, border radius) - 3D only
+    RoundedCylinder,
+}
+
+/// Bit flags for the pairs of body types a collider generates contacts with
+///
+/// A pair is checked if either of its colliders enables it. Rapier skips
+/// kinematic-static contacts by default, so a kinematic player standing on a
+/// static floor needs `KINEMATIC_STATIC` on the player or the floor.
+pub struct CollisionTypeFlags;
+
+impl CollisionTypeFlags {
+    /// Dynamic against dynamic
+    pub const DYNAMIC_DYNAMIC: u8 = 1 << 0;
+    /// Dynamic against kinematic
+    pub const DYNAMIC_KINEMATIC: u8 = 1 << 1;
+    /// Dynamic against static
+    pub const DYNAMIC_STATIC: u8 = 1 << 2;
+    /// Kinematic against kinematic
+    pub const KINEMATIC_KINEMATIC: u8 = 1 << 3;
+    /// Kinematic against static
+    pub const KINEMATIC_STATIC: u8 = 1 << 4;
+    /// Static against static
+    pub const STATIC_STATIC: u8 = 1 << 5;
+    /// Rapier's default, every pair involving a dynamic body
+    pub const DEFAULT: u8 = Self::DYNAMIC_DYNAMIC | Self::DYNAMIC_KINEMATIC | Self::DYNAMIC_STATIC;
+    /// Every pair
+    pub const ALL: u8 = Self::DEFAULT | Self::KINEMATIC_KINEMATIC | Self::KINEMATIC_STATIC | Self::STATIC_STATIC;
 }
 
 /// A collider (collision shape) in the physics simulation
@@ -66,6 +102,11 @@ pub struct Collider {
     #[builder(default = 0.5)]
     pub half_extent_z: f32,
 
+    /// For RoundedCuboid/RoundedCylinder: radius of the rounded border,
+    /// added around the base shape so it grows the shape by this amount
+    #[builder(default = 0.0)]
+    pub border_radius: f32,
+
     // For Triangle: vertices
     #[builder(default = 0.0)]
     pub vertex_a_x: f32,
@@ -89,14 +130,112 @@ pub struct Collider {
     /// Whether this is a sensor (trigger) - no physical response
     #[builder(default = false)]
     pub is_sensor: bool,
+
+    /// Pairs of body types this collider generates contacts with (see
+    /// `CollisionTypeFlags`), 0 keeps Rapier's default
+    #[builder(default = 0)]
+    pub active_collision_types: u8,
 }
 
 impl Collider {
     /// Insert this collider into the database
+    ///
+    /// Invalid shape parameters are logged but still inserted, use
+    /// `try_insert` to reject them instead.
     pub fn insert(self, ctx: &ReducerContext) -> Self {
+        if let Err(err) = self.validate() {
+            log::warn!("Collider::insert: {}", err);
+        }
         ctx.db.rapier_collider().insert(self)
     }
 
+    /// Insert this collider into the database if its shape parameters are valid
+    pub fn try_insert(self, ctx: &ReducerContext) -> Result<Self, PhysicsError> {
+        self.validate()?;
+        Ok(ctx.db.rapier_collider().insert(self))
+    }
+
+    /// Check that the parameters used by this collider's shape are finite and positive
+    pub fn validate(&self) -> Result<(), PhysicsError> {
+        self.check_shape().map_err(|reason| PhysicsError::InvalidShape {
+            collider_id: self.id,
+            reason,
+        })
+    }
+
+    fn check_shape(&self) -> Result<(), String> {
+        let positive = |name: &str, value: f32| {
+            if value.is_finite() && value > 0.0 {
+                Ok(())
+            } else {
+                Err(format!("{:?} collider has an invalid {} ({})", self.collider_type, name, value))
+            }
+        };
+        let non_negative = |name: &str, value: f32| {
+            if value.is_finite() && value >= 0.0 {
+                Ok(())
+            } else {
+                Err(format!("{:?} collider has an invalid {} ({})", self.collider_type, name, value))
+            }
+        };
+        let finite = |name: &str, value: f32| {
+            if value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("{:?} collider has a non-finite {}", self.collider_type, name))
+            }
+        };
+
+        match self.collider_type {
+            ColliderType::Ball => positive("radius", self.radius),
+            ColliderType::Cuboid => {
+                positive("half_extent_x", self.half_extent_x)?;
+                positive("half_extent_y", self.half_extent_y)?;
+                #[cfg(feature = "dim3")]
+                positive("half_extent_z", self.half_extent_z)?;
+                Ok(())
+            }
+            ColliderType::Capsule => {
+                positive("radius", self.radius)?;
+                // A capsule with no half-height is a ball
+                non_negative("half_height", self.half_height)
+            }
+            ColliderType::Cylinder | ColliderType::Cone => {
+                positive("radius", self.radius)?;
+                positive("half_height", self.half_height)
+            }
+            ColliderType::Triangle => {
+                for (name, value) in [
+                    ("vertex_a_x", self.vertex_a_x),
+                    ("vertex_a_y", self.vertex_a_y),
+                    ("vertex_a_z", self.vertex_a_z),
+                    ("vertex_b_x", self.vertex_b_x),
+                    ("vertex_b_y", self.vertex_b_y),
+                    ("vertex_b_z", self.vertex_b_z),
+                    ("vertex_c_x", self.vertex_c_x),
+                    ("vertex_c_y", self.vertex_c_y),
+                    ("vertex_c_z", self.vertex_c_z),
+                ] {
+                    finite(name, value)?;
+                }
+                Ok(())
+            }
+            ColliderType::Heightfield => Ok(()),
+            ColliderType::RoundedCuboid => {
+                positive("half_extent_x", self.half_extent_x)?;
+                positive("half_extent_y", self.half_extent_y)?;
+                #[cfg(feature = "dim3")]
+                positive("half_extent_z", self.half_extent_z)?;
+                non_negative("border_radius", self.border_radius)
+            }
+            ColliderType::RoundedCylinder => {
+                positive("radius", self.radius)?;
+                positive("half_height", self.half_height)?;
+                non_negative("border_radius", self.border_radius)
+            }
+        }
+    }
+
     /// Find a collider by ID
     pub fn find(ctx: &ReducerContext, id: ColliderId) -> Option<Self> {
         ctx.db.rapier_collider().id().find(id)
@@ -107,6 +246,11 @@ impl Collider {
         ctx.db.rapier_collider().world_id().filter(world_id)
     }
 
+    /// Get all colliders in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
     /// Update this collider in the database
     pub fn update(self, ctx: &ReducerContext) -> Self {
         ctx.db.rapier_collider().id().update(self)
@@ -155,6 +299,14 @@ impl Collider {
         }
     }
 
+    /// Create an upright capsule for a character of the given total height
+    ///
+    /// The height includes both hemispherical caps. Pair it with
+    /// `RigidBody::lock_upright` (or use `CharacterBundle`) so it can't topple.
+    pub fn character(world_id: u64, height: f32, radius: f32) -> Self {
+        Self::capsule(world_id, (height * 0.5 - radius).max(0.0), radius)
+    }
+
     /// Create a cylinder collider (3D only)
     pub fn cylinder(world_id: u64, half_height: f32, radius: f32) -> Self {
         Self {
@@ -179,6 +331,29 @@ impl Collider {
         }
     }
 
+    /// Create a cuboid collider with rounded edges
+    ///
+    /// The border is added around the box, so the collider extends
+    /// `half_extents + border_radius` from its center.
+    pub fn rounded_cuboid(world_id: u64, half_extents: Vec3, border_radius: f32) -> Self {
+        Self {
+            collider_type: ColliderType::RoundedCuboid,
+            border_radius,
+            ..Self::cuboid(world_id, half_extents)
+        }
+    }
+
+    /// Create a cylinder collider with rounded edges (3D only)
+    ///
+    /// The border is added around the cylinder, like `rounded_cuboid`.
+    pub fn rounded_cylinder(world_id: u64, half_height: f32, radius: f32, border_radius: f32) -> Self {
+        Self {
+            collider_type: ColliderType::RoundedCylinder,
+            border_radius,
+            ..Self::cylinder(world_id, half_height, radius)
+        }
+    }
+
     /// Create a triangle collider
     pub fn triangle(world_id: u64, a: Vec3, b: Vec3, c: Vec3) -> Self {
         Self {
@@ -198,11 +373,251 @@ impl Collider {
         }
     }
 
+    /// Get the end points of a capsule's segment, at `±half_height` on Y
+    ///
+    /// Matches the shape built by `shared_shape` and the profile used by
+    /// `inertia_2d`. `spacetime_physics` capsules store their full height
+    /// instead, so `height = 2 * half_height` when porting between crates.
+    pub fn capsule_points(&self) -> (Vec3, Vec3) {
+        (
+            Vec3::new(0.0, -self.half_height, 0.0),
+            Vec3::new(0.0, self.half_height, 0.0),
+        )
+    }
+
+    /// Whether the shape is convex
+    ///
+    /// GJK-based queries only give correct results when the moving shape is
+    /// convex, `shape_cast` rejects concave ones. Heightfields are concave,
+    /// every other shape is convex.
+    pub fn is_convex(&self) -> bool {
+        match self.collider_type {
+            ColliderType::Heightfield => false,
+            ColliderType::Ball
+            | ColliderType::Cuboid
+            | ColliderType::Capsule
+            | ColliderType::Cylinder
+            | ColliderType::Cone
+            | ColliderType::Triangle
+            | ColliderType::RoundedCuboid
+            | ColliderType::RoundedCylinder => true,
+        }
+    }
+
+    /// Convert `active_collision_types` to Rapier's flags
+    pub fn collision_types(&self) -> ActiveCollisionTypes {
+        let flags = match self.active_collision_types {
+            0 => CollisionTypeFlags::DEFAULT,
+            flags => flags,
+        };
+        let pairs = [
+            (CollisionTypeFlags::DYNAMIC_DYNAMIC, ActiveCollisionTypes::DYNAMIC_DYNAMIC),
+            (CollisionTypeFlags::DYNAMIC_KINEMATIC, ActiveCollisionTypes::DYNAMIC_KINEMATIC),
+            (CollisionTypeFlags::DYNAMIC_STATIC, ActiveCollisionTypes::DYNAMIC_FIXED),
+            (CollisionTypeFlags::KINEMATIC_KINEMATIC, ActiveCollisionTypes::KINEMATIC_KINEMATIC),
+            (CollisionTypeFlags::KINEMATIC_STATIC, ActiveCollisionTypes::KINEMATIC_FIXED),
+            (CollisionTypeFlags::STATIC_STATIC, ActiveCollisionTypes::FIXED_FIXED),
+        ];
+        pairs
+            .into_iter()
+            .filter(|(flag, _)| flags & flag != 0)
+            .fold(ActiveCollisionTypes::empty(), |types, (_, rapier)| types | rapier)
+    }
+
     /// Get half-extents as Vec3 (for Cuboid)
     pub fn half_extents(&self) -> Vec3 {
         Vec3::new(self.half_extent_x, self.half_extent_y, self.half_extent_z)
     }
 
+    /// Volume of the shape, treated as a 3D solid
+    ///
+    /// Planar and placeholder shapes (triangle, heightfield) have no volume.
+    pub fn volume(&self) -> f32 {
+        let r = self.radius;
+        let h = 2.0 * self.half_height;
+        match self.collider_type {
+            ColliderType::Ball => (4.0 / 3.0) * PI * r * r * r,
+            ColliderType::Cuboid => {
+                8.0 * self.half_extent_x * self.half_extent_y * self.half_extent_z
+            }
+            ColliderType::Capsule => PI * r * r * h + (4.0 / 3.0) * PI * r * r * r,
+            ColliderType::Cylinder => PI * r * r * h,
+            ColliderType::Cone => PI * r * r * h / 3.0,
+            ColliderType::Triangle | ColliderType::Heightfield => 0.0,
+            // Steiner formula: base volume, plus its faces, edges and corners swept by the border
+            ColliderType::RoundedCuboid => {
+                let b = self.border_radius;
+                let (x, y, z) = (
+                    2.0 * self.half_extent_x,
+                    2.0 * self.half_extent_y,
+                    2.0 * self.half_extent_z,
+                );
+                x * y * z
+                    + 2.0 * b * (x * y + y * z + x * z)
+                    + PI * b * b * (x + y + z)
+                    + (4.0 / 3.0) * PI * b * b * b
+            }
+            ColliderType::RoundedCylinder => {
+                let b = self.border_radius;
+                PI * r * r * h
+                    + 2.0 * PI * r * b * (r + h)
+                    + (PI * h + PI * PI * r) * b * b
+                    + (4.0 / 3.0) * PI * b * b * b
+            }
+        }
+    }
+
+    /// Surface area of the shape, treated as a 3D solid
+    ///
+    /// Planar and placeholder shapes (triangle, heightfield) return 0.
+    pub fn surface_area(&self) -> f32 {
+        let r = self.radius;
+        let h = 2.0 * self.half_height;
+        match self.collider_type {
+            ColliderType::Ball => 4.0 * PI * r * r,
+            ColliderType::Cuboid => {
+                let (x, y, z) = (
+                    2.0 * self.half_extent_x,
+                    2.0 * self.half_extent_y,
+                    2.0 * self.half_extent_z,
+                );
+                2.0 * (x * y + y * z + x * z)
+            }
+            ColliderType::Capsule => 2.0 * PI * r * h + 4.0 * PI * r * r,
+            ColliderType::Cylinder => 2.0 * PI * r * (r + h),
+            ColliderType::Cone => PI * r * (r + (r * r + h * h).sqrt()),
+            ColliderType::Triangle | ColliderType::Heightfield => 0.0,
+            // Derivatives of the rounded volumes with respect to the border radius
+            ColliderType::RoundedCuboid => {
+                let b = self.border_radius;
+                let (x, y, z) = (
+                    2.0 * self.half_extent_x,
+                    2.0 * self.half_extent_y,
+                    2.0 * self.half_extent_z,
+                );
+                2.0 * (x * y + y * z + x * z) + 2.0 * PI * b * (x + y + z) + 4.0 * PI * b * b
+            }
+            ColliderType::RoundedCylinder => {
+                let b = self.border_radius;
+                2.0 * PI * r * (r + h) + 2.0 * (PI * h + PI * PI * r) * b + 4.0 * PI * b * b
+            }
+        }
+    }
+
+    /// Scalar moment of inertia of the shape as a 2D solid, about its center (Z axis)
+    ///
+    /// Uses the XY profile of the shape. Rounded cuboids are approximated by
+    /// their enclosing rectangle. Cylinders, cones and heightfields have no 2D
+    /// counterpart and return 0.
+    pub fn inertia_2d(&self, mass: f32) -> f32 {
+        let r = self.radius;
+        let h = self.half_height;
+        match self.collider_type {
+            ColliderType::Ball => 0.5 * mass * r * r,
+            ColliderType::Cuboid => {
+                let (hx, hy) = (self.half_extent_x, self.half_extent_y);
+                mass * (hx * hx + hy * hy) / 3.0
+            }
+            ColliderType::RoundedCuboid => {
+                let b = self.border_radius;
+                let (hx, hy) = (self.half_extent_x + b, self.half_extent_y + b);
+                mass * (hx * hx + hy * hy) / 3.0
+            }
+            ColliderType::Capsule => {
+                // A 2r x 2h rectangle plus two half disks at its ends (along Y)
+                let rect_area = 4.0 * r * h;
+                let disk_area = PI * r * r;
+                let area = rect_area + disk_area;
+                if area <= 0.0 {
+                    return 0.0;
+                }
+                let rect_mass = mass * rect_area / area;
+                let disk_mass = mass * disk_area / area;
+                // Distance from a half disk's flat side to its centroid
+                let d = 4.0 * r / (3.0 * PI);
+
+                rect_mass * (r * r + h * h) / 3.0
+                    + disk_mass * (0.5 * r * r + h * h + 2.0 * h * d)
+            }
+            ColliderType::Triangle => {
+                let a = Vec2::new(self.vertex_a_x, self.vertex_a_y);
+                let b = Vec2::new(self.vertex_b_x, self.vertex_b_y);
+                let c = Vec2::new(self.vertex_c_x, self.vertex_c_y);
+                // About the centroid: m * (sum of squared side lengths) / 36
+                mass * (a.distance_squared(b) + b.distance_squared(c) + c.distance_squared(a)) / 36.0
+            }
+            ColliderType::Cylinder
+            | ColliderType::RoundedCylinder
+            | ColliderType::Cone
+            | ColliderType::Heightfield => 0.0,
+        }
+    }
+
+    /// Build the Rapier shape for this collider (3D)
+    #[cfg(feature = "dim3")]
+    pub fn shared_shape(&self) -> SharedShape {
+        use nalgebra::Point3;
+
+        match self.collider_type {
+            ColliderType::Ball => SharedShape::ball(self.radius),
+            ColliderType::Cuboid => SharedShape::cuboid(
+                self.half_extent_x,
+                self.half_extent_y,
+                self.half_extent_z,
+            ),
+            ColliderType::Capsule => SharedShape::capsule_y(self.half_height, self.radius),
+            ColliderType::Cylinder => SharedShape::cylinder(self.half_height, self.radius),
+            ColliderType::Cone => SharedShape::cone(self.half_height, self.radius),
+            ColliderType::RoundedCuboid => SharedShape::round_cuboid(
+                self.half_extent_x,
+                self.half_extent_y,
+                self.half_extent_z,
+                self.border_radius,
+            ),
+            ColliderType::RoundedCylinder => SharedShape::round_cylinder(
+                self.half_height,
+                self.radius,
+                self.border_radius,
+            ),
+            ColliderType::Triangle => SharedShape::triangle(
+                Point3::new(self.vertex_a_x, self.vertex_a_y, self.vertex_a_z),
+                Point3::new(self.vertex_b_x, self.vertex_b_y, self.vertex_b_z),
+                Point3::new(self.vertex_c_x, self.vertex_c_y, self.vertex_c_z),
+            ),
+            ColliderType::Heightfield => SharedShape::ball(1.0), // Placeholder
+        }
+    }
+
+    /// Build the Rapier shape for this collider (2D)
+    #[cfg(feature = "dim2")]
+    pub fn shared_shape(&self) -> SharedShape {
+        use nalgebra::Point2;
+
+        match self.collider_type {
+            ColliderType::Ball => SharedShape::ball(self.radius),
+            ColliderType::Cuboid => SharedShape::cuboid(
+                self.half_extent_x,
+                self.half_extent_y,
+            ),
+            ColliderType::Capsule => SharedShape::capsule_y(self.half_height, self.radius),
+            ColliderType::RoundedCuboid => SharedShape::round_cuboid(
+                self.half_extent_x,
+                self.half_extent_y,
+                self.border_radius,
+            ),
+            // 3D-only shapes fallback to ball in 2D
+            ColliderType::Cylinder | ColliderType::Cone | ColliderType::RoundedCylinder => {
+                SharedShape::ball(self.radius)
+            }
+            ColliderType::Triangle => SharedShape::triangle(
+                Point2::new(self.vertex_a_x, self.vertex_a_y),
+                Point2::new(self.vertex_b_x, self.vertex_b_y),
+                Point2::new(self.vertex_c_x, self.vertex_c_y),
+            ),
+            ColliderType::Heightfield => SharedShape::ball(1.0), // Placeholder
+        }
+    }
+
     // 2D factory methods
     #[cfg(feature = "dim2")]
     pub fn cuboid_2d(world_id: u64, half_extents: Vec2) -> Self {
@@ -216,4 +631,120 @@ impl Collider {
             ..Default::default()
         }
     }
+
+    #[cfg(feature = "dim2")]
+    pub fn rounded_cuboid_2d(world_id: u64, half_extents: Vec2, border_radius: f32) -> Self {
+        Self {
+            collider_type: ColliderType::RoundedCuboid,
+            border_radius,
+            ..Self::cuboid_2d(world_id, half_extents)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn unit_sphere_volume_and_area() {
+        let sphere = Collider::ball(1, 1.0);
+        assert_close(sphere.volume(), 4.0 / 3.0 * PI);
+        assert_close(sphere.surface_area(), 4.0 * PI);
+    }
+
+    #[test]
+    fn unit_cube_volume_and_area() {
+        let cube = Collider::cuboid(1, Vec3::new(0.5, 0.5, 0.5));
+        assert_close(cube.volume(), 1.0);
+        assert_close(cube.surface_area(), 6.0);
+    }
+
+    #[test]
+    fn capsule_is_a_cylinder_plus_a_sphere() {
+        let capsule = Collider::capsule(1, 1.0, 0.5);
+        let cylinder = Collider::cylinder(1, 1.0, 0.5);
+        let sphere = Collider::ball(1, 0.5);
+        assert_close(capsule.volume(), cylinder.volume() + sphere.volume());
+    }
+
+    #[test]
+    fn invalid_shape_parameters_are_rejected() {
+        assert!(Collider::ball(1, 0.5).validate().is_ok());
+        assert!(Collider::capsule(1, 0.0, 0.5).validate().is_ok());
+
+        for invalid in [
+            Collider::ball(1, -1.0),
+            Collider::ball(1, f32::NAN),
+            Collider::cuboid(1, Vec3::new(0.5, 0.0, 0.5)),
+            Collider::capsule(1, -0.1, 0.5),
+            Collider::cylinder(1, 1.0, f32::INFINITY),
+        ] {
+            assert!(
+                matches!(invalid.validate(), Err(PhysicsError::InvalidShape { .. })),
+                "{:?} was accepted",
+                invalid.collider_type
+            );
+        }
+    }
+
+    #[test]
+    fn inertia_2d_matches_analytic_moments() {
+        // Disk: m r² / 2
+        assert_close(Collider::ball(1, 3.0).inertia_2d(2.0), 9.0);
+        // Rectangle: m (w² + h²) / 12
+        assert_close(Collider::cuboid(1, Vec3::new(1.0, 2.0, 0.5)).inertia_2d(6.0), 6.0 * (4.0 + 16.0) / 12.0);
+        // A capsule without a straight part is a disk
+        assert_close(Collider::capsule(1, 0.0, 3.0).inertia_2d(2.0), 9.0);
+    }
+
+    #[test]
+    fn rounded_cylinder_is_the_sum_of_its_shells() {
+        let (r, h, b) = (0.5, 2.0, 0.2);
+        let rounded = Collider::rounded_cylinder(1, h / 2.0, r, b);
+
+        // Each quarter-torus edge is a quarter disk (or arc) of radius b swept around the axis,
+        // its centroid is 4b/3π (2b/π for the arc) outside the base radius
+        let edge_volume = PI * b * b / 4.0 * 2.0 * PI * (r + 4.0 * b / (3.0 * PI));
+        let edge_area = PI * b / 2.0 * 2.0 * PI * (r + 2.0 * b / PI);
+
+        let base = PI * r * r * h;
+        let radial_shell = PI * ((r + b) * (r + b) - r * r) * h;
+        let end_caps = 2.0 * PI * r * r * b;
+        assert_close(rounded.volume(), base + radial_shell + end_caps + 2.0 * edge_volume);
+
+        let side = 2.0 * PI * (r + b) * h;
+        let flat_ends = 2.0 * PI * r * r;
+        assert_close(rounded.surface_area(), side + flat_ends + 2.0 * edge_area);
+    }
+
+    #[test]
+    fn rounded_cuboid_aabb_grows_by_the_border_radius() {
+        let half_extents = Vec3::new(1.0, 0.5, 2.0);
+        let base = Collider::cuboid(1, half_extents).shared_shape().compute_local_aabb();
+        let rounded = Collider::rounded_cuboid(1, half_extents, 0.1).shared_shape().compute_local_aabb();
+
+        for axis in 0..base.maxs.len() {
+            assert_close(rounded.maxs[axis], base.maxs[axis] + 0.1);
+            assert_close(rounded.mins[axis], base.mins[axis] - 0.1);
+        }
+    }
+
+    #[test]
+    fn capsule_points_are_at_plus_minus_half_height_on_y() {
+        let capsule = Collider::capsule(1, 0.75, 0.25);
+        let (a, b) = capsule.capsule_points();
+        assert_eq!(a, Vec3::new(0.0, -0.75, 0.0));
+        assert_eq!(b, Vec3::new(0.0, 0.75, 0.0));
+
+        // The shape Rapier collides with has the same segment
+        let shape = capsule.shared_shape();
+        let segment = shape.as_capsule().expect("not a capsule").segment;
+        assert_close(segment.a.y, a.y);
+        assert_close(segment.b.y, b.y);
+    }
 }
@@ -0,0 +1,218 @@
+//! CollisionEvent table - per-pair contact phases between rigid bodies
+
+use std::collections::{HashMap, HashSet};
+
+use spacetimedb::{table, ReducerContext, SpacetimeType, Table};
+
+pub type CollisionEventId = u64;
+
+/// Phase of a contact between two rigid bodies
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionPhase {
+    /// The bodies started touching this tick
+    Started,
+    /// The bodies were already touching last tick and still are
+    Persisted,
+    /// The bodies stopped touching this tick
+    Stopped,
+}
+
+impl CollisionPhase {
+    /// Phase of a pair this tick, from its phase last tick (`None` if it had no event)
+    /// and whether it is touching now. `None` means the event is removed.
+    pub fn advance(previous: Option<Self>, touching: bool) -> Option<Self> {
+        match (previous, touching) {
+            // A pair that stopped last tick and touches again starts over
+            (None, true) | (Some(Self::Stopped), true) => Some(Self::Started),
+            (Some(_), true) => Some(Self::Persisted),
+            (None, false) | (Some(Self::Stopped), false) => None,
+            (Some(_), false) => Some(Self::Stopped),
+        }
+    }
+}
+
+/// A contact between two rigid bodies, tracked across ticks
+///
+/// There is at most one row per body pair. A pair goes through
+/// `Started` -> `Persisted` -> `Stopped`, and the row is removed on the
+/// tick after it was reported as `Stopped`.
+#[table(name = rapier_collision_event, public)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionEvent {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+
+    /// Which physics world this event belongs to
+    #[index(btree)]
+    pub world_id: u64,
+
+    /// ID of the first rigid body (always the lower of the two IDs)
+    pub body_a_id: u64,
+
+    /// ID of the second rigid body (always the higher of the two IDs)
+    pub body_b_id: u64,
+
+    /// Current phase of this contact
+    pub phase: CollisionPhase,
+
+    /// `material_id` of body A's properties (0 if it has none)
+    pub material_a_id: u32,
+
+    /// `material_id` of body B's properties (0 if it has none)
+    pub material_b_id: u32,
+}
+
+impl CollisionEvent {
+    /// Insert this event into the database
+    pub fn insert(self, ctx: &ReducerContext) -> Self {
+        ctx.db.rapier_collision_event().insert(self)
+    }
+
+    /// Find an event by ID
+    pub fn find(ctx: &ReducerContext, id: CollisionEventId) -> Option<Self> {
+        ctx.db.rapier_collision_event().id().find(id)
+    }
+
+    /// Get all events in a world
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.rapier_collision_event().world_id().filter(world_id)
+    }
+
+    /// Get all events in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    /// Update this event in the database
+    pub fn update(self, ctx: &ReducerContext) -> Self {
+        ctx.db.rapier_collision_event().id().update(self)
+    }
+
+    /// Delete this event from the database
+    pub fn delete(&self, ctx: &ReducerContext) {
+        ctx.db.rapier_collision_event().id().delete(self.id);
+    }
+
+    /// Check if this event involves the given body
+    pub fn involves(&self, body_id: u64) -> bool {
+        self.body_a_id == body_id || self.body_b_id == body_id
+    }
+
+    /// Get the other body of the pair, if `body_id` is part of it
+    pub fn other(&self, body_id: u64) -> Option<u64> {
+        if self.body_a_id == body_id {
+            Some(self.body_b_id)
+        } else if self.body_b_id == body_id {
+            Some(self.body_a_id)
+        } else {
+            None
+        }
+    }
+
+    /// Check if the contact started this tick
+    pub fn is_started(&self) -> bool {
+        self.phase == CollisionPhase::Started
+    }
+
+    /// Check if the contact stopped this tick
+    pub fn is_stopped(&self) -> bool {
+        self.phase == CollisionPhase::Stopped
+    }
+
+    /// Diff the current contact pairs against the previous tick's events
+    ///
+    /// Pairs are `(lower_id, higher_id)`. New pairs are inserted as `Started`,
+    /// pairs still touching become `Persisted`, pairs no longer touching become
+    /// `Stopped`, and pairs reported as `Stopped` last tick are removed.
+    /// `materials` maps body IDs to their material, used for new events.
+    pub fn sync_world(
+        ctx: &ReducerContext,
+        world_id: u64,
+        current_pairs: &HashSet<(u64, u64)>,
+        materials: &HashMap<u64, u32>,
+    ) {
+        let previous: Vec<_> = Self::all_in_world(ctx, world_id).collect();
+        let mut seen: HashSet<(u64, u64)> = HashSet::with_capacity(previous.len());
+
+        for event in previous {
+            let pair = (event.body_a_id, event.body_b_id);
+            let touching = current_pairs.contains(&pair);
+            if touching {
+                seen.insert(pair);
+            }
+
+            match CollisionPhase::advance(Some(event.phase), touching) {
+                Some(phase) if phase != event.phase => {
+                    Self { phase, ..event }.update(ctx);
+                }
+                Some(_) => {}
+                None => event.delete(ctx),
+            }
+        }
+
+        for &(body_a_id, body_b_id) in current_pairs {
+            if seen.contains(&(body_a_id, body_b_id)) {
+                continue;
+            }
+
+            Self::started(world_id, (body_a_id, body_b_id), materials).insert(ctx);
+        }
+    }
+
+    /// A new event for a pair that just started touching
+    ///
+    /// `materials` maps body IDs to their material, bodies missing from it get 0.
+    pub fn started(world_id: u64, (body_a_id, body_b_id): (u64, u64), materials: &HashMap<u64, u32>) -> Self {
+        Self {
+            id: 0,
+            world_id,
+            body_a_id,
+            body_b_id,
+            phase: CollisionPhase::Started,
+            material_a_id: materials.get(&body_a_id).copied().unwrap_or(0),
+            material_b_id: materials.get(&body_b_id).copied().unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CollisionPhase::{self, *};
+
+    /// Feeds `touching` tick by tick, returns the phase reported after each tick.
+    fn phases(touching: &[bool]) -> Vec<Option<CollisionPhase>> {
+        let mut phase = None;
+        touching
+            .iter()
+            .map(|&touching| {
+                phase = CollisionPhase::advance(phase, touching);
+                phase
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resting_contact_starts_once_then_persists() {
+        assert_eq!(
+            phases(&[true, true, true, true]),
+            vec![Some(Started), Some(Persisted), Some(Persisted), Some(Persisted)]
+        );
+    }
+
+    #[test]
+    fn separated_pair_stops_then_is_removed() {
+        assert_eq!(
+            phases(&[true, true, false, false]),
+            vec![Some(Started), Some(Persisted), Some(Stopped), None]
+        );
+    }
+
+    #[test]
+    fn pair_touching_again_after_stopping_starts_over() {
+        assert_eq!(
+            phases(&[true, false, true, true]),
+            vec![Some(Started), Some(Stopped), Some(Started), Some(Persisted)]
+        );
+    }
+}
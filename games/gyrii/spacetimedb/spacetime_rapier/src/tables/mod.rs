@@ -10,6 +10,9 @@ mod collider;
 mod properties;
 mod trigger;
 mod raycast;
+mod collision_event;
+mod sensor_overlap;
+mod portal;
 
 pub use physics_world::*;
 pub use rigid_body::*;
@@ -17,3 +20,6 @@ pub use collider::*;
 pub use properties::*;
 pub use trigger::*;
 pub use raycast::*;
+pub use collision_event::*;
+pub use sensor_overlap::*;
+pub use portal::*;
@@ -1,10 +1,27 @@
 //! PhysicsWorld table - configuration for a physics simulation
 
+use std::collections::{HashMap, HashSet};
+
 use bon::Builder;
 use spacetimedb::{table, ReducerContext, Table};
+use crate::engine::WorldRows;
+use crate::math::Vec3;
+use crate::tables::{
+    Collider, ColliderId, CollisionEvent, PortalEvent, RayCast, RigidBody, RigidBodyProperties, RigidBodyType,
+    SensorOverlap, Trigger,
+};
+use crate::PhysicsError;
 
 pub type PhysicsWorldId = u64;
 
+/// Dimension of the compiled physics backend
+#[cfg(feature = "dim2")]
+pub const COMPILED_DIMENSION: u8 = 2;
+
+/// Dimension of the compiled physics backend
+#[cfg(feature = "dim3")]
+pub const COMPILED_DIMENSION: u8 = 3;
+
 /// Physics world configuration
 ///
 /// Each world is an isolated physics simulation with its own gravity,
@@ -18,6 +35,13 @@ pub struct PhysicsWorld {
     #[builder(default = 0)]
     pub id: u64,
 
+    /// Dimension this world was created for (2 or 3)
+    ///
+    /// Stamped at creation and checked by `step_world`, so a world
+    /// stepped by a build with the other dimension feature is rejected.
+    #[builder(name = dim, default = COMPILED_DIMENSION)]
+    pub dimension: u8,
+
     /// Physics updates per second (default: 60)
     #[builder(default = 60.0)]
     pub ticks_per_second: f32,
@@ -46,6 +70,69 @@ pub struct PhysicsWorld {
     #[builder(default = 1)]
     pub num_internal_pgs_iterations: u32,
 
+    /// Contacts with a normal velocity below this value don't bounce. The solver never uses less
+    /// than `2 * |gravity| * sub_dt`, so resting bodies don't jitter.
+    ///
+    /// In meters per second. Rapier substeps once per solver iteration, so `sub_dt` is the
+    /// timestep over `num_solver_iterations`, see `restitution_threshold`.
+    #[builder(default = 0.0)]
+    pub restitution_velocity_threshold: f32,
+
+    /// Let kinematic bodies push each other apart (each moves half the overlap)
+    ///
+    /// Rapier never resolves kinematic-kinematic contacts, so without this
+    /// two kinematic players pass through each other.
+    #[builder(default = false)]
+    pub kinematic_vs_kinematic: bool,
+
+    /// Move kinematic bodies from their stored pose to the pose given to
+    /// `step_world` over the step, instead of teleporting them
+    ///
+    /// Rapier derives a velocity from the move, so kinematic players push
+    /// dynamic bodies smoothly and sudden input changes don't snap them.
+    #[builder(default = false)]
+    pub kinematic_interpolation: bool,
+
+    /// Load and evaluate triggers during `step_world`
+    ///
+    /// Worlds without triggers can turn this off to skip loading the table.
+    /// Trigger rows are left untouched while it's off.
+    #[builder(default = true)]
+    pub process_triggers: bool,
+
+    /// Linear damping added to every body's own `linear_damping`
+    ///
+    /// A whole-level drag, e.g. underwater or in a thick atmosphere.
+    #[builder(default = 0.0)]
+    pub global_linear_damping: f32,
+
+    /// Angular damping added to every body's own `angular_damping`
+    #[builder(default = 0.0)]
+    pub global_angular_damping: f32,
+
+    /// Skip writing back bodies at rest: both velocities at most this and a
+    /// pose and velocities that changed less than this during the step
+    /// (0 = always write back)
+    ///
+    /// Saves row updates and replication traffic for piles of resting bodies.
+    /// Bodies moving faster than this are always written, however little
+    /// they moved in one step.
+    #[builder(default = 0.0)]
+    pub write_back_epsilon: f32,
+
+    /// Snap Z position and velocity to zero when loading bodies (2D only)
+    ///
+    /// Useful when bodies are authored with 3D data and may carry a stray Z.
+    #[builder(default = false)]
+    pub zero_z_on_load: bool,
+
+    /// Number of completed steps, incremented at the end of every `step_world`
+    ///
+    /// A monotonic clock for gameplay timers, unlike timestamps it doesn't
+    /// drift when the server stalls.
+    #[builder(default = 0)]
+    pub tick: u64,
+
     /// Enable debug logging
     #[builder(default = false)]
     pub debug: bool,
@@ -53,10 +140,40 @@ pub struct PhysicsWorld {
 
 impl PhysicsWorld {
     /// Insert this world into the database
+    ///
+    /// Invalid parameters are logged but still inserted, use `try_insert`
+    /// to reject them instead.
     pub fn insert(self, ctx: &ReducerContext) -> Self {
+        if let Err(err) = self.validate() {
+            log::warn!("PhysicsWorld::insert: {}", err);
+        }
         ctx.db.rapier_physics_world().insert(self)
     }
 
+    /// Insert this world into the database if its parameters are valid
+    pub fn try_insert(self, ctx: &ReducerContext) -> Result<Self, PhysicsError> {
+        self.validate()?;
+        Ok(ctx.db.rapier_physics_world().insert(self))
+    }
+
+    /// Check that the world's parameters can be simulated
+    pub fn validate(&self) -> Result<(), PhysicsError> {
+        let invalid = |reason: String| PhysicsError::InvalidWorldParameter { world_id: self.id, reason };
+        if !(self.ticks_per_second.is_finite() && self.ticks_per_second > 0.0) {
+            return Err(invalid(format!("ticks_per_second must be positive, got {}", self.ticks_per_second)));
+        }
+        if !self.has_valid_gravity() {
+            return Err(PhysicsError::NonFiniteGravity(self.id));
+        }
+        if !(self.restitution_velocity_threshold.is_finite() && self.restitution_velocity_threshold >= 0.0) {
+            return Err(invalid(format!(
+                "restitution_velocity_threshold must not be negative, got {}",
+                self.restitution_velocity_threshold
+            )));
+        }
+        Ok(())
+    }
+
     /// Find a world by ID
     pub fn find(ctx: &ReducerContext, id: PhysicsWorldId) -> Option<Self> {
         ctx.db.rapier_physics_world().id().find(id)
@@ -72,11 +189,206 @@ impl PhysicsWorld {
         ctx.db.rapier_physics_world().id().delete(self.id);
     }
 
+    /// Copy a template world into a new one, e.g. one per match
+    ///
+    /// Copies the world settings (with the tick reset to 0), its static bodies
+    /// and triggers, and the colliders and properties they use. References
+    /// are remapped to the new rows, so the copy is isolated from the source.
+    /// Dynamic and kinematic bodies, raycasts and portals are not copied, and
+    /// trigger contents start empty.
+    pub fn duplicate(ctx: &ReducerContext, source_world_id: PhysicsWorldId) -> Result<Self, PhysicsError> {
+        let source = Self::find(ctx, source_world_id).ok_or(PhysicsError::WorldNotFound(source_world_id))?;
+        let world = Self { id: 0, tick: 0, ..source }.insert(ctx);
+
+        let rows = WorldRows {
+            bodies: RigidBody::collect_in_world(ctx, source.id),
+            colliders: Collider::collect_in_world(ctx, source.id),
+            properties: RigidBodyProperties::collect_in_world(ctx, source.id),
+            triggers: Trigger::collect_in_world(ctx, source.id),
+            ..Default::default()
+        };
+        Self::duplicate_rows_with(
+            world.id,
+            rows,
+            |collider| collider.insert(ctx).id,
+            |props| props.insert(ctx).id,
+            |body| {
+                body.insert(ctx);
+            },
+            |trigger| {
+                trigger.insert(ctx);
+            },
+        );
+
+        Ok(world)
+    }
+
+    /// Copy the rows `duplicate` keeps from a source world into `world_id`
+    ///
+    /// Each `insert_*` inserts a copy, the collider and properties ones
+    /// return the ID it was given.
+    pub(crate) fn duplicate_rows_with(
+        world_id: PhysicsWorldId,
+        rows: WorldRows,
+        mut insert_collider: impl FnMut(Collider) -> ColliderId,
+        mut insert_properties: impl FnMut(RigidBodyProperties) -> u64,
+        mut insert_body: impl FnMut(RigidBody),
+        mut insert_trigger: impl FnMut(Trigger),
+    ) {
+        let bodies: Vec<_> = RigidBody::all_of_type_in(rows.bodies, RigidBodyType::Static).collect();
+        let triggers = rows.triggers;
+
+        // Only copy the shapes and properties the copied rows use
+        let used_colliders: HashSet<ColliderId> = bodies
+            .iter()
+            .map(|body| body.collider_id)
+            .chain(triggers.iter().map(|trigger| trigger.collider_id))
+            .collect();
+        let used_properties: HashSet<u64> = bodies.iter().map(|body| body.properties_id).collect();
+
+        let colliders: HashMap<ColliderId, ColliderId> = rows
+            .colliders
+            .into_iter()
+            .filter(|collider| used_colliders.contains(&collider.id))
+            .map(|collider| (collider.id, insert_collider(Collider { id: 0, world_id, ..collider })))
+            .collect();
+        let properties: HashMap<u64, u64> = rows
+            .properties
+            .into_iter()
+            .filter(|props| used_properties.contains(&props.id))
+            .map(|props| (props.id, insert_properties(RigidBodyProperties { id: 0, world_id, ..props })))
+            .collect();
+
+        // 0 (none) and references to missing rows both map to none
+        let remap = |ids: &HashMap<u64, u64>, id: u64| ids.get(&id).copied().unwrap_or(0);
+        for body in bodies {
+            insert_body(RigidBody {
+                id: 0,
+                world_id,
+                collider_id: remap(&colliders, body.collider_id),
+                properties_id: remap(&properties, body.properties_id),
+                ..body
+            });
+        }
+        for trigger in triggers {
+            insert_trigger(Trigger {
+                id: 0,
+                world_id,
+                collider_id: remap(&colliders, trigger.collider_id),
+                entities_inside: Vec::new(),
+                added_entities: Vec::new(),
+                removed_entities: Vec::new(),
+                ..trigger
+            });
+        }
+    }
+
+    /// Get the current tick of a world, as stored in the database
+    ///
+    /// Prefer this over the `tick` of a copy taken before `step_world`,
+    /// which is one step behind.
+    pub fn current_tick(ctx: &ReducerContext, id: PhysicsWorldId) -> Option<u64> {
+        Self::find(ctx, id).map(|world| world.tick)
+    }
+
+    /// Count one more completed step and return the new tick
+    ///
+    /// `step_world` calls this on the stored row, wrapping around at `u64::MAX`.
+    pub fn advance_tick(&mut self) -> u64 {
+        self.tick = self.tick.wrapping_add(1);
+        self.tick
+    }
+
+    /// Check if every moving body has settled
+    ///
+    /// True when each enabled, non-frozen dynamic body's linear speed is at most
+    /// `linear_eps` and its angular speed at most `angular_eps`. Useful for
+    /// turn-based games that wait for the table to settle before the next turn.
+    pub fn is_at_rest(&self, ctx: &ReducerContext, linear_eps: f32, angular_eps: f32) -> bool {
+        Self::is_at_rest_in(RigidBody::all_in_world(ctx, self.id), linear_eps, angular_eps)
+    }
+
+    /// Same as `is_at_rest`, over already loaded bodies
+    pub fn is_at_rest_in(bodies: impl IntoIterator<Item = RigidBody>, linear_eps: f32, angular_eps: f32) -> bool {
+        bodies
+            .into_iter()
+            .filter(|body| body.enabled && !body.frozen && body.is_dynamic())
+            .all(|body| {
+                body.linear_velocity().length() <= linear_eps
+                    && body.angular_velocity().length() <= angular_eps
+            })
+    }
+
+    /// Bring the world to rest without touching its geometry
+    ///
+    /// Zeroes the velocities of every body, empties triggers, sensor overlaps
+    /// and raycast hits, and deletes pending collision and portal events.
+    /// Positions, colliders and properties are kept.
+    pub fn reset(&self, ctx: &ReducerContext) {
+        let mut bodies: Vec<_> = RigidBody::all_in_world(ctx, self.id).collect();
+        let mut triggers: Vec<_> = Trigger::all_in_world(ctx, self.id).collect();
+        let mut raycasts: Vec<_> = RayCast::all_in_world(ctx, self.id).collect();
+        reset_rows(&mut bodies, &mut triggers, &mut raycasts);
+        for body in bodies {
+            body.update(ctx);
+        }
+        for trigger in triggers {
+            trigger.update(ctx);
+        }
+        for raycast in raycasts {
+            raycast.update(ctx);
+        }
+
+        for mut overlap in SensorOverlap::all_in_world(ctx, self.id).collect::<Vec<_>>() {
+            overlap.entities_inside.clear();
+            overlap.added_entities.clear();
+            overlap.removed_entities.clear();
+            overlap.update(ctx);
+        }
+
+        for event in CollisionEvent::all_in_world(ctx, self.id).collect::<Vec<_>>() {
+            event.delete(ctx);
+        }
+
+        for event in PortalEvent::all_in_world(ctx, self.id).collect::<Vec<_>>() {
+            event.delete(ctx);
+        }
+    }
+
+    /// Check if this world's dimension matches the compiled backend
+    pub fn matches_compiled_dimension(&self) -> bool {
+        self.dimension == COMPILED_DIMENSION
+    }
+
     /// Get the timestep duration in seconds
     pub fn timestep(&self) -> f32 {
         1.0 / self.ticks_per_second
     }
 
+    /// The speed below which contacts don't bounce, `restitution_velocity_threshold`
+    /// raised to the `2 * |gravity| * sub_dt` floor
+    pub fn restitution_threshold(&self) -> f32 {
+        let sub_dt = self.timestep() / self.num_solver_iterations.max(1) as f32;
+        self.restitution_velocity_threshold.max(2.0 * self.gravity_vector().norm() * sub_dt)
+    }
+
+    /// Get gravity as a Vec3 (Z is ignored in 2D)
+    pub fn gravity(&self) -> Vec3 {
+        Vec3::new(self.gravity_x, self.gravity_y, self.gravity_z)
+    }
+
+    /// Set gravity from a Vec3 (Z is ignored in 2D)
+    pub fn set_gravity(&mut self, gravity: Vec3) {
+        self.gravity_x = gravity.x;
+        self.gravity_y = gravity.y;
+        self.gravity_z = gravity.z;
+    }
+
+    /// Check that every gravity component is a finite number
+    pub fn has_valid_gravity(&self) -> bool {
+        self.gravity_x.is_finite() && self.gravity_y.is_finite() && self.gravity_z.is_finite()
+    }
+
     /// Get gravity as a 3D vector (for Rapier3D)
     #[cfg(feature = "dim3")]
     pub fn gravity_vector(&self) -> nalgebra::Vector3<f32> {
@@ -89,3 +401,178 @@ impl PhysicsWorld {
         nalgebra::Vector2::new(self.gravity_x, self.gravity_y)
     }
 }
+
+/// Clear the per-step state `PhysicsWorld::reset` clears, on rows already loaded
+pub(crate) fn reset_rows(bodies: &mut [RigidBody], triggers: &mut [Trigger], raycasts: &mut [RayCast]) {
+    for body in bodies {
+        body.set_linear_velocity(Vec3::ZERO);
+        body.set_angular_velocity(Vec3::ZERO);
+    }
+    for trigger in triggers {
+        trigger.entities_inside.clear();
+        trigger.clear_events();
+    }
+    for raycast in raycasts {
+        raycast.hits.clear();
+        raycast.clear_events();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::test_utils::TestWorld;
+
+    #[test]
+    fn gravity_y_sets_only_the_y_component() {
+        let world = PhysicsWorld::builder().gravity_y(-9.81).build();
+        assert_eq!(world.gravity(), Vec3::new(0.0, -9.81, 0.0));
+    }
+
+    #[test]
+    fn stepping_n_times_advances_the_tick_by_n() {
+        let mut world = TestWorld::with_defaults();
+        world.add_body(RigidBody { position_y: 3.0, ..Default::default() }, Collider::ball(1, 0.5));
+
+        world.step_n(7);
+        assert_eq!(world.world.tick, 7);
+        world.step();
+        assert_eq!(world.world.tick, 8);
+
+        let mut last = PhysicsWorld::builder().tick(u64::MAX).build();
+        assert_eq!(last.advance_tick(), 0);
+    }
+
+    #[test]
+    fn invalid_world_parameters_are_rejected() {
+        assert_eq!(PhysicsWorld::builder().build().validate(), Ok(()));
+        let invalid = |world: PhysicsWorld| matches!(world.validate(), Err(PhysicsError::InvalidWorldParameter { .. }));
+        assert!(invalid(PhysicsWorld::builder().ticks_per_second(0.0).build()));
+        assert!(invalid(PhysicsWorld::builder().ticks_per_second(-60.0).build()));
+        assert!(invalid(PhysicsWorld::builder().restitution_velocity_threshold(-1.0).build()));
+        let floating = PhysicsWorld::builder().id(3).gravity_y(f32::NAN).build();
+        assert_eq!(floating.validate(), Err(PhysicsError::NonFiniteGravity(3)));
+    }
+
+    #[test]
+    fn restitution_threshold_never_drops_below_the_gravity_floor() {
+        // 2 * 12 m/s² * (1/60 s / 4 iterations) = 0.1 m/s
+        let world = |threshold| {
+            PhysicsWorld::builder()
+                .gravity_y(-12.0)
+                .num_solver_iterations(4)
+                .restitution_velocity_threshold(threshold)
+                .build()
+        };
+        assert!((world(0.0).restitution_threshold() - 0.1).abs() < 1e-6);
+        assert_eq!(world(2.0).restitution_threshold(), 2.0);
+        let weightless = PhysicsWorld::builder().gravity_y(0.0).build();
+        assert_eq!(weightless.restitution_threshold(), 0.0);
+    }
+
+    #[test]
+    fn reset_brings_bodies_to_rest_and_empties_triggers() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let trigger = world.add_trigger(Trigger::default(), Collider::ball(1, 2.0));
+        let ray = world.add_raycast(RayCast { origin_y: 5.0, ..Default::default() });
+        world.add_body(
+            RigidBody { linear_velocity_x: 0.5, angular_velocity_z: 2.0, ..Default::default() },
+            Collider::ball(1, 0.5),
+        );
+        world.step();
+        assert!(!world.trigger(trigger).entities_inside.is_empty());
+        assert!(!world.raycast(ray).hits.is_empty());
+
+        let rows = &mut world.rows;
+        reset_rows(&mut rows.bodies, &mut rows.triggers, &mut rows.raycasts);
+
+        for body in &world.rows.bodies {
+            assert_eq!((body.linear_velocity(), body.angular_velocity()), (Vec3::ZERO, Vec3::ZERO));
+        }
+        let trigger = world.trigger(trigger);
+        assert!(trigger.entities_inside.is_empty() && trigger.added_entities.is_empty());
+        let ray = world.raycast(ray);
+        assert!(ray.hits.is_empty() && ray.added_hits.is_empty());
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn world_is_at_rest_only_once_the_rolling_ball_stopped() {
+        let mut world = TestWorld::with_defaults();
+        let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+        world.add_body(floor, Collider::cuboid(1, Vec3::new(50.0, 0.5, 50.0)));
+        let damped = RigidBodyProperties { linear_damping: 2.0, angular_damping: 2.0, ..Default::default() };
+        let ball =
+            RigidBody { position_y: 0.5, linear_velocity_x: 3.0, angular_velocity_z: -6.0, ..Default::default() };
+        world.add_body_with(ball, Collider::ball(1, 0.5), damped);
+        let at_rest = |world: &TestWorld| PhysicsWorld::is_at_rest_in(world.rows.bodies.clone(), 0.01, 0.01);
+
+        world.step();
+        assert!(!at_rest(&world));
+
+        world.step_n(600);
+        assert!(at_rest(&world));
+    }
+
+    #[test]
+    fn duplicate_copies_the_static_geometry_into_an_isolated_world() {
+        let mut template = TestWorld::with_defaults();
+        let floor = RigidBody { position_y: -0.5, body_type: RigidBodyType::Static, ..Default::default() };
+        let floor = template.add_body(floor, Collider::cuboid(1, Vec3::new(50.0, 0.5, 50.0)));
+        let ball = template.add_body(RigidBody { position_y: 2.0, ..Default::default() }, Collider::ball(1, 0.5));
+        // Above the floor, around the resting ball
+        let goal = Trigger { position_y: 1.0, ..Default::default() };
+        let goal = template.add_trigger(goal, Collider::cuboid(1, Vec3::new(1.0, 0.4, 1.0)));
+        template.step_n(60);
+        assert_eq!(template.trigger(goal).entities_inside, vec![ball]);
+
+        // Fresh IDs past the template's, like in a shared table
+        let next_id = std::cell::Cell::new(1000);
+        let new_id = || {
+            next_id.set(next_id.get() + 1);
+            next_id.get()
+        };
+        let mut copy = WorldRows::default();
+        let (mut bodies, mut triggers) = (Vec::new(), Vec::new());
+        PhysicsWorld::duplicate_rows_with(
+            2,
+            template.rows.clone(),
+            |collider| {
+                copy.colliders.push(Collider { id: new_id(), ..collider });
+                next_id.get()
+            },
+            |props| {
+                copy.properties.push(RigidBodyProperties { id: new_id(), ..props });
+                next_id.get()
+            },
+            |body| bodies.push(RigidBody { id: new_id(), ..body }),
+            |trigger| triggers.push(Trigger { id: new_id(), ..trigger }),
+        );
+        copy.bodies = bodies;
+        copy.triggers = triggers;
+
+        // Same geometry: the floor and the goal only, with their poses and shapes
+        let shape = |rows: &WorldRows, id: ColliderId| {
+            let collider = rows.colliders.iter().find(|collider| collider.id == id).expect("collider not found");
+            Collider { id: 0, world_id: 0, ..*collider }
+        };
+        let source_floor = template.body(floor);
+        let [copied_floor] = copy.bodies.as_slice() else { panic!("copied bodies {:?}", copy.bodies) };
+        assert_eq!(copied_floor.position(), source_floor.position());
+        assert_eq!(shape(&copy, copied_floor.collider_id), shape(&template.rows, source_floor.collider_id));
+        let source_goal = template.trigger(goal);
+        let [copied_goal] = copy.triggers.as_slice() else { panic!("copied triggers {:?}", copy.triggers) };
+        assert_eq!(copied_goal.position_y, source_goal.position_y);
+        assert_eq!(shape(&copy, copied_goal.collider_id), shape(&template.rows, source_goal.collider_id));
+        assert!(copied_goal.entities_inside.is_empty());
+
+        // Isolated: every row is in world 2 and only references rows of the copy
+        assert!(copy.bodies.iter().all(|body| body.world_id == 2 && body.id > 1000));
+        assert!(copy.triggers.iter().all(|trigger| trigger.world_id == 2 && trigger.id > 1000));
+        assert!(copy.colliders.iter().all(|collider| collider.world_id == 2));
+        assert_eq!(copy.colliders.len(), 2);
+        let [copied_properties] = copy.properties.as_slice() else { panic!("copied {:?}", copy.properties) };
+        assert_eq!(copied_properties.world_id, 2);
+        assert_eq!(copied_floor.properties_id, copied_properties.id);
+    }
+}
@@ -0,0 +1,228 @@
+//! Portal tables - triggers that report a teleport destination on entry
+
+use bon::Builder;
+use spacetimedb::{table, ReducerContext, Table};
+use crate::math::{Vec3, Quat};
+
+pub type PortalTriggerId = u64;
+pub type PortalEventId = u64;
+
+/// Turns a `Trigger` into a portal
+///
+/// Every body entering the trigger gets a `PortalEvent` carrying this
+/// destination. Moving the body is left to the game.
+#[table(name = rapier_portal_trigger, public)]
+#[derive(Builder, Clone, Copy, Debug, PartialEq)]
+#[builder(derive(Debug, Clone))]
+pub struct PortalTrigger {
+    #[primary_key]
+    #[auto_inc]
+    #[builder(default = 0)]
+    pub id: u64,
+
+    /// Which physics world this portal belongs to
+    #[index(btree)]
+    #[builder(default = 1)]
+    pub world_id: u64,
+
+    /// ID of the trigger acting as the portal's entrance
+    #[unique]
+    pub trigger_id: u64,
+
+    // Destination position
+    #[builder(default = 0.0)]
+    pub destination_position_x: f32,
+    #[builder(default = 0.0)]
+    pub destination_position_y: f32,
+    #[builder(default = 0.0)]
+    pub destination_position_z: f32,
+
+    // Destination rotation (quaternion)
+    #[builder(default = 0.0)]
+    pub destination_rotation_x: f32,
+    #[builder(default = 0.0)]
+    pub destination_rotation_y: f32,
+    #[builder(default = 0.0)]
+    pub destination_rotation_z: f32,
+    #[builder(default = 1.0)]
+    pub destination_rotation_w: f32,
+}
+
+impl PortalTrigger {
+    /// Create a portal for a trigger
+    pub fn new(world_id: u64, trigger_id: u64, destination_position: Vec3, destination_rotation: Quat) -> Self {
+        Self {
+            id: 0,
+            world_id,
+            trigger_id,
+            destination_position_x: destination_position.x,
+            destination_position_y: destination_position.y,
+            destination_position_z: destination_position.z,
+            destination_rotation_x: destination_rotation.x,
+            destination_rotation_y: destination_rotation.y,
+            destination_rotation_z: destination_rotation.z,
+            destination_rotation_w: destination_rotation.w,
+        }
+    }
+
+    /// Insert this portal into the database
+    pub fn insert(self, ctx: &ReducerContext) -> Self {
+        ctx.db.rapier_portal_trigger().insert(self)
+    }
+
+    /// Find a portal by ID
+    pub fn find(ctx: &ReducerContext, id: PortalTriggerId) -> Option<Self> {
+        ctx.db.rapier_portal_trigger().id().find(id)
+    }
+
+    /// Find the portal of a trigger
+    pub fn find_by_trigger(ctx: &ReducerContext, trigger_id: u64) -> Option<Self> {
+        ctx.db.rapier_portal_trigger().trigger_id().find(trigger_id)
+    }
+
+    /// Get all portals in a world
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.rapier_portal_trigger().world_id().filter(world_id)
+    }
+
+    /// Get all portals in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    /// Update this portal in the database
+    pub fn update(self, ctx: &ReducerContext) -> Self {
+        ctx.db.rapier_portal_trigger().id().update(self)
+    }
+
+    /// Delete this portal from the database
+    pub fn delete(&self, ctx: &ReducerContext) {
+        ctx.db.rapier_portal_trigger().id().delete(self.id);
+    }
+
+    /// Get destination position as Vec3
+    pub fn destination_position(&self) -> Vec3 {
+        Vec3::new(
+            self.destination_position_x,
+            self.destination_position_y,
+            self.destination_position_z,
+        )
+    }
+
+    /// Get destination rotation as Quat
+    pub fn destination_rotation(&self) -> Quat {
+        Quat::new(
+            self.destination_rotation_x,
+            self.destination_rotation_y,
+            self.destination_rotation_z,
+            self.destination_rotation_w,
+        )
+    }
+
+    /// Set the destination
+    pub fn set_destination(&mut self, position: Vec3, rotation: Quat) {
+        self.destination_position_x = position.x;
+        self.destination_position_y = position.y;
+        self.destination_position_z = position.z;
+        self.destination_rotation_x = rotation.x;
+        self.destination_rotation_y = rotation.y;
+        self.destination_rotation_z = rotation.z;
+        self.destination_rotation_w = rotation.w;
+    }
+}
+
+/// A body entered a portal this tick
+///
+/// Rows are inserted by `step_world` and kept until the game deletes them,
+/// typically right after teleporting the body.
+#[table(name = rapier_portal_event, public)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PortalEvent {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+
+    /// Which physics world this event belongs to
+    #[index(btree)]
+    pub world_id: u64,
+
+    /// ID of the portal that was entered
+    pub portal_id: u64,
+
+    /// ID of the rigid body that entered the portal
+    pub body_id: u64,
+
+    // Destination position, copied from the portal when the body entered
+    pub destination_position_x: f32,
+    pub destination_position_y: f32,
+    pub destination_position_z: f32,
+
+    // Destination rotation (quaternion)
+    pub destination_rotation_x: f32,
+    pub destination_rotation_y: f32,
+    pub destination_rotation_z: f32,
+    pub destination_rotation_w: f32,
+}
+
+impl PortalEvent {
+    /// Create an event for a body entering a portal
+    pub fn new(portal: &PortalTrigger, body_id: u64) -> Self {
+        Self {
+            id: 0,
+            world_id: portal.world_id,
+            portal_id: portal.id,
+            body_id,
+            destination_position_x: portal.destination_position_x,
+            destination_position_y: portal.destination_position_y,
+            destination_position_z: portal.destination_position_z,
+            destination_rotation_x: portal.destination_rotation_x,
+            destination_rotation_y: portal.destination_rotation_y,
+            destination_rotation_z: portal.destination_rotation_z,
+            destination_rotation_w: portal.destination_rotation_w,
+        }
+    }
+
+    /// Insert this event into the database
+    pub fn insert(self, ctx: &ReducerContext) -> Self {
+        ctx.db.rapier_portal_event().insert(self)
+    }
+
+    /// Find an event by ID
+    pub fn find(ctx: &ReducerContext, id: PortalEventId) -> Option<Self> {
+        ctx.db.rapier_portal_event().id().find(id)
+    }
+
+    /// Get all events in a world
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.rapier_portal_event().world_id().filter(world_id)
+    }
+
+    /// Get all events in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    /// Delete this event from the database
+    pub fn delete(&self, ctx: &ReducerContext) {
+        ctx.db.rapier_portal_event().id().delete(self.id);
+    }
+
+    /// Get destination position as Vec3
+    pub fn destination_position(&self) -> Vec3 {
+        Vec3::new(
+            self.destination_position_x,
+            self.destination_position_y,
+            self.destination_position_z,
+        )
+    }
+
+    /// Get destination rotation as Quat
+    pub fn destination_rotation(&self) -> Quat {
+        Quat::new(
+            self.destination_rotation_x,
+            self.destination_rotation_y,
+            self.destination_rotation_z,
+            self.destination_rotation_w,
+        )
+    }
+}
@@ -42,6 +42,21 @@ pub struct RigidBodyProperties {
     #[builder(default = 0.0)]
     pub angular_damping: f32,
 
+    /// Extra linear damping applied while the body stands on something
+    ///
+    /// Only slows the motion along the ground, so a mover stops quickly once
+    /// landed but keeps gliding in the air and falls at the usual speed.
+    #[builder(default = 0.0)]
+    pub grounded_linear_damping: f32,
+
+    /// Damping of the rolling motion while the body stands on something
+    ///
+    /// Rapier has no rolling friction, so a ball rolls forever. This slows
+    /// its spin and its speed along the ground together until it stops.
+    /// Meant for balls and cylinders, higher values stop them sooner.
+    #[builder(default = 0.0)]
+    pub rolling_resistance: f32,
+
     /// Density (used if mass is not specified directly)
     #[builder(default = 1.0)]
     pub density: f32,
@@ -49,6 +64,10 @@ pub struct RigidBodyProperties {
     /// Whether continuous collision detection is enabled
     #[builder(default = false)]
     pub ccd_enabled: bool,
+
+    /// Game-defined material (metal, wood, ...), passed through to collision events
+    #[builder(default = 0)]
+    pub material_id: u32,
 }
 
 impl Default for RigidBodyProperties {
@@ -61,15 +80,21 @@ impl Default for RigidBodyProperties {
             restitution: 0.0,
             linear_damping: 0.0,
             angular_damping: 0.0,
+            grounded_linear_damping: 0.0,
+            rolling_resistance: 0.0,
             density: 1.0,
             ccd_enabled: false,
+            material_id: 0,
         }
     }
 }
 
 impl RigidBodyProperties {
     /// Insert these properties into the database
+    ///
+    /// Warns once here if they are massless, rather than on every step.
     pub fn insert(self, ctx: &ReducerContext) -> Self {
+        self.warn_if_massless();
         ctx.db.rapier_rigid_body_properties().insert(self)
     }
 
@@ -83,8 +108,16 @@ impl RigidBodyProperties {
         ctx.db.rapier_rigid_body_properties().world_id().filter(world_id)
     }
 
+    /// Get all properties in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
     /// Update these properties in the database
+    ///
+    /// Warns once here if they are massless, rather than on every step.
     pub fn update(self, ctx: &ReducerContext) -> Self {
+        self.warn_if_massless();
         ctx.db.rapier_rigid_body_properties().id().update(self)
     }
 
@@ -101,4 +134,22 @@ impl RigidBodyProperties {
             0.0
         }
     }
+    /// Check if these properties give a body no mass (mass or density not positive)
+    ///
+    /// Dynamic bodies with massless properties are simulated as static.
+    pub fn is_massless(&self) -> bool {
+        !(self.mass > 0.0 && self.density > 0.0)
+    }
+
+    fn warn_if_massless(&self) {
+        if self.is_massless() {
+            log::warn!(
+                "RigidBodyProperties {}: mass {} or density {} is not positive, \
+                 dynamic bodies using them are simulated as static",
+                self.id,
+                self.mass,
+                self.density
+            );
+        }
+    }
 }
@@ -22,12 +22,28 @@ pub struct RayCastHit {
     pub point_y: f32,
     /// Hit point Z
     pub point_z: f32,
-    /// Surface normal X at hit point
+    /// Surface normal X at hit point, pointing out of the hit surface
+    ///
+    /// A ray cast down onto a floor gets a +Y normal.
     pub normal_x: f32,
     /// Surface normal Y at hit point
     pub normal_y: f32,
     /// Surface normal Z at hit point
     pub normal_z: f32,
+    /// `user_data` of the rigid body that was hit
+    pub user_data: u64,
+    /// Linear velocity X of the hit body at the end of the step
+    pub body_linear_velocity_x: f32,
+    /// Linear velocity Y of the hit body at the end of the step
+    pub body_linear_velocity_y: f32,
+    /// Linear velocity Z of the hit body at the end of the step
+    pub body_linear_velocity_z: f32,
+    /// Angular velocity X of the hit body at the end of the step
+    pub body_angular_velocity_x: f32,
+    /// Angular velocity Y of the hit body at the end of the step
+    pub body_angular_velocity_y: f32,
+    /// Angular velocity Z of the hit body at the end of the step
+    pub body_angular_velocity_z: f32,
 }
 
 impl RayCastHit {
@@ -42,9 +58,33 @@ impl RayCastHit {
             normal_x: normal.x,
             normal_y: normal.y,
             normal_z: normal.z,
+            user_data: 0,
+            body_linear_velocity_x: 0.0,
+            body_linear_velocity_y: 0.0,
+            body_linear_velocity_z: 0.0,
+            body_angular_velocity_x: 0.0,
+            body_angular_velocity_y: 0.0,
+            body_angular_velocity_z: 0.0,
         }
     }
 
+    /// Attach the hit body's `user_data`
+    pub fn with_user_data(mut self, user_data: u64) -> Self {
+        self.user_data = user_data;
+        self
+    }
+
+    /// Attach the hit body's velocities, e.g. to lead a moving target
+    pub fn with_body_velocity(mut self, linear: Vec3, angular: Vec3) -> Self {
+        self.body_linear_velocity_x = linear.x;
+        self.body_linear_velocity_y = linear.y;
+        self.body_linear_velocity_z = linear.z;
+        self.body_angular_velocity_x = angular.x;
+        self.body_angular_velocity_y = angular.y;
+        self.body_angular_velocity_z = angular.z;
+        self
+    }
+
     /// Get hit point as Vec3
     pub fn point(&self) -> Vec3 {
         Vec3::new(self.point_x, self.point_y, self.point_z)
@@ -55,6 +95,16 @@ impl RayCastHit {
         Vec3::new(self.normal_x, self.normal_y, self.normal_z)
     }
 
+    /// Get the hit body's linear velocity as Vec3
+    pub fn body_linear_velocity(&self) -> Vec3 {
+        Vec3::new(self.body_linear_velocity_x, self.body_linear_velocity_y, self.body_linear_velocity_z)
+    }
+
+    /// Get the hit body's angular velocity as Vec3
+    pub fn body_angular_velocity(&self) -> Vec3 {
+        Vec3::new(self.body_angular_velocity_x, self.body_angular_velocity_y, self.body_angular_velocity_z)
+    }
+
     #[cfg(feature = "dim2")]
     pub fn point_2d(&self) -> Vec2 {
         Vec2::new(self.point_x, self.point_y)
@@ -64,6 +114,12 @@ impl RayCastHit {
     pub fn normal_2d(&self) -> Vec2 {
         Vec2::new(self.normal_x, self.normal_y)
     }
+
+    /// Get the hit body's linear velocity as Vec2
+    #[cfg(feature = "dim2")]
+    pub fn body_linear_velocity_2d(&self) -> Vec2 {
+        Vec2::new(self.body_linear_velocity_x, self.body_linear_velocity_y)
+    }
 }
 
 /// A persistent raycast that is checked every physics tick
@@ -89,7 +145,9 @@ pub struct RayCast {
     #[builder(default = 0.0)]
     pub origin_z: f32,
 
-    // Direction (should be normalized)
+    // Direction, normalized by `new`/`set_direction`. Values set through the
+    // builder are normalized when the ray is cast, so `max_distance` is always
+    // in world units regardless of the direction's length.
     #[builder(default = 0.0)]
     pub direction_x: f32,
     #[builder(default = -1.0)]
@@ -97,7 +155,7 @@ pub struct RayCast {
     #[builder(default = 0.0)]
     pub direction_z: f32,
 
-    /// Maximum distance for the ray
+    /// Maximum distance for the ray, in world units
     #[builder(default = 100.0)]
     pub max_distance: f32,
 
@@ -143,24 +201,64 @@ impl Default for RayCast {
     }
 }
 
+/// Parameters of a ray, without any hit state
+///
+/// `spacetime_physics` has a `RayDesc` with the same fields, so a raycast
+/// can be moved between the two crates by copying them over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayDesc {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub max_distance: f32,
+    pub solid: bool,
+}
+
 impl RayCast {
-    /// Create a new raycast
+    /// Create a new raycast, the direction is normalized
     pub fn new(world_id: u64, origin: Vec3, direction: Vec3, max_distance: f32, solid: bool) -> Self {
+        Self::from_desc(world_id, RayDesc { origin, direction, max_distance, solid })
+    }
+
+    /// Create a new raycast from a `RayDesc`, the direction is normalized
+    pub fn from_desc(world_id: u64, desc: RayDesc) -> Self {
+        let direction = desc.direction.normalize_or_zero();
         Self {
             id: 0,
             world_id,
-            origin_x: origin.x,
-            origin_y: origin.y,
-            origin_z: origin.z,
+            origin_x: desc.origin.x,
+            origin_y: desc.origin.y,
+            origin_z: desc.origin.z,
             direction_x: direction.x,
             direction_y: direction.y,
             direction_z: direction.z,
-            max_distance,
-            solid,
+            max_distance: desc.max_distance,
+            solid: desc.solid,
             ..Default::default()
         }
     }
 
+    /// Create a raycast going from `origin` to `target`, and not beyond
+    pub fn from_to(world_id: u64, origin: Vec3, target: Vec3, solid: bool) -> Self {
+        Self::new(world_id, origin, target - origin, origin.distance(target), solid)
+    }
+
+    /// Point this raycast from `origin` to `target`, its length becomes the distance between them
+    pub fn set_from_to(&mut self, origin: Vec3, target: Vec3) {
+        self.set_origin(origin);
+        self.set_direction(target - origin);
+        self.max_distance = origin.distance(target);
+    }
+
+    /// Get the ray parameters of this raycast
+    pub fn desc(&self) -> RayDesc {
+        RayDesc {
+            origin: self.origin(),
+            direction: self.direction(),
+            max_distance: self.max_distance,
+            solid: self.solid,
+        }
+    }
+
     /// Insert this raycast into the database
     pub fn insert(self, ctx: &ReducerContext) -> Self {
         ctx.db.rapier_raycast().insert(self)
@@ -176,6 +274,24 @@ impl RayCast {
         ctx.db.rapier_raycast().world_id().filter(world_id)
     }
 
+    /// Enable or disable every raycast in a world, returns how many changed
+    pub fn set_enabled_in_world(ctx: &ReducerContext, world_id: u64, enabled: bool) -> usize {
+        let raycasts: Vec<_> = Self::all_in_world(ctx, world_id)
+            .filter(|raycast| raycast.enabled != enabled)
+            .collect();
+        let changed = raycasts.len();
+        for mut raycast in raycasts {
+            raycast.enabled = enabled;
+            raycast.update(ctx);
+        }
+        changed
+    }
+
+    /// Get all raycasts in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
     /// Update this raycast in the database
     pub fn update(self, ctx: &ReducerContext) -> Self {
         ctx.db.rapier_raycast().id().update(self)
@@ -203,8 +319,9 @@ impl RayCast {
         Vec3::new(self.direction_x, self.direction_y, self.direction_z)
     }
 
-    /// Set direction from Vec3
+    /// Set direction from Vec3, the direction is normalized
     pub fn set_direction(&mut self, direction: Vec3) {
+        let direction = direction.normalize_or_zero();
         self.direction_x = direction.x;
         self.direction_y = direction.y;
         self.direction_z = direction.z;
@@ -268,3 +385,67 @@ impl RayCast {
         Vec2::new(self.direction_x, self.direction_y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_desc_round_trips_through_the_constructor() {
+        let desc = RayDesc {
+            origin: Vec3::new(1.0, 2.0, 3.0),
+            direction: Vec3::new(0.0, 0.0, -1.0),
+            max_distance: 25.0,
+            solid: true,
+        };
+
+        let raycast = RayCast::from_desc(1, desc);
+
+        assert_eq!(raycast.desc(), desc);
+        // Same ray as the positional constructor
+        assert_eq!(RayCast::new(1, desc.origin, desc.direction, 25.0, true).desc(), desc);
+    }
+
+    #[test]
+    fn from_to_ray_hits_the_target_and_nothing_beyond() {
+        use crate::engine::test_utils::TestWorld;
+        use crate::tables::{Collider, PhysicsWorld, RigidBody};
+
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let target = world.add_body(RigidBody { position_x: 5.0, ..Default::default() }, Collider::ball(1, 0.5));
+        world.add_body(RigidBody { position_x: 8.0, ..Default::default() }, Collider::ball(1, 0.5));
+
+        let aimed = world.add_raycast(RayCast::from_to(1, Vec3::ZERO, Vec3::new(5.0, 0.0, 0.0), true));
+        let mut short = RayCast::from_to(1, Vec3::ZERO, Vec3::new(9.0, 0.0, 0.0), true);
+        short.set_from_to(Vec3::ZERO, Vec3::new(4.4, 0.0, 0.0));
+        assert!((short.max_distance - 4.4).abs() < 1e-6);
+        let short = world.add_raycast(short);
+
+        world.step();
+
+        let hits = &world.raycast(aimed).hits;
+        assert_eq!(hits.iter().map(|hit| hit.rigid_body_id).collect::<Vec<_>>(), vec![target]);
+        assert!((hits[0].distance - 4.5).abs() < 1e-4, "hit at {}", hits[0].distance);
+        assert!(world.raycast(short).hits.is_empty());
+    }
+
+    #[test]
+    fn hitting_a_moving_body_reports_its_velocities() {
+        use crate::engine::test_utils::TestWorld;
+        use crate::tables::{Collider, PhysicsWorld, RigidBody};
+
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let moving = RigidBody { linear_velocity_x: 3.0, angular_velocity_z: 2.0, ..Default::default() };
+        let target = world.add_body(moving, Collider::ball(1, 1.0));
+        let ray = world.add_raycast(RayCast { origin_y: 5.0, ..Default::default() });
+
+        world.step();
+
+        let hit = &world.raycast(ray).hits[0];
+        assert_eq!(hit.rigid_body_id, target);
+        assert_eq!(hit.body_linear_velocity(), world.body(target).linear_velocity());
+        assert_eq!(hit.body_angular_velocity(), world.body(target).angular_velocity());
+        assert!((hit.body_linear_velocity() - Vec3::new(3.0, 0.0, 0.0)).length() < 1e-4);
+        assert!((hit.body_angular_velocity() - Vec3::new(0.0, 0.0, 2.0)).length() < 1e-4);
+    }
+}
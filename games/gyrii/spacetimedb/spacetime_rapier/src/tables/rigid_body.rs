@@ -3,9 +3,17 @@
 use bon::Builder;
 use spacetimedb::{table, ReducerContext, SpacetimeType, Table};
 use crate::math::{Vec3, Quat};
+use crate::tables::{Collider, RigidBodyProperties};
+use crate::engine::ExternalForce;
 
 #[cfg(feature = "dim2")]
-use crate::math::Vec2;
+use crate::math::{float, Vec2};
+
+#[cfg(feature = "dim2")]
+use rapier2d::prelude::MassProperties;
+
+#[cfg(feature = "dim3")]
+use rapier3d::prelude::MassProperties;
 
 pub type RigidBodyId = u64;
 
@@ -21,9 +29,46 @@ pub enum RigidBodyType {
     Kinematic,
 }
 
+/// Pose and velocities of a body, for snapshots and rollback
+///
+/// Everything the simulation changes on a body, so restoring a `Transform`
+/// puts the body back exactly where and how fast it was.
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub linear_velocity: Vec3,
+    pub angular_velocity: Vec3,
+}
+
+impl RigidBodyType {
+    /// Bit flag for this body type (see `BodyTypeFlags`)
+    pub const fn flag(self) -> u8 {
+        match self {
+            RigidBodyType::Static => BodyTypeFlags::STATIC,
+            RigidBodyType::Dynamic => BodyTypeFlags::DYNAMIC,
+            RigidBodyType::Kinematic => BodyTypeFlags::KINEMATIC,
+        }
+    }
+}
+
+/// Bit flags for selecting a set of body types
+pub struct BodyTypeFlags;
+
+impl BodyTypeFlags {
+    /// Static bodies
+    pub const STATIC: u8 = 1 << 0;
+    /// Dynamic bodies
+    pub const DYNAMIC: u8 = 1 << 1;
+    /// Kinematic bodies
+    pub const KINEMATIC: u8 = 1 << 2;
+    /// All body types
+    pub const ALL: u8 = Self::STATIC | Self::DYNAMIC | Self::KINEMATIC;
+}
+
 /// A rigid body in the physics simulation
 #[table(name = rapier_rigid_body, public)]
-#[derive(Builder, Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Builder, Clone, Copy, Debug, PartialEq)]
 #[builder(derive(Debug, Clone))]
 pub struct RigidBody {
     #[primary_key]
@@ -75,14 +120,171 @@ pub struct RigidBody {
     pub body_type: RigidBodyType,
 
     /// ID of the collider attached to this body
+    ///
+    /// A body has exactly one collider, and its friction, restitution and
+    /// density always come from `properties_id`, never from the collider.
+    /// This keeps the material independent of how colliders are attached.
     pub collider_id: u64,
 
     /// ID of the properties (mass, friction, restitution)
     pub properties_id: u64,
 
+    /// Game-defined data, e.g. the ID of the entity owning this body
+    ///
+    /// Carried into raycast hits and contacts to avoid reverse lookups.
+    #[builder(default = 0)]
+    pub user_data: u64,
+
     /// Whether this body is currently enabled
     #[builder(default = true)]
     pub enabled: bool,
+
+    /// Collision groups this body is a member of (bitmask, default: all)
+    #[builder(default = u32::MAX)]
+    pub collision_groups: u32,
+
+    /// Prevent rotation around the X axis (ignored in 2D)
+    #[builder(default = false)]
+    pub lock_rotation_x: bool,
+
+    /// Prevent rotation around the Y axis (ignored in 2D)
+    #[builder(default = false)]
+    pub lock_rotation_y: bool,
+
+    /// Prevent rotation around the Z axis (the only rotation axis in 2D)
+    #[builder(default = false)]
+    pub lock_rotation_z: bool,
+
+    /// Torque pulling the body's local up axis back toward the world up
+    /// (against gravity), per unit of sine of the tilt angle. 0 disables it
+    ///
+    /// A soft alternative to rotation locks: the body can tip over when hit
+    /// and rights itself afterwards. Only applies to dynamic bodies.
+    #[builder(default = 0.0)]
+    pub upright_stiffness: f32,
+
+    /// Simulate this body as static without changing its `body_type`
+    ///
+    /// A frozen body is not moved or written back, so it resumes with its
+    /// stored velocity once unfrozen.
+    #[builder(default = false)]
+    pub frozen: bool,
+
+    /// Whether contacts with this body produce a physical response
+    ///
+    /// When false the body is still hit by raycasts, detected by triggers and
+    /// reported in collision events, but nothing is pushed by or pushes it.
+    /// A dynamic body with no response still falls under gravity.
+    #[builder(default = true)]
+    pub response_enabled: bool,
+
+    /// Whether a kinematic body has a pose to reach during the next step
+    ///
+    /// Set with `set_kinematic_target`. Unlike writing the position directly,
+    /// the move gives the body a velocity so it pushes dynamic bodies out of
+    /// the way. Cleared once the step has moved the body.
+    #[builder(default = false)]
+    pub has_kinematic_target: bool,
+
+    // Kinematic target position
+    #[builder(default = 0.0)]
+    pub kinematic_target_x: f32,
+    #[builder(default = 0.0)]
+    pub kinematic_target_y: f32,
+    #[builder(default = 0.0)]
+    pub kinematic_target_z: f32,
+
+    // Kinematic target rotation (quaternion)
+    #[builder(default = 0.0)]
+    pub kinematic_target_rotation_x: f32,
+    #[builder(default = 0.0)]
+    pub kinematic_target_rotation_y: f32,
+    #[builder(default = 0.0)]
+    pub kinematic_target_rotation_z: f32,
+    #[builder(default = 1.0)]
+    pub kinematic_target_rotation_w: f32,
+
+    /// World tick at which `step_world` deletes this body, for debris and
+    /// projectiles. Its collider and properties are deleted too unless
+    /// another body or trigger still uses them.
+    pub expire_at_tick: Option<u64>,
+}
+
+/// The builder's defaults: an enabled body in world 1, at the identity rotation
+///
+/// Note that a derived `Default` gave `world_id: 0`, `rotation_w: 0.0` and
+/// `enabled: false`, a disabled body with an invalid rotation that the step
+/// skipped. Code relying on those values has to set them explicitly.
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            world_id: 1,
+            position_x: 0.0,
+            position_y: 0.0,
+            position_z: 0.0,
+            rotation_x: 0.0,
+            rotation_y: 0.0,
+            rotation_z: 0.0,
+            rotation_w: 1.0,
+            linear_velocity_x: 0.0,
+            linear_velocity_y: 0.0,
+            linear_velocity_z: 0.0,
+            angular_velocity_x: 0.0,
+            angular_velocity_y: 0.0,
+            angular_velocity_z: 0.0,
+            body_type: RigidBodyType::default(),
+            collider_id: 0,
+            properties_id: 0,
+            user_data: 0,
+            enabled: true,
+            collision_groups: u32::MAX,
+            lock_rotation_x: false,
+            lock_rotation_y: false,
+            lock_rotation_z: false,
+            upright_stiffness: 0.0,
+            frozen: false,
+            response_enabled: true,
+            has_kinematic_target: false,
+            kinematic_target_x: 0.0,
+            kinematic_target_y: 0.0,
+            kinematic_target_z: 0.0,
+            kinematic_target_rotation_x: 0.0,
+            kinematic_target_rotation_y: 0.0,
+            kinematic_target_rotation_z: 0.0,
+            kinematic_target_rotation_w: 1.0,
+            expire_at_tick: None,
+        }
+    }
+}
+
+impl<S: rigid_body_builder::State> RigidBodyBuilder<S> {
+    /// Insert a collider and attach it to this body
+    ///
+    /// Replaces inserting the collider separately and passing `collider_id`.
+    /// The collider should belong to the same world as the body.
+    pub fn shape(
+        self,
+        ctx: &ReducerContext,
+        collider: Collider,
+    ) -> RigidBodyBuilder<rigid_body_builder::SetColliderId<S>>
+    where
+        S::ColliderId: rigid_body_builder::IsUnset,
+    {
+        self.shape_with(collider, |collider| collider.insert(ctx).id)
+    }
+
+    /// Same as `shape`, with a custom way to store the collider and get its ID
+    pub fn shape_with(
+        self,
+        collider: Collider,
+        insert: impl FnOnce(Collider) -> u64,
+    ) -> RigidBodyBuilder<rigid_body_builder::SetColliderId<S>>
+    where
+        S::ColliderId: rigid_body_builder::IsUnset,
+    {
+        self.collider_id(insert(collider))
+    }
 }
 
 impl RigidBody {
@@ -101,6 +303,30 @@ impl RigidBody {
         ctx.db.rapier_rigid_body().world_id().filter(world_id)
     }
 
+    /// Get all bodies of a given type in a world
+    ///
+    /// Matches the stored `body_type`, so frozen bodies are included.
+    pub fn all_of_type(
+        ctx: &ReducerContext,
+        world_id: u64,
+        body_type: RigidBodyType,
+    ) -> impl Iterator<Item = Self> + '_ {
+        Self::all_of_type_in(Self::all_in_world(ctx, world_id), body_type)
+    }
+
+    /// Same as `all_of_type`, over already loaded bodies
+    pub fn all_of_type_in(
+        bodies: impl IntoIterator<Item = Self>,
+        body_type: RigidBodyType,
+    ) -> impl Iterator<Item = Self> {
+        bodies.into_iter().filter(move |body| body.body_type == body_type)
+    }
+
+    /// Get all bodies in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
     /// Update this body in the database
     pub fn update(self, ctx: &ReducerContext) -> Self {
         ctx.db.rapier_rigid_body().id().update(self)
@@ -160,6 +386,90 @@ impl RigidBody {
         self.angular_velocity_z = vel.z;
     }
 
+    /// Get position, rotation and velocities as a `Transform`
+    pub fn transform(&self) -> Transform {
+        Transform {
+            position: self.position(),
+            rotation: self.rotation(),
+            linear_velocity: self.linear_velocity(),
+            angular_velocity: self.angular_velocity(),
+        }
+    }
+
+    /// Set position, rotation and velocities from a `Transform`
+    pub fn apply_transform(&mut self, transform: Transform) {
+        self.set_position(transform.position);
+        self.set_rotation(transform.rotation);
+        self.set_linear_velocity(transform.linear_velocity);
+        self.set_angular_velocity(transform.angular_velocity);
+    }
+
+    /// True if the position, rotation and velocities hold no NaN or infinity
+    pub fn is_finite(&self) -> bool {
+        self.position().is_finite()
+            && self.rotation().is_finite()
+            && self.linear_velocity().is_finite()
+            && self.angular_velocity().is_finite()
+    }
+
+    /// Replace non-finite state with the pose of `previous` and zero velocities
+    ///
+    /// Returns true if anything was replaced. Used before writing a body back,
+    /// a NaN stored once would poison every following step.
+    pub fn sanitize(&mut self, previous: &RigidBody) -> bool {
+        if self.is_finite() {
+            return false;
+        }
+        self.set_position(self.position().sanitize(previous.position().sanitize(Vec3::ZERO)));
+        self.set_rotation(self.rotation().sanitize(previous.rotation().sanitize(Quat::IDENTITY)));
+        self.set_linear_velocity(self.linear_velocity().sanitize(Vec3::ZERO));
+        self.set_angular_velocity(self.angular_velocity().sanitize(Vec3::ZERO));
+        true
+    }
+
+    /// Rotate the body so its forward (-Z) faces `target`
+    ///
+    /// This sets the rotation directly, it's meant for kinematic bodies such
+    /// as turrets or projectiles rather than physically driven ones.
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        self.set_rotation(Quat::look_at(self.position(), target, up));
+    }
+
+    /// Move a kinematic body to a pose during the next step
+    ///
+    /// The body sweeps from its current pose to the target, pushing dynamic
+    /// bodies in the way. Use `set_position` to teleport instead.
+    pub fn set_kinematic_target(&mut self, position: Vec3, rotation: Quat) {
+        self.has_kinematic_target = true;
+        self.kinematic_target_x = position.x;
+        self.kinematic_target_y = position.y;
+        self.kinematic_target_z = position.z;
+        self.kinematic_target_rotation_x = rotation.x;
+        self.kinematic_target_rotation_y = rotation.y;
+        self.kinematic_target_rotation_z = rotation.z;
+        self.kinematic_target_rotation_w = rotation.w;
+    }
+
+    /// Get the pending kinematic target, if any
+    pub fn kinematic_target(&self) -> Option<(Vec3, Quat)> {
+        self.has_kinematic_target.then(|| {
+            (
+                Vec3::new(self.kinematic_target_x, self.kinematic_target_y, self.kinematic_target_z),
+                Quat::new(
+                    self.kinematic_target_rotation_x,
+                    self.kinematic_target_rotation_y,
+                    self.kinematic_target_rotation_z,
+                    self.kinematic_target_rotation_w,
+                ),
+            )
+        })
+    }
+
+    /// Drop the pending kinematic target
+    pub fn clear_kinematic_target(&mut self) {
+        self.has_kinematic_target = false;
+    }
+
     /// Check if this is a dynamic body
     pub fn is_dynamic(&self) -> bool {
         self.body_type == RigidBodyType::Dynamic
@@ -175,6 +485,169 @@ impl RigidBody {
         self.body_type == RigidBodyType::Static
     }
 
+    /// Flag a body's mass properties for recomputation on the next step
+    ///
+    /// Call this after resizing its collider or changing its density. Mass
+    /// and inertia are currently rebuilt from the collider every step, so
+    /// this does nothing yet; it's the hook a cached path will honour.
+    pub fn mark_mass_dirty(_ctx: &ReducerContext, _id: RigidBodyId) {}
+
+    /// Swap the collider of a body, keeping its ID, pose and velocity
+    ///
+    /// Pass 0 to detach the collider. Mass properties are recomputed from the
+    /// new shape on the next step. The old collider is left in its table.
+    /// Returns `None` if the body or the new collider doesn't exist.
+    pub fn set_collider(ctx: &ReducerContext, id: RigidBodyId, collider_id: u64) -> Option<Self> {
+        let mut body = Self::find(ctx, id)?;
+        if collider_id != 0 {
+            Collider::find(ctx, collider_id)?;
+        }
+        body.collider_id = collider_id;
+        Some(body.update(ctx))
+    }
+
+    /// Temporarily make this body behave as static
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    /// Let a frozen body move again
+    pub fn unfreeze(&mut self) {
+        self.frozen = false;
+    }
+
+    /// Turn a force in the body's local frame into a world-space force for the next step
+    ///
+    /// Useful for thrusters: a local -Z force always pushes along the body's forward.
+    /// Rapier forces only live for one step, so pass the result to
+    /// `step_world_with_forces` every tick the thrust is active.
+    #[cfg(feature = "dim3")]
+    pub fn apply_local_force(ctx: &ReducerContext, id: RigidBodyId, local_force: Vec3) -> Option<ExternalForce> {
+        Some(Self::find(ctx, id)?.local_force(local_force))
+    }
+
+    /// Same as `apply_local_force` for a body that was already loaded
+    #[cfg(feature = "dim3")]
+    pub fn local_force(&self, local_force: Vec3) -> ExternalForce {
+        (self.id, self.rotation().rotate_vec3(local_force), Vec3::ZERO)
+    }
+
+    /// Turn a force in the body's local frame into a world-space force for the next step (2D)
+    ///
+    /// Rapier forces only live for one step, so pass the result to
+    /// `step_world_with_forces` every tick the thrust is active.
+    #[cfg(feature = "dim2")]
+    pub fn apply_local_force(ctx: &ReducerContext, id: RigidBodyId, local_force: Vec2) -> Option<ExternalForce> {
+        Some(Self::find(ctx, id)?.local_force(local_force))
+    }
+
+    /// Same as `apply_local_force` for a body that was already loaded (2D)
+    #[cfg(feature = "dim2")]
+    pub fn local_force(&self, local_force: Vec2) -> ExternalForce {
+        (self.id, local_force.rotate(self.rotation_angle()), 0.0)
+    }
+
+    /// Lock X and Z rotation so the body can turn around Y but never tip over
+    pub fn lock_upright(&mut self) {
+        self.lock_rotation_x = true;
+        self.lock_rotation_z = true;
+    }
+
+    /// Compute mass properties from the collider shape and the properties' density
+    ///
+    /// This matches what the simulation uses: Rapier derives mass from density,
+    /// so `RigidBodyProperties::mass` is not taken into account here.
+    pub fn mass_properties(&self, ctx: &ReducerContext) -> Option<MassProperties> {
+        let collider = Collider::find(ctx, self.collider_id)?;
+        let density = RigidBodyProperties::find(ctx, self.properties_id)
+            .map(|p| p.density)
+            .unwrap_or(1.0);
+        Some(collider.shared_shape().mass_properties(density))
+    }
+
+    /// Center of mass in world space (3D)
+    ///
+    /// Shapes like cones and triangles are not centered on the body origin,
+    /// falls back to the body position when the collider is missing.
+    #[cfg(feature = "dim3")]
+    pub fn center_of_mass(&self, ctx: &ReducerContext) -> Vec3 {
+        match self.mass_properties(ctx) {
+            Some(mprops) => self.center_of_mass_with(&mprops),
+            None => self.position(),
+        }
+    }
+
+    /// Center of mass in world space from already computed mass properties (3D)
+    #[cfg(feature = "dim3")]
+    pub fn center_of_mass_with(&self, mprops: &MassProperties) -> Vec3 {
+        self.position() + self.rotation().rotate_vec3(mprops.local_com.into())
+    }
+
+    /// Center of mass in world space (2D)
+    #[cfg(feature = "dim2")]
+    pub fn center_of_mass(&self, ctx: &ReducerContext) -> Vec2 {
+        match self.mass_properties(ctx) {
+            Some(mprops) => self.center_of_mass_with(&mprops),
+            None => self.position_2d(),
+        }
+    }
+
+    /// Center of mass in world space from already computed mass properties (2D)
+    #[cfg(feature = "dim2")]
+    pub fn center_of_mass_with(&self, mprops: &MassProperties) -> Vec2 {
+        self.position_2d() + Vec2::from(mprops.local_com).rotate(self.rotation_angle())
+    }
+
+    /// Linear momentum (mass * linear velocity)
+    pub fn momentum(&self, ctx: &ReducerContext) -> Vec3 {
+        match self.mass_properties(ctx) {
+            Some(mprops) => self.linear_velocity() * mprops.mass(),
+            None => Vec3::ZERO,
+        }
+    }
+
+    /// Kinetic energy, linear plus rotational (3D)
+    #[cfg(feature = "dim3")]
+    pub fn kinetic_energy(&self, ctx: &ReducerContext) -> f32 {
+        self.mass_properties(ctx)
+            .map_or(0.0, |mprops| self.kinetic_energy_with(&mprops))
+    }
+
+    /// Kinetic energy for already computed mass properties (3D)
+    #[cfg(feature = "dim3")]
+    pub fn kinetic_energy_with(&self, mprops: &MassProperties) -> f32 {
+        let linear = 0.5 * mprops.mass() * self.linear_velocity().length_squared();
+
+        // Express the angular velocity in the inertia's principal frame
+        let frame = nalgebra::UnitQuaternion::from(self.rotation())
+            * mprops.principal_inertia_local_frame;
+        let w = frame.inverse_transform_vector(&nalgebra::Vector3::new(
+            self.angular_velocity_x,
+            self.angular_velocity_y,
+            self.angular_velocity_z,
+        ));
+        let i = mprops.principal_inertia();
+        let angular = 0.5 * (i.x * w.x * w.x + i.y * w.y * w.y + i.z * w.z * w.z);
+
+        linear + angular
+    }
+
+    /// Kinetic energy, linear plus rotational (2D)
+    #[cfg(feature = "dim2")]
+    pub fn kinetic_energy(&self, ctx: &ReducerContext) -> f32 {
+        self.mass_properties(ctx)
+            .map_or(0.0, |mprops| self.kinetic_energy_with(&mprops))
+    }
+
+    /// Kinetic energy for already computed mass properties (2D)
+    #[cfg(feature = "dim2")]
+    pub fn kinetic_energy_with(&self, mprops: &MassProperties) -> f32 {
+        let linear = 0.5 * mprops.mass() * self.linear_velocity().length_squared();
+        let angular = 0.5 * mprops.principal_inertia() * self.angular_velocity_z * self.angular_velocity_z;
+
+        linear + angular
+    }
+
     // 2D helpers
     #[cfg(feature = "dim2")]
     pub fn position_2d(&self) -> Vec2 {
@@ -190,7 +663,15 @@ impl RigidBody {
     #[cfg(feature = "dim2")]
     pub fn rotation_angle(&self) -> f32 {
         // Extract Z rotation from quaternion
-        2.0 * self.rotation_z.atan2(self.rotation_w)
+        2.0 * float::atan2(self.rotation_z, self.rotation_w)
+    }
+
+    #[cfg(feature = "dim2")]
+    pub fn set_kinematic_target_2d(&mut self, position: Vec2, angle: f32) {
+        self.set_kinematic_target(
+            Vec3::new(position.x, position.y, self.position_z),
+            Quat::from_angle_z(angle),
+        );
     }
 
     #[cfg(feature = "dim2")]
@@ -198,7 +679,191 @@ impl RigidBody {
         let half = angle / 2.0;
         self.rotation_x = 0.0;
         self.rotation_y = 0.0;
-        self.rotation_z = half.sin();
-        self.rotation_w = half.cos();
+        self.rotation_z = float::sin(half);
+        self.rotation_w = float::cos(half);
+    }
+}
+
+/// A ready-to-insert upright character: a capsule collider and a body that can't tip over
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CharacterBundle {
+    pub collider: Collider,
+    pub body: RigidBody,
+}
+
+impl CharacterBundle {
+    /// Create a character standing at `position` with the given total height and radius
+    pub fn new(
+        world_id: u64,
+        position: Vec3,
+        height: f32,
+        radius: f32,
+        properties_id: u64,
+        body_type: RigidBodyType,
+    ) -> Self {
+        let mut body = RigidBody {
+            world_id,
+            body_type,
+            properties_id,
+            ..Default::default()
+        };
+        body.set_position(position);
+        body.lock_upright();
+
+        Self {
+            collider: Collider::character(world_id, height, radius),
+            body,
+        }
+    }
+
+    /// Insert the collider and the body, wiring the collider ID into the body
+    pub fn insert(self, ctx: &ReducerContext) -> (RigidBody, Collider) {
+        let collider = self.collider.insert(ctx);
+        let body = RigidBody {
+            collider_id: collider.id,
+            ..self.body
+        }
+        .insert(ctx);
+        (body, collider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::test_utils::TestWorld;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn default_matches_the_builder_defaults() {
+        let built = RigidBody::builder().collider_id(0).properties_id(0).build();
+        assert_eq!(RigidBody::default(), built);
+        assert_eq!((built.world_id, built.rotation_w, built.enabled), (1, 1.0, true));
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn kinetic_energy_of_spinning_ball_matches_analytic_value() {
+        // Unit ball of density 1: m = 4/3 π, I = 2/5 m r²
+        let mprops = Collider::ball(1, 1.0).shared_shape().mass_properties(1.0);
+        let mut body = RigidBody::default();
+        body.set_linear_velocity(Vec3::new(3.0, 0.0, 0.0));
+        body.set_angular_velocity(Vec3::new(0.0, 2.0, 0.0));
+
+        let mass = 4.0 / 3.0 * PI;
+        let expected = 0.5 * mass * 9.0 + 0.5 * (0.4 * mass) * 4.0;
+        let energy = body.kinetic_energy_with(&mprops);
+        assert!((energy - expected).abs() < 1e-4, "{} != {}", energy, expected);
+    }
+
+    #[cfg(feature = "dim2")]
+    #[test]
+    fn kinetic_energy_of_spinning_disk_matches_analytic_value() {
+        // Unit disk of density 1: m = π, I = 1/2 m r²
+        let mprops = Collider::ball(1, 1.0).shared_shape().mass_properties(1.0);
+        let mut body = RigidBody::default();
+        body.set_linear_velocity(Vec3::new(3.0, 0.0, 0.0));
+        body.angular_velocity_z = 2.0;
+
+        let expected = 0.5 * PI * 9.0 + 0.5 * (0.5 * PI) * 4.0;
+        let energy = body.kinetic_energy_with(&mprops);
+        assert!((energy - expected).abs() < 1e-4, "{} != {}", energy, expected);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn cone_center_of_mass_is_below_the_origin_and_turns_with_the_body() {
+        // A cone's center of mass is a quarter of its height above the base, so
+        // half_height / 2 below its center, which the body origin sits on
+        let mprops = Collider::cone(1, 2.0, 1.0).shared_shape().mass_properties(1.0);
+        let mut body = RigidBody::default();
+        body.set_position(Vec3::new(1.0, 2.0, 3.0));
+        assert!((body.center_of_mass_with(&mprops) - Vec3::new(1.0, 1.0, 3.0)).length() < 1e-5);
+
+        // Lying on its side, the center of mass moves along X
+        body.set_rotation(Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), PI / 2.0));
+        let com = body.center_of_mass_with(&mprops);
+        assert!((com - Vec3::new(2.0, 2.0, 3.0)).length() < 1e-5, "{:?}", com);
+    }
+
+    #[cfg(feature = "dim2")]
+    #[test]
+    fn triangle_center_of_mass_is_its_centroid_and_turns_with_the_body() {
+        let triangle = Collider::triangle(1, Vec3::ZERO, Vec3::new(3.0, 0.0, 0.0), Vec3::new(0.0, 3.0, 0.0));
+        let mprops = triangle.shared_shape().mass_properties(1.0);
+        let mut body = RigidBody::default();
+        body.set_position_2d(Vec2::new(1.0, 2.0));
+        assert!((body.center_of_mass_with(&mprops) - Vec2::new(2.0, 3.0)).length() < 1e-5);
+
+        body.set_rotation_angle(PI / 2.0);
+        let com = body.center_of_mass_with(&mprops);
+        assert!((com - Vec2::new(0.0, 3.0)).length() < 1e-5, "{:?}", com);
+    }
+
+    #[test]
+    fn sanitize_replaces_non_finite_state_with_the_previous_pose() {
+        let mut previous = RigidBody::default();
+        previous.set_position(Vec3::new(1.0, 2.0, 3.0));
+
+        let mut body = previous;
+        body.set_position(Vec3::new(f32::NAN, f32::NAN, f32::NAN));
+        body.set_linear_velocity(Vec3::new(f32::INFINITY, 0.0, 0.0));
+        assert!(body.sanitize(&previous));
+        assert!(body.is_finite());
+        assert_eq!(body.position(), previous.position());
+        assert_eq!(body.linear_velocity(), Vec3::ZERO);
+
+        // Finite state is kept as is
+        let mut moving = previous;
+        moving.set_linear_velocity(Vec3::new(0.0, -1.0, 0.0));
+        assert!(!moving.sanitize(&previous));
+        assert_eq!(moving.linear_velocity(), Vec3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn all_of_type_returns_only_the_bodies_of_that_type() {
+        let bodies = [
+            (1, RigidBodyType::Dynamic, false),
+            (2, RigidBodyType::Static, false),
+            (3, RigidBodyType::Kinematic, false),
+            (4, RigidBodyType::Dynamic, true),
+            (5, RigidBodyType::Static, false),
+        ]
+        .map(|(id, body_type, frozen)| RigidBody { id, body_type, frozen, ..Default::default() });
+
+        let ids = |body_type| RigidBody::all_of_type_in(bodies, body_type).map(|body| body.id).collect::<Vec<_>>();
+        // Frozen bodies keep their stored type
+        assert_eq!(ids(RigidBodyType::Dynamic), vec![1, 4]);
+        assert_eq!(ids(RigidBodyType::Static), vec![2, 5]);
+        assert_eq!(ids(RigidBodyType::Kinematic), vec![3]);
+    }
+
+    #[test]
+    fn transform_round_trips_and_restores_a_stepped_body() {
+        let mut body = RigidBody { id: 3, collider_id: 4, user_data: 5, ..Default::default() };
+        body.set_position(Vec3::new(1.0, 2.0, 3.0));
+        body.set_rotation(Quat::from_rotation_z(0.5));
+        body.set_linear_velocity(Vec3::new(-1.0, 4.0, 0.5));
+        body.set_angular_velocity(Vec3::new(0.0, 0.0, 2.0));
+
+        let snapshot = body.transform();
+        let mut restored = RigidBody { id: 3, collider_id: 4, user_data: 5, ..Default::default() };
+        restored.apply_transform(snapshot);
+        assert_eq!(restored, body);
+        assert_eq!(restored.transform(), snapshot);
+
+        // Rolling back and stepping again replays the same motion
+        let mut world = TestWorld::with_defaults();
+        let ball =
+            world.add_body(RigidBody { linear_velocity_x: 2.0, ..Default::default() }, Collider::ball(1, 0.5));
+        world.step_n(10);
+        let snapshot = world.body(ball).transform();
+        world.step_n(10);
+        let ahead = world.body(ball).transform();
+
+        world.body_mut(ball).apply_transform(snapshot);
+        assert_eq!(world.body(ball).transform(), snapshot);
+        world.step_n(10);
+        assert_eq!(world.body(ball).transform(), ahead);
     }
 }
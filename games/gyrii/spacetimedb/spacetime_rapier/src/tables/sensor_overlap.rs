@@ -0,0 +1,116 @@
+//! SensorOverlap table - bodies overlapping a rigid body's sensor collider
+
+use spacetimedb::{table, ReducerContext, Table};
+
+pub type SensorOverlapId = u64;
+
+/// Bodies currently inside the sensor collider attached to a rigid body
+///
+/// Works like a `Trigger` that moves with its body. A row exists for every
+/// enabled body whose collider has `is_sensor` set, and is removed once the
+/// body no longer has one.
+#[table(name = rapier_sensor_overlap, public)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorOverlap {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+
+    /// Which physics world this overlap list belongs to
+    #[index(btree)]
+    pub world_id: u64,
+
+    /// ID of the rigid body carrying the sensor
+    #[unique]
+    pub body_id: u64,
+
+    /// Bodies currently inside the sensor
+    pub entities_inside: Vec<u64>,
+
+    /// Bodies that entered this tick
+    pub added_entities: Vec<u64>,
+
+    /// Bodies that left this tick
+    pub removed_entities: Vec<u64>,
+}
+
+impl SensorOverlap {
+    /// Create an empty overlap list for a body
+    pub fn new(world_id: u64, body_id: u64) -> Self {
+        Self {
+            id: 0,
+            world_id,
+            body_id,
+            entities_inside: Vec::new(),
+            added_entities: Vec::new(),
+            removed_entities: Vec::new(),
+        }
+    }
+
+    /// Insert this overlap list into the database
+    pub fn insert(self, ctx: &ReducerContext) -> Self {
+        ctx.db.rapier_sensor_overlap().insert(self)
+    }
+
+    /// Find an overlap list by ID
+    pub fn find(ctx: &ReducerContext, id: SensorOverlapId) -> Option<Self> {
+        ctx.db.rapier_sensor_overlap().id().find(id)
+    }
+
+    /// Find the overlap list of a body
+    pub fn find_by_body(ctx: &ReducerContext, body_id: u64) -> Option<Self> {
+        ctx.db.rapier_sensor_overlap().body_id().find(body_id)
+    }
+
+    /// Get all overlap lists in a world
+    pub fn all_in_world(ctx: &ReducerContext, world_id: u64) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.rapier_sensor_overlap().world_id().filter(world_id)
+    }
+
+    /// Get all overlap lists in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    /// Update this overlap list in the database
+    pub fn update(self, ctx: &ReducerContext) -> Self {
+        ctx.db.rapier_sensor_overlap().id().update(self)
+    }
+
+    /// Delete this overlap list from the database
+    pub fn delete(&self, ctx: &ReducerContext) {
+        ctx.db.rapier_sensor_overlap().id().delete(self.id);
+    }
+
+    /// Check if a body is inside this sensor
+    pub fn contains(&self, entity_id: u64) -> bool {
+        self.entities_inside.contains(&entity_id)
+    }
+
+    /// Check if a body just entered this sensor
+    pub fn just_entered(&self, entity_id: u64) -> bool {
+        self.added_entities.contains(&entity_id)
+    }
+
+    /// Check if a body just left this sensor
+    pub fn just_left(&self, entity_id: u64) -> bool {
+        self.removed_entities.contains(&entity_id)
+    }
+
+    /// Update entity lists based on current intersection state
+    pub fn update_entities(&mut self, current_inside: Vec<u64>) {
+        self.added_entities = current_inside
+            .iter()
+            .filter(|id| !self.entities_inside.contains(id))
+            .copied()
+            .collect();
+
+        self.removed_entities = self.entities_inside
+            .iter()
+            .filter(|id| !current_inside.contains(id))
+            .copied()
+            .collect();
+
+        self.entities_inside = current_inside;
+    }
+}
@@ -3,6 +3,13 @@
 use bon::Builder;
 use spacetimedb::{table, ReducerContext, Table};
 use crate::math::{Vec3, Quat};
+use crate::tables::{BodyTypeFlags, Collider, RigidBody};
+
+#[cfg(feature = "dim2")]
+use rapier2d::prelude::ActiveCollisionTypes;
+
+#[cfg(feature = "dim3")]
+use rapier3d::prelude::ActiveCollisionTypes;
 
 pub type TriggerId = u64;
 
@@ -42,6 +49,12 @@ pub struct Trigger {
     #[builder(default = 1.0)]
     pub rotation_w: f32,
 
+    /// Rotation around Z in radians, used as is by the 2D backend (2D only)
+    ///
+    /// Skips the lossy quaternion to angle conversion. When `None` the angle
+    /// is derived from the quaternion.
+    pub rotation_angle: Option<f32>,
+
     /// ID of the collider shape for this trigger
     pub collider_id: u64,
 
@@ -60,6 +73,29 @@ pub struct Trigger {
     /// Whether this trigger is currently enabled
     #[builder(default = true)]
     pub enabled: bool,
+
+    /// Body types this trigger detects (see `BodyTypeFlags`)
+    #[builder(default = BodyTypeFlags::ALL)]
+    pub detect_body_types: u8,
+
+    /// If set, only bodies whose `collision_groups` share a bit with this mask are detected
+    pub collision_filter: Option<u32>,
+
+    /// Game-defined data, e.g. the ID of the entity owning this trigger
+    #[builder(default = 0)]
+    pub user_data: u64,
+
+    /// Delete bodies when they leave this trigger, e.g. a play area
+    ///
+    /// Runs at the end of `step_world`. Their collider and properties are
+    /// deleted too unless another body or trigger still uses them.
+    #[builder(default = false)]
+    pub auto_despawn_on_exit: bool,
+
+    /// Game-defined purpose of the trigger (damage zone, pickup, ...), see `all_in_group`
+    #[index(btree)]
+    #[builder(default = 0)]
+    pub group: u32,
 }
 
 impl Default for Trigger {
@@ -74,15 +110,50 @@ impl Default for Trigger {
             rotation_y: 0.0,
             rotation_z: 0.0,
             rotation_w: 1.0,
+            rotation_angle: None,
             collider_id: 0,
             entities_inside: Vec::new(),
             added_entities: Vec::new(),
             removed_entities: Vec::new(),
             enabled: true,
+            detect_body_types: BodyTypeFlags::ALL,
+            collision_filter: None,
+            user_data: 0,
+            auto_despawn_on_exit: false,
+            group: 0,
         }
     }
 }
 
+impl<S: trigger_builder::State> TriggerBuilder<S> {
+    /// Insert a collider and use it as this trigger's shape
+    ///
+    /// Replaces inserting the collider separately and passing `collider_id`.
+    /// The collider should belong to the same world as the trigger.
+    pub fn shape(
+        self,
+        ctx: &ReducerContext,
+        collider: Collider,
+    ) -> TriggerBuilder<trigger_builder::SetColliderId<S>>
+    where
+        S::ColliderId: trigger_builder::IsUnset,
+    {
+        self.shape_with(collider, |collider| collider.insert(ctx).id)
+    }
+
+    /// Same as `shape`, with a custom way to store the collider and get its ID
+    pub fn shape_with(
+        self,
+        collider: Collider,
+        insert: impl FnOnce(Collider) -> u64,
+    ) -> TriggerBuilder<trigger_builder::SetColliderId<S>>
+    where
+        S::ColliderId: trigger_builder::IsUnset,
+    {
+        self.collider_id(insert(collider))
+    }
+}
+
 impl Trigger {
     /// Insert this trigger into the database
     pub fn insert(self, ctx: &ReducerContext) -> Self {
@@ -99,6 +170,60 @@ impl Trigger {
         ctx.db.rapier_trigger().world_id().filter(world_id)
     }
 
+    /// Get all triggers in a world, collected into a Vec
+    pub fn collect_in_world(ctx: &ReducerContext, world_id: u64) -> Vec<Self> {
+        Self::all_in_world(ctx, world_id).collect()
+    }
+
+    /// Get the triggers of a world belonging to a group
+    pub fn all_in_group(ctx: &ReducerContext, world_id: u64, group: u32) -> impl Iterator<Item = Self> + '_ {
+        Self::all_in_group_in(ctx.db.rapier_trigger().group().filter(group), world_id, group)
+    }
+
+    /// Same as `all_in_group`, over already loaded triggers
+    pub fn all_in_group_in(
+        triggers: impl IntoIterator<Item = Self>,
+        world_id: u64,
+        group: u32,
+    ) -> impl Iterator<Item = Self> {
+        triggers
+            .into_iter()
+            .filter(move |trigger| trigger.world_id == world_id && trigger.group == group)
+    }
+
+    /// Enable or disable every trigger in a world, returns how many changed
+    pub fn set_enabled_in_world(ctx: &ReducerContext, world_id: u64, enabled: bool) -> usize {
+        Self::set_enabled_all(ctx, Self::all_in_world(ctx, world_id).collect(), enabled)
+    }
+
+    /// Enable or disable every trigger of a group, returns how many changed
+    ///
+    /// E.g. turn off all damage zones between rounds.
+    pub fn set_enabled_in_group(ctx: &ReducerContext, world_id: u64, group: u32, enabled: bool) -> usize {
+        Self::set_enabled_all(ctx, Self::all_in_group(ctx, world_id, group).collect(), enabled)
+    }
+
+    fn set_enabled_all(ctx: &ReducerContext, triggers: Vec<Self>, enabled: bool) -> usize {
+        Self::set_enabled_all_with(triggers, enabled, |trigger| {
+            trigger.update(ctx);
+        })
+    }
+
+    /// Set `enabled` on every trigger that differs and pass it to `update`, returns how many changed
+    pub fn set_enabled_all_with(
+        triggers: impl IntoIterator<Item = Self>,
+        enabled: bool,
+        mut update: impl FnMut(Self),
+    ) -> usize {
+        let mut changed = 0;
+        for mut trigger in triggers.into_iter().filter(|t| t.enabled != enabled) {
+            trigger.enabled = enabled;
+            update(trigger);
+            changed += 1;
+        }
+        changed
+    }
+
     /// Update this trigger in the database
     pub fn update(self, ctx: &ReducerContext) -> Self {
         ctx.db.rapier_trigger().id().update(self)
@@ -126,12 +251,54 @@ impl Trigger {
         Quat::new(self.rotation_x, self.rotation_y, self.rotation_z, self.rotation_w)
     }
 
-    /// Set rotation from Quat
+    /// Set rotation from Quat, clears `rotation_angle`
     pub fn set_rotation(&mut self, rot: Quat) {
         self.rotation_x = rot.x;
         self.rotation_y = rot.y;
         self.rotation_z = rot.z;
         self.rotation_w = rot.w;
+        self.rotation_angle = None;
+    }
+
+    /// Get the rotation angle around Z (2D)
+    #[cfg(feature = "dim2")]
+    pub fn rotation_angle(&self) -> f32 {
+        self.rotation_angle.unwrap_or_else(|| self.rotation().to_angle_z())
+    }
+
+    /// Set the rotation angle around Z (2D), the quaternion is kept in sync for clients
+    #[cfg(feature = "dim2")]
+    pub fn set_rotation_angle(&mut self, angle: f32) {
+        self.set_rotation(Quat::from_angle_z(angle));
+        self.rotation_angle = Some(angle);
+    }
+
+    /// Check if this trigger should report the given body when overlapping it
+    pub fn detects(&self, body: &RigidBody) -> bool {
+        if self.detect_body_types & body.body_type.flag() == 0 {
+            return false;
+        }
+
+        match self.collision_filter {
+            Some(filter) => body.collision_groups & filter != 0,
+            None => true,
+        }
+    }
+
+    /// Pairs of body types the trigger's sensor checks, from `detect_body_types`
+    ///
+    /// The sensor has no body, so Rapier treats it as static and by default
+    /// would only see dynamic bodies.
+    pub fn collision_types(&self) -> ActiveCollisionTypes {
+        let pairs = [
+            (BodyTypeFlags::DYNAMIC, ActiveCollisionTypes::DYNAMIC_FIXED),
+            (BodyTypeFlags::KINEMATIC, ActiveCollisionTypes::KINEMATIC_FIXED),
+            (BodyTypeFlags::STATIC, ActiveCollisionTypes::FIXED_FIXED),
+        ];
+        pairs
+            .into_iter()
+            .filter(|(flag, _)| self.detect_body_types & flag != 0)
+            .fold(ActiveCollisionTypes::empty(), |types, (_, rapier)| types | rapier)
     }
 
     /// Check if an entity is inside this trigger
@@ -175,3 +342,91 @@ impl Trigger {
         self.entities_inside = current_inside;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::test_utils::TestWorld;
+    use crate::tables::PhysicsWorld;
+
+    #[test]
+    fn shape_builders_attach_a_findable_collider() {
+        let mut world = TestWorld::with_defaults();
+
+        let trigger = Trigger::builder()
+            .shape_with(Collider::ball(1, 2.0), |collider| world.add_collider(collider))
+            .build();
+        let body = RigidBody::builder()
+            .properties_id(0)
+            .shape_with(Collider::cuboid(1, Vec3::new(1.0, 0.5, 1.0)), |collider| world.add_collider(collider))
+            .build();
+
+        let find = |id: u64| world.rows.colliders.iter().find(|collider| collider.id == id);
+        let trigger_collider = find(trigger.collider_id).expect("trigger collider not found");
+        assert_eq!(trigger_collider.radius, 2.0);
+        let body_collider = find(body.collider_id).expect("body collider not found");
+        assert_eq!(body_collider.half_extent_x, 1.0);
+        assert_ne!(trigger.collider_id, body.collider_id);
+    }
+
+    #[test]
+    fn all_in_group_returns_only_the_triggers_of_that_group() {
+        let mut world = TestWorld::with_defaults();
+        let damage = 1;
+        let pickup = 2;
+        let lava = world.add_trigger(Trigger { group: damage, ..Default::default() }, Collider::ball(1, 1.0));
+        world.add_trigger(Trigger { group: pickup, ..Default::default() }, Collider::ball(1, 1.0));
+        let spikes = world.add_trigger(Trigger { group: damage, ..Default::default() }, Collider::ball(1, 1.0));
+        world.add_trigger(Trigger::default(), Collider::ball(1, 1.0));
+        // Same group in another world
+        let other_world = Trigger { id: 999, world_id: world.world.id + 1, group: damage, ..Default::default() };
+        let triggers = world.rows.triggers.iter().cloned().chain([other_world]);
+
+        let ids: Vec<u64> = Trigger::all_in_group_in(triggers, world.world.id, damage)
+            .map(|trigger| trigger.id)
+            .collect();
+        assert_eq!(ids, vec![lava, spikes]);
+    }
+
+    #[test]
+    fn bulk_disabled_triggers_stop_reporting_overlaps() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let near = world.add_trigger(Trigger::default(), Collider::ball(1, 2.0));
+        let far = world.add_trigger(Trigger { position_x: 10.0, ..Default::default() }, Collider::ball(1, 2.0));
+        let set_enabled = |world: &mut TestWorld, enabled: bool| {
+            let triggers = world.rows.triggers.clone();
+            Trigger::set_enabled_all_with(triggers, enabled, |updated| {
+                *world.rows.triggers.iter_mut().find(|t| t.id == updated.id).unwrap() = updated;
+            })
+        };
+
+        assert_eq!(set_enabled(&mut world, false), 2);
+        assert_eq!(set_enabled(&mut world, false), 0);
+        let body = world.add_body(RigidBody::default(), Collider::ball(1, 0.5));
+        world.step();
+        assert!(world.output.triggers.is_empty());
+        assert!(world.trigger(near).entities_inside.is_empty());
+
+        assert_eq!(set_enabled(&mut world, true), 2);
+        world.step();
+        assert_eq!(world.trigger(near).entities_inside, vec![body]);
+        assert!(world.trigger(far).entities_inside.is_empty());
+    }
+
+    #[cfg(feature = "dim2")]
+    #[test]
+    fn trigger_rotated_90_degrees_overlaps_along_its_rotated_extent() {
+        let mut world = TestWorld::new(PhysicsWorld::builder().gravity_y(0.0).build());
+        let mut beam = Trigger::default();
+        beam.set_rotation_angle(std::f32::consts::FRAC_PI_2);
+        assert_eq!(beam.rotation_angle(), std::f32::consts::FRAC_PI_2);
+        // 10 long along X before the rotation, along Y after it
+        let beam = world.add_trigger(beam, Collider::cuboid(1, Vec3::new(5.0, 0.5, 0.5)));
+        let along = world.add_body(RigidBody { position_y: 4.0, ..Default::default() }, Collider::ball(1, 0.25));
+        world.add_body(RigidBody { position_x: 4.0, ..Default::default() }, Collider::ball(1, 0.25));
+
+        world.step();
+
+        assert_eq!(world.trigger(beam).entities_inside, vec![along]);
+    }
+}
@@ -84,8 +84,10 @@ pub fn physics_tick(ctx: &ReducerContext, timer: PhysicsTickTimer) -> Result<(),
             (p.rigid_body_id, (pos, rotation))
         });
 
-    // Step the physics simulation
-    step_world(ctx, &world, kinematic_entities);
+    // Step the physics simulation, a broken world is logged but keeps the game ticking
+    if let Err(err) = step_world(ctx, &world, kinematic_entities) {
+        log::warn!("physics_tick: {}", err);
+    }
 
     // Process game logic
     process_grenades(ctx);
@@ -1,7 +1,7 @@
 // Weapon definitions, shooting, and throwable reducers
 
 use spacetimedb::{reducer, table, Identity, ReducerContext, Table};
-use spacetime_rapier::{Collider, RigidBody, RigidBodyProperties, RigidBodyType, Trigger, RayCast, Vec3};
+use spacetime_rapier::{spawn_projectile, Collider, RigidBody, Trigger, RayCast, Vec3};
 
 use crate::game::apply_damage;
 use crate::player::{player, get_weapon_damage, get_weapon_fire_rate_ms, get_weapon_knockback, WeaponType};
@@ -175,35 +175,12 @@ fn shoot_bazooka(
     let speed = 20.0;
     let velocity = Vec3::new(aim_dir.x * speed, 0.0, aim_dir.z * speed);
     
-    // Create rocket rigid body properties
-    let rb_props = RigidBodyProperties::builder()
-        .world_id(world_id)
-        .mass(0.5)
-        .restitution(0.0) // no bounce
-        .build()
-        .insert(ctx);
-    
-    // Create sphere collider for rocket
-    let collider = Collider::ball(world_id, 0.2).insert(ctx);
-    
-    // Create dynamic rigid body
-    let rocket_rb = RigidBody::builder()
-        .world_id(world_id)
-        .position_x(start_pos.x)
-        .position_y(start_pos.y)
-        .position_z(start_pos.z)
-        .linear_velocity_x(velocity.x)
-        .linear_velocity_y(velocity.y)
-        .linear_velocity_z(velocity.z)
-        .collider_id(collider.id)
-        .properties_id(rb_props.id)
-        .body_type(RigidBodyType::Dynamic)
-        .build()
-        .insert(ctx);
+    // Create rocket rigid body (no bounce)
+    let rocket_rb_id = spawn_projectile(ctx, world_id, start_pos, velocity, 0.2, 0.5, 0.0, true);
     
     // Create projectile entity
     ctx.db.projectile().insert(Projectile {
-        rigid_body_id: rocket_rb.id,
+        rigid_body_id: rocket_rb_id,
         owner_id: player.identity,
         damage: 80.0,
         radius: 5.0,
@@ -284,35 +261,14 @@ pub fn throw_grenade(ctx: &ReducerContext, aim_x: f32, aim_z: f32) -> Result<(),
         player.position_z + aim_dir.z * 0.5,
     );
     
-    // Create grenade rigid body properties (bouncy!)
-    let rb_props = RigidBodyProperties::builder()
-        .world_id(lobby.physics_world_id)
-        .mass(0.3)
-        .restitution(0.5) // bouncy
-        .build()
-        .insert(ctx);
-    
-    // Create sphere collider
-    let collider = Collider::ball(lobby.physics_world_id, 0.15).insert(ctx);
-    
-    // Create dynamic rigid body with initial velocity
-    let grenade_rb = RigidBody::builder()
-        .world_id(lobby.physics_world_id)
-        .position_x(start_pos.x)
-        .position_y(start_pos.y)
-        .position_z(start_pos.z)
-        .linear_velocity_x(velocity.x)
-        .linear_velocity_y(velocity.y)
-        .linear_velocity_z(velocity.z)
-        .collider_id(collider.id)
-        .properties_id(rb_props.id)
-        .body_type(RigidBodyType::Dynamic)
-        .build()
-        .insert(ctx);
+    // Create grenade rigid body with initial velocity (bouncy!)
+    let grenade_rb_id = spawn_projectile(
+        ctx, lobby.physics_world_id, start_pos, velocity, 0.15, 0.3, 0.5, true,
+    );
     
     // Create grenade entity with fuse timer
     ctx.db.grenade().insert(Grenade {
-        rigid_body_id: grenade_rb.id,
+        rigid_body_id: grenade_rb_id,
         owner_id: identity,
         fuse_ticks: 180, // 3 seconds at 60 Hz
         damage: 70.0,
@@ -357,19 +313,16 @@ pub fn throw_molotov(ctx: &ReducerContext, aim_x: f32, aim_z: f32) -> Result<(),
         player.position_z + aim_dir.z * throw_power,
     );
     
-    // Create trigger collider (cuboid for fire zone)
-    let collider = Collider::cuboid(
-        lobby.physics_world_id,
-        Vec3::new(4.0, 1.0, 4.0), // fire zone size (half-extents)
-    ).insert(ctx);
-    
-    // Create trigger for damage zone
+    // Create trigger for damage zone, with a cuboid collider for the fire zone
     let trigger = Trigger::builder()
         .world_id(lobby.physics_world_id)
         .position_x(target_pos.x)
         .position_y(target_pos.y)
         .position_z(target_pos.z)
-        .collider_id(collider.id)
+        .shape(ctx, Collider::cuboid(
+            lobby.physics_world_id,
+            Vec3::new(4.0, 1.0, 4.0), // fire zone size (half-extents)
+        ))
         .build()
         .insert(ctx);
     